@@ -0,0 +1,125 @@
+//! Throughput of the GateBuilder, the MemorySink, the PlaintextBackend evaluator and
+//! the serialization, on circuits of 1M Add gates.
+//!
+//! Run with `cargo bench --bench builder_throughput`.
+//! Criterion reports the throughput in gates per second; divide 1e9 by it to get ns/gate.
+//! Criterion keeps the results of the previous run and reports the changes against them.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use zki_sieve::consumers::evaluator::{Evaluator, PlaintextBackend};
+use zki_sieve::producers::builder::{BuildGate::*, GateBuilder, GateBuilderT};
+use zki_sieve::producers::sink::MemorySink;
+use zki_sieve::structs::directives::Directive;
+use zki_sieve::structs::types::Type;
+use zki_sieve::structs::IR_VERSION;
+use zki_sieve::{Gate, Relation, Source, Value};
+
+const GATE_COUNT: u64 = 1_000_000;
+const INPUT_COUNT: u64 = 1_000;
+const SEED: u64 = 0x5eed;
+
+/// Deterministic pseudo-random private input values (xorshift64 from a fixed seed).
+fn input_values() -> Vec<Value> {
+    let mut state = SEED;
+    (0..INPUT_COUNT)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            vec![(state % 101) as u8]
+        })
+        .collect()
+}
+
+fn types() -> Vec<Type> {
+    vec![Type::Field(vec![101])]
+}
+
+/// Builds a circuit of INPUT_COUNT private inputs followed by GATE_COUNT Add gates,
+/// each one adding the previous output to one of the inputs.
+fn build_circuit(b: &mut GateBuilder<MemorySink>) {
+    let inputs = input_values()
+        .into_iter()
+        .map(|value| b.create_gate(Private(0, Some(value))).unwrap())
+        .collect::<Vec<_>>();
+    let mut acc = inputs[0];
+    for i in 0..GATE_COUNT {
+        acc = b
+            .create_gate(Add(0, acc, inputs[(i % INPUT_COUNT) as usize]))
+            .unwrap();
+    }
+}
+
+fn add_gates_relation() -> Relation {
+    let directives = (0..INPUT_COUNT)
+        .map(|wire| Gate::Private(0, wire))
+        .chain(
+            (INPUT_COUNT..INPUT_COUNT + GATE_COUNT)
+                .map(|wire| Gate::Add(0, wire, wire - 1, wire % INPUT_COUNT)),
+        )
+        .map(Directive::Gate)
+        .collect();
+    Relation {
+        version: IR_VERSION.to_string(),
+        plugins: vec![],
+        types: types(),
+        conversions: vec![],
+        directives,
+    }
+}
+
+fn bench_builder(c: &mut Criterion) {
+    let mut group = c.benchmark_group("builder_throughput");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(GATE_COUNT));
+
+    group.bench_function("build_add_gates", |bencher| {
+        bencher.iter_batched(
+            || GateBuilder::new(MemorySink::default(), &[], &types(), &[]),
+            |mut b| {
+                build_circuit(&mut b);
+                b
+            },
+            BatchSize::PerIteration,
+        )
+    });
+
+    group.bench_function("flush_memory_sink", |bencher| {
+        bencher.iter_batched(
+            || {
+                let mut b = GateBuilder::new(MemorySink::default(), &[], &types(), &[]);
+                // Hold all gates in memory until finish() is called
+                b.set_max_len(2 * GATE_COUNT as usize).unwrap();
+                build_circuit(&mut b);
+                b
+            },
+            |b| b.finish(),
+            BatchSize::PerIteration,
+        )
+    });
+
+    group.bench_function("evaluate_add_gates", |bencher| {
+        let mut b = GateBuilder::new(MemorySink::default(), &[], &types(), &[]);
+        build_circuit(&mut b);
+        let source: Source = b.finish().into();
+        bencher.iter(|| {
+            let evaluator =
+                Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+            assert!(evaluator.get_violations().is_empty());
+        })
+    });
+
+    group.bench_function("serialize_add_gates", |bencher| {
+        let relation = add_gates_relation();
+        bencher.iter(|| {
+            let mut buffer = vec![];
+            relation.write_into(&mut buffer).unwrap();
+            buffer
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_builder);
+criterion_main!(benches);
@@ -0,0 +1,94 @@
+//! Compares the peak memory needed to read the gates of a relation of 1M gates
+//! with `Relation::try_from` (all gates converted at once), with `gates_from_flatbuffer`
+//! and with `Source::iter_gates` (one gate converted at a time).
+//!
+//! Run with `cargo bench --bench gate_reading`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::convert::TryFrom;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use zki_sieve::structs::directives::Directive;
+use zki_sieve::structs::relation::gates_from_flatbuffer;
+use zki_sieve::structs::types::Type;
+use zki_sieve::structs::IR_VERSION;
+use zki_sieve::{Gate, Relation, Source};
+
+const GATE_COUNT: u64 = 1_000_000;
+
+/// An allocator which keeps track of the allocated bytes, and of their high-water mark.
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(allocated, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Runs `f` and prints its duration and the peak memory it allocated on top of the
+/// memory already allocated before the call.
+fn measure(name: &str, f: impl FnOnce() -> u64) {
+    let baseline = ALLOCATED.load(Ordering::SeqCst);
+    PEAK.store(baseline, Ordering::SeqCst);
+    let start = Instant::now();
+    let gate_count = f();
+    let elapsed = start.elapsed();
+    assert_eq!(gate_count, GATE_COUNT);
+    println!(
+        "{:<22} {:>6} ns/gate, {:>11} bytes allocated at peak",
+        name,
+        elapsed.as_nanos() / GATE_COUNT as u128,
+        PEAK.load(Ordering::SeqCst) - baseline
+    );
+}
+
+fn main() {
+    let buffer = {
+        let relation = Relation {
+            version: IR_VERSION.to_string(),
+            plugins: vec![],
+            types: vec![Type::Field(vec![101])],
+            conversions: vec![],
+            directives: std::iter::once(Gate::Constant(0, 0, vec![1]))
+                .chain((1..GATE_COUNT).map(|i| Gate::Add(0, i, i - 1, i - 1)))
+                .map(Directive::Gate)
+                .collect(),
+        };
+        let mut buffer = vec![];
+        relation.write_into(&mut buffer).unwrap();
+        buffer
+    };
+    println!("Relation message of {} bytes", buffer.len());
+
+    measure("Relation::try_from:", || {
+        let relation = Relation::try_from(&buffer[..]).unwrap();
+        relation.directives.len() as u64
+    });
+
+    measure("gates_from_flatbuffer:", || {
+        gates_from_flatbuffer(&buffer)
+            .map(|gate| gate.unwrap())
+            .count() as u64
+    });
+
+    let source = Source::from_buffers(vec![buffer]);
+    measure("Source::iter_gates:", || {
+        source.iter_gates().map(|gate| gate.unwrap()).count() as u64
+    });
+}
@@ -0,0 +1,76 @@
+//! Compares the memory and time needed to serialize a relation of 500k gates
+//! with `Relation::write_into` (all gates in memory) and with `StreamingRelationWriter`.
+//!
+//! Run with `cargo bench --bench relation_writer`.
+
+use std::io::{Result, Write};
+use std::time::Instant;
+use zki_sieve::producers::sink::StreamingRelationWriter;
+use zki_sieve::structs::directives::Directive;
+use zki_sieve::structs::types::Type;
+use zki_sieve::structs::IR_VERSION;
+use zki_sieve::{Gate, Relation};
+
+const GATE_COUNT: u64 = 500_000;
+const DIRECTIVES_PER_MESSAGE: usize = 10_000;
+
+/// A writer which only counts the written bytes.
+#[derive(Default)]
+struct CountingWriter {
+    written: usize,
+}
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        self.written += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+fn gates() -> impl Iterator<Item = Gate> {
+    std::iter::once(Gate::Constant(0, 0, vec![1]))
+        .chain((1..GATE_COUNT).map(|i| Gate::Add(0, i, i - 1, i - 1)))
+}
+
+fn main() {
+    let types = vec![Type::Field(vec![101])];
+
+    let start = Instant::now();
+    let relation = Relation {
+        version: IR_VERSION.to_string(),
+        plugins: vec![],
+        types: types.clone(),
+        conversions: vec![],
+        directives: gates().map(Directive::Gate).collect(),
+    };
+    let mut buffer = vec![];
+    relation.write_into(&mut buffer).unwrap();
+    let elapsed = start.elapsed();
+    println!(
+        "Relation::write_into:     {:>6} ns/gate, {} bytes serialized at once",
+        elapsed.as_nanos() / GATE_COUNT as u128,
+        buffer.len()
+    );
+
+    let start = Instant::now();
+    let mut writer = StreamingRelationWriter::new(
+        CountingWriter::default(),
+        &[],
+        &types,
+        &[],
+        DIRECTIVES_PER_MESSAGE,
+    );
+    writer.push_gates(gates()).unwrap();
+    let counter = writer.finish().unwrap();
+    let elapsed = start.elapsed();
+    println!(
+        "StreamingRelationWriter:  {:>6} ns/gate, {} bytes in messages of at most {} gates",
+        elapsed.as_nanos() / GATE_COUNT as u128,
+        counter.written,
+        DIRECTIVES_PER_MESSAGE
+    );
+}
@@ -142,7 +142,7 @@ fn load_messages(opts: &Options) -> Result<Messages> {
     stream_messages(opts)?.read_all_messages()
 }
 
-fn stream_messages(opts: &Options) -> Result<Source> {
+fn stream_messages(opts: &Options) -> Result<Source<'static>> {
     let mut source = Source::from_dirs_and_files(&opts.paths)?;
     source.print_filenames = true;
     Ok(source)
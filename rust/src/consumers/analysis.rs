@@ -0,0 +1,754 @@
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::structs::count::Count;
+use crate::structs::directives::Directive;
+use crate::structs::function::{Function, FunctionBody};
+use crate::structs::types::Type;
+use crate::structs::value::{value_to_biguint, Value};
+use crate::structs::wirerange::{add_types_to_wire_ranges, WireRange};
+use crate::{Gate, Relation, Result, TypeId, WireId};
+
+/// Returns the names of the functions called by `gates`.
+pub(crate) fn called_functions(gates: &[Gate]) -> BTreeSet<String> {
+    gates
+        .iter()
+        .filter_map(|gate| match gate {
+            Gate::Call(name, _, _) => Some(name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum VisitState {
+    InProgress,
+    Done,
+}
+
+fn visit_call_graph(
+    name: &str,
+    graph: &BTreeMap<String, BTreeSet<String>>,
+    states: &mut BTreeMap<String, VisitState>,
+    stack: &mut Vec<String>,
+    cycles: &mut Vec<Vec<String>>,
+) {
+    states.insert(name.to_string(), VisitState::InProgress);
+    stack.push(name.to_string());
+    if let Some(callees) = graph.get(name) {
+        for callee in callees.iter() {
+            match states.get(callee) {
+                Some(VisitState::InProgress) => {
+                    let start = stack.iter().position(|n| n == callee).unwrap();
+                    let mut cycle = stack[start..].to_vec();
+                    cycle.push(callee.clone());
+                    cycles.push(cycle);
+                }
+                Some(VisitState::Done) => {}
+                None => visit_call_graph(callee, graph, states, stack, cycles),
+            }
+        }
+    }
+    stack.pop();
+    states.insert(name.to_string(), VisitState::Done);
+}
+
+/// Returns all cycles found by a depth-first search of the call graph `graph`
+/// (function name => names of the called functions).
+/// Each cycle starts and ends with the same function name.
+pub(crate) fn call_graph_cycles(graph: &BTreeMap<String, BTreeSet<String>>) -> Vec<Vec<String>> {
+    let mut states = BTreeMap::new();
+    let mut stack = vec![];
+    let mut cycles = vec![];
+    for name in graph.keys() {
+        if !states.contains_key(name) {
+            visit_call_graph(name, graph, &mut states, &mut stack, &mut cycles);
+        }
+    }
+    cycles
+}
+
+pub(crate) fn format_cycle(cycle: &[String]) -> String {
+    format!("Recursive cycle: {}", cycle.join(" → "))
+}
+
+/// Builds the call graph of `functions` and checks that it contains no cycle
+/// (i.e. no function calls itself, directly or through other functions).
+/// Otherwise, it returns the list of detected cycles, e.g. "Recursive cycle: A → B → C → A".
+pub fn detect_recursion(functions: &[Function]) -> std::result::Result<(), Vec<String>> {
+    let graph = functions
+        .iter()
+        .map(|function| {
+            let callees = match &function.body {
+                FunctionBody::Gates(gates) => called_functions(gates),
+                FunctionBody::PluginBody(_) => BTreeSet::new(),
+            };
+            (function.name.clone(), callees)
+        })
+        .collect::<BTreeMap<_, _>>();
+
+    let cycles = call_graph_cycles(&graph);
+    if cycles.is_empty() {
+        Ok(())
+    } else {
+        Err(cycles.iter().map(|cycle| format_cycle(cycle)).collect())
+    }
+}
+
+/// Wires used by a gate, as (type_id, wire_id).
+/// The type of the wires of a Call gate is unknown (None) since it depends on the function.
+#[derive(Default)]
+struct GateWires {
+    inputs: Vec<(Option<TypeId>, WireId)>,
+    outputs: Vec<(Option<TypeId>, WireId)>,
+    allocated: Vec<(Option<TypeId>, WireId)>,
+    deleted: Vec<(Option<TypeId>, WireId)>,
+}
+
+fn typed_range(
+    type_id: TypeId,
+    first_id: WireId,
+    last_id: WireId,
+) -> Vec<(Option<TypeId>, WireId)> {
+    (first_id..=last_id).map(|id| (Some(type_id), id)).collect()
+}
+
+fn untyped_ranges(ranges: &[WireRange]) -> Vec<(Option<TypeId>, WireId)> {
+    ranges
+        .iter()
+        .flat_map(|range| (range.first_id..=range.last_id).map(|id| (None, id)))
+        .collect()
+}
+
+fn gate_wires(gate: &Gate) -> GateWires {
+    let mut wires = GateWires::default();
+    match gate {
+        Gate::Constant(type_id, out, _)
+        | Gate::Public(type_id, out)
+        | Gate::Private(type_id, out) => {
+            wires.outputs.push((Some(*type_id), *out));
+        }
+        Gate::AssertZero(type_id, inp) => wires.inputs.push((Some(*type_id), *inp)),
+        Gate::Copy(type_id, out, inp)
+        | Gate::AddConstant(type_id, out, inp, _)
+        | Gate::MulConstant(type_id, out, inp, _) => {
+            wires.inputs.push((Some(*type_id), *inp));
+            wires.outputs.push((Some(*type_id), *out));
+        }
+        Gate::Add(type_id, out, left, right) | Gate::Mul(type_id, out, left, right) => {
+            wires.inputs.push((Some(*type_id), *left));
+            wires.inputs.push((Some(*type_id), *right));
+            wires.outputs.push((Some(*type_id), *out));
+        }
+        Gate::New(type_id, first_id, last_id) => {
+            wires.allocated = typed_range(*type_id, *first_id, *last_id);
+        }
+        Gate::Delete(type_id, first_id, last_id) => {
+            wires.deleted = typed_range(*type_id, *first_id, *last_id);
+        }
+        Gate::Convert(
+            out_type_id,
+            out_first_id,
+            out_last_id,
+            in_type_id,
+            in_first_id,
+            in_last_id,
+        ) => {
+            wires.inputs = typed_range(*in_type_id, *in_first_id, *in_last_id);
+            wires.outputs = typed_range(*out_type_id, *out_first_id, *out_last_id);
+        }
+        Gate::Call(_, out_ids, in_ids) => {
+            wires.inputs = untyped_ranges(in_ids);
+            wires.outputs = untyped_ranges(out_ids);
+        }
+    }
+    wires
+}
+
+/// Returns the wires set or allocated by `gate`.
+/// The type of the outputs of a Call gate is unknown (None).
+pub(crate) fn gate_output_wires(gate: &Gate) -> Vec<(Option<TypeId>, WireId)> {
+    let wires = gate_wires(gate);
+    wires.outputs.into_iter().chain(wires.allocated).collect()
+}
+
+/// Two wires with the same id may be the same wire if their types are equal or unknown.
+pub(crate) fn same_type(left: Option<TypeId>, right: Option<TypeId>) -> bool {
+    left.is_none() || right.is_none() || left == right
+}
+
+/// Returns a permutation of the indices of `gates` in topological order:
+/// - a gate comes after the gates setting its input wires,
+/// - a gate setting a wire comes after the New gate allocating it,
+/// - a Delete gate comes after all gates setting or using the deleted wires.
+/// Whenever several gates are ready, the one with the lowest index comes first,
+/// so a circuit already in topological order is returned unchanged.
+/// The types of the wires of Call gates are unknown, so they are matched with wires of any type.
+/// It returns an error if the dependencies contain a cycle.
+pub fn topological_sort(gates: &[Gate]) -> Result<Vec<usize>> {
+    let wires = gates.iter().map(gate_wires).collect::<Vec<_>>();
+
+    // wire_id => list of (type_id, gate index)
+    let mut producers: BTreeMap<WireId, Vec<(Option<TypeId>, usize)>> = BTreeMap::new();
+    let mut consumers: BTreeMap<WireId, Vec<(Option<TypeId>, usize)>> = BTreeMap::new();
+    let mut allocators: BTreeMap<WireId, Vec<(Option<TypeId>, usize)>> = BTreeMap::new();
+    for (index, gate_wires) in wires.iter().enumerate() {
+        for (type_id, wire_id) in gate_wires.outputs.iter() {
+            producers
+                .entry(*wire_id)
+                .or_default()
+                .push((*type_id, index));
+        }
+        for (type_id, wire_id) in gate_wires.inputs.iter() {
+            consumers
+                .entry(*wire_id)
+                .or_default()
+                .push((*type_id, index));
+        }
+        for (type_id, wire_id) in gate_wires.allocated.iter() {
+            allocators
+                .entry(*wire_id)
+                .or_default()
+                .push((*type_id, index));
+        }
+    }
+
+    let mut successors = vec![BTreeSet::new(); gates.len()];
+    let mut in_degree = vec![0usize; gates.len()];
+    let mut add_dependencies =
+        |index: usize,
+         wire: &(Option<TypeId>, WireId),
+         map: &BTreeMap<WireId, Vec<(Option<TypeId>, usize)>>| {
+            for (type_id, predecessor) in map.get(&wire.1).into_iter().flatten() {
+                if *predecessor != index
+                    && same_type(*type_id, wire.0)
+                    && successors[*predecessor].insert(index)
+                {
+                    in_degree[index] += 1;
+                }
+            }
+        };
+    for (index, gate_wires) in wires.iter().enumerate() {
+        for wire in gate_wires.inputs.iter() {
+            add_dependencies(index, wire, &producers);
+        }
+        for wire in gate_wires.outputs.iter() {
+            add_dependencies(index, wire, &allocators);
+        }
+        for wire in gate_wires.deleted.iter() {
+            add_dependencies(index, wire, &producers);
+            add_dependencies(index, wire, &consumers);
+        }
+    }
+
+    // Kahn's algorithm
+    let mut ready = (0..gates.len())
+        .filter(|index| in_degree[*index] == 0)
+        .collect::<BTreeSet<_>>();
+    let mut order = Vec::with_capacity(gates.len());
+    while let Some(index) = ready.iter().next().cloned() {
+        ready.remove(&index);
+        order.push(index);
+        for successor in successors[index].iter() {
+            in_degree[*successor] -= 1;
+            if in_degree[*successor] == 0 {
+                ready.insert(*successor);
+            }
+        }
+    }
+
+    if order.len() != gates.len() {
+        return Err(format!(
+            "topological_sort: the circuit contains a cycle ({} gates cannot be ordered).",
+            gates.len() - order.len()
+        )
+        .into());
+    }
+    Ok(order)
+}
+
+/// Returns the name of the gate, its constant (if any) and the list of wires it uses
+/// (outputs first), with their types.
+/// The types of the wires of a Call gate to an unknown function are unknown (None).
+fn describe_gate<'a>(
+    gate: &'a Gate,
+    functions: &BTreeMap<&str, &Function>,
+) -> (&'a str, Option<&'a Value>, Vec<(Option<TypeId>, WireId)>) {
+    let range = |type_id: &TypeId, first_id: &WireId, last_id: &WireId| {
+        (*first_id..=*last_id)
+            .map(|wire_id| (Some(*type_id), wire_id))
+            .collect::<Vec<_>>()
+    };
+    match gate {
+        Gate::Constant(type_id, out, value) => {
+            ("Constant", Some(value), vec![(Some(*type_id), *out)])
+        }
+        Gate::AssertZero(type_id, inp) => ("AssertZero", None, vec![(Some(*type_id), *inp)]),
+        Gate::Copy(type_id, out, inp) => (
+            "Copy",
+            None,
+            vec![(Some(*type_id), *out), (Some(*type_id), *inp)],
+        ),
+        Gate::Add(type_id, out, left, right) => (
+            "Add",
+            None,
+            vec![
+                (Some(*type_id), *out),
+                (Some(*type_id), *left),
+                (Some(*type_id), *right),
+            ],
+        ),
+        Gate::Mul(type_id, out, left, right) => (
+            "Mul",
+            None,
+            vec![
+                (Some(*type_id), *out),
+                (Some(*type_id), *left),
+                (Some(*type_id), *right),
+            ],
+        ),
+        Gate::AddConstant(type_id, out, inp, value) => (
+            "AddConstant",
+            Some(value),
+            vec![(Some(*type_id), *out), (Some(*type_id), *inp)],
+        ),
+        Gate::MulConstant(type_id, out, inp, value) => (
+            "MulConstant",
+            Some(value),
+            vec![(Some(*type_id), *out), (Some(*type_id), *inp)],
+        ),
+        Gate::Public(type_id, out) => ("Public", None, vec![(Some(*type_id), *out)]),
+        Gate::Private(type_id, out) => ("Private", None, vec![(Some(*type_id), *out)]),
+        Gate::New(type_id, first_id, last_id) => ("New", None, range(type_id, first_id, last_id)),
+        Gate::Delete(type_id, first_id, last_id) => {
+            ("Delete", None, range(type_id, first_id, last_id))
+        }
+        Gate::Convert(
+            out_type_id,
+            out_first_id,
+            out_last_id,
+            in_type_id,
+            in_first_id,
+            in_last_id,
+        ) => {
+            let mut wires = range(out_type_id, out_first_id, out_last_id);
+            wires.extend(range(in_type_id, in_first_id, in_last_id));
+            ("Convert", None, wires)
+        }
+        Gate::Call(name, out_ids, in_ids) => {
+            let typed_ranges = functions.get(name.as_str()).and_then(|function| {
+                let mut ranges = add_types_to_wire_ranges(out_ids, &function.output_count).ok()?;
+                ranges.extend(add_types_to_wire_ranges(in_ids, &function.input_count).ok()?);
+                Some(ranges)
+            });
+            let wires = match typed_ranges {
+                Some(ranges) => ranges
+                    .iter()
+                    .flat_map(|range_with_type| {
+                        range(
+                            &range_with_type.type_id,
+                            &range_with_type.first_id,
+                            &range_with_type.last_id,
+                        )
+                    })
+                    .collect(),
+                None => out_ids
+                    .iter()
+                    .chain(in_ids.iter())
+                    .flat_map(|wire_range| {
+                        (wire_range.first_id..=wire_range.last_id).map(|wire_id| (None, wire_id))
+                    })
+                    .collect(),
+            };
+            (name.as_str(), None, wires)
+        }
+    }
+}
+
+/// CanonicalHasher feeds a SHA-256 hasher with an unambiguous serialization of the parts of a
+/// relation: each variable-length item is prefixed with its length.
+struct CanonicalHasher(Sha256);
+
+impl CanonicalHasher {
+    fn u64(&mut self, value: u64) {
+        self.0.update(value.to_le_bytes());
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.u64(bytes.len() as u64);
+        self.0.update(bytes);
+    }
+
+    fn str(&mut self, string: &str) {
+        self.bytes(string.as_bytes());
+    }
+
+    fn strings(&mut self, strings: &[String]) {
+        self.u64(strings.len() as u64);
+        strings.iter().for_each(|string| self.str(string));
+    }
+
+    /// Hashes the number represented by `value` (ignoring its trailing zero bytes).
+    fn value(&mut self, value: &[u8]) {
+        self.bytes(&value_to_biguint(value).to_bytes_le());
+    }
+
+    fn count(&mut self, count: &Count) {
+        self.u64(count.type_id as u64);
+        self.u64(count.count);
+    }
+
+    fn counts(&mut self, counts: &[Count]) {
+        self.u64(counts.len() as u64);
+        counts.iter().for_each(|count| self.count(count));
+    }
+
+    fn count_map(&mut self, counts: &BTreeMap<TypeId, u64>) {
+        self.u64(counts.len() as u64);
+        for (type_id, count) in counts.iter() {
+            self.count(&Count::new(*type_id, *count));
+        }
+    }
+
+    fn type_value(&mut self, type_value: &Type) {
+        match type_value {
+            Type::Field(modulus) => {
+                self.u64(0);
+                self.value(modulus);
+            }
+            Type::PluginType(name, operation, params) => {
+                self.u64(1);
+                self.str(name);
+                self.str(operation);
+                self.strings(params);
+            }
+        }
+    }
+
+    /// Hashes `gates` in topological order (or in their original order if they contain a cycle).
+    /// Each wire is hashed as its de Bruijn index: 0 for the wire which appeared last,
+    /// 1 for the one before, and so on. `known_wires` lists the wires which are already defined
+    /// (e.g. the outputs and inputs of a function), in their order of appearance.
+    fn gates(
+        &mut self,
+        gates: &[Gate],
+        functions: &BTreeMap<&str, &Function>,
+        known_wires: Vec<(Option<TypeId>, WireId)>,
+    ) {
+        let mut levels: BTreeMap<(Option<TypeId>, WireId), u64> = BTreeMap::new();
+        for wire in known_wires {
+            let level = levels.len() as u64;
+            levels.entry(wire).or_insert(level);
+        }
+        let order = topological_sort(gates).unwrap_or_else(|_| (0..gates.len()).collect());
+
+        self.u64(gates.len() as u64);
+        for index in order {
+            let (name, constant, wires) = describe_gate(&gates[index], functions);
+            self.str(name);
+            match constant {
+                Some(value) => {
+                    self.u64(1);
+                    self.value(value);
+                }
+                None => self.u64(0),
+            }
+            self.u64(wires.len() as u64);
+            for wire in wires {
+                match wire.0 {
+                    Some(type_id) => self.u64(type_id as u64),
+                    None => self.u64(u64::MAX),
+                }
+                let depth = levels.len() as u64;
+                match levels.get(&wire) {
+                    Some(level) => {
+                        self.u64(1);
+                        self.u64(depth - 1 - level);
+                    }
+                    None => {
+                        // First appearance of this wire
+                        self.u64(0);
+                        levels.insert(wire, depth);
+                    }
+                }
+            }
+        }
+    }
+
+    fn function(&mut self, function: &Function, functions: &BTreeMap<&str, &Function>) {
+        self.str(&function.name);
+        self.counts(&function.output_count);
+        self.counts(&function.input_count);
+        match &function.body {
+            FunctionBody::Gates(gates) => {
+                // The outputs, then the inputs, are numbered from 0 (for each type).
+                let mut next_ids: BTreeMap<TypeId, WireId> = BTreeMap::new();
+                let mut known_wires = vec![];
+                for count in function
+                    .output_count
+                    .iter()
+                    .chain(function.input_count.iter())
+                {
+                    let next_id = next_ids.entry(count.type_id).or_insert(0);
+                    for wire_id in *next_id..*next_id + count.count {
+                        known_wires.push((Some(count.type_id), wire_id));
+                    }
+                    *next_id += count.count;
+                }
+                self.u64(0);
+                self.gates(gates, functions, known_wires);
+            }
+            FunctionBody::PluginBody(plugin_body) => {
+                self.u64(1);
+                self.str(&plugin_body.name);
+                self.str(&plugin_body.operation);
+                self.strings(&plugin_body.params);
+                self.count_map(&plugin_body.public_count);
+                self.count_map(&plugin_body.private_count);
+            }
+        }
+    }
+}
+
+/// Computes a SHA-256 checksum of the structure of `relation`, which does not depend on the
+/// numbering of the wires nor on the order of function declarations.
+/// The hash is computed over a canonical serialization of the plugins, types and conversions,
+/// then the function declarations sorted by name, then the top-level gates. The gates of the
+/// relation and of each function body are taken in topological order (see `topological_sort`),
+/// and each wire is replaced by its de Bruijn index.
+pub fn circuit_checksum(relation: &Relation) -> [u8; 32] {
+    let mut hasher = CanonicalHasher(Sha256::new());
+    hasher.strings(&relation.plugins);
+    hasher.u64(relation.types.len() as u64);
+    relation
+        .types
+        .iter()
+        .for_each(|type_value| hasher.type_value(type_value));
+    hasher.u64(relation.conversions.len() as u64);
+    for conversion in relation.conversions.iter() {
+        hasher.count(&conversion.output_count);
+        hasher.count(&conversion.input_count);
+    }
+
+    let mut functions = BTreeMap::new();
+    let mut gates = vec![];
+    for directive in relation.directives.iter() {
+        match directive {
+            Directive::Function(function) => {
+                functions.insert(function.name.as_str(), function);
+            }
+            Directive::Gate(gate) => gates.push(gate.clone()),
+        }
+    }
+    hasher.u64(functions.len() as u64);
+    for function in functions.values() {
+        hasher.function(function, &functions);
+    }
+
+    hasher.gates(&gates, &functions, vec![]);
+    hasher.0.finalize().into()
+}
+
+#[test]
+fn test_detect_recursion() {
+    use crate::producers::examples::example_relation;
+
+    let function_calling = |name: &str, callee: &str| {
+        Function::new(
+            name.to_string(),
+            vec![Count::new(0, 1)],
+            vec![Count::new(0, 1)],
+            FunctionBody::Gates(vec![Gate::Call(
+                callee.to_string(),
+                vec![WireRange::new(0, 0)],
+                vec![WireRange::new(1, 1)],
+            )]),
+        )
+    };
+
+    // Functions of the example are not recursive
+    let functions = example_relation()
+        .directives
+        .into_iter()
+        .filter_map(|directive| match directive {
+            Directive::Function(function) => Some(function),
+            Directive::Gate(_) => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(detect_recursion(&functions), Ok(()));
+
+    // Direct recursion
+    assert_eq!(
+        detect_recursion(&[function_calling("A", "A")]),
+        Err(vec!["Recursive cycle: A → A".to_string()])
+    );
+
+    // Mutual recursion between three functions
+    assert_eq!(
+        detect_recursion(&[
+            function_calling("A", "B"),
+            function_calling("B", "C"),
+            function_calling("C", "A"),
+        ]),
+        Err(vec!["Recursive cycle: A → B → C → A".to_string()])
+    );
+}
+
+#[test]
+fn test_topological_sort() -> Result<()> {
+    use crate::producers::examples::example_relation;
+    use crate::Gate::*;
+
+    // Checks that each gate comes after the gates setting its input wires.
+    let check_data_flow = |gates: &[Gate], order: &[usize]| {
+        let mut sorted = order.to_vec();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..gates.len()).collect::<Vec<_>>());
+        for (position, index) in order.iter().enumerate() {
+            for (type_id, wire_id) in gate_wires(&gates[*index]).inputs {
+                let set_before = order[..position].iter().any(|other| {
+                    gate_wires(&gates[*other])
+                        .outputs
+                        .iter()
+                        .any(|(t, w)| *w == wire_id && same_type(*t, type_id))
+                });
+                assert!(set_before);
+            }
+        }
+    };
+
+    let gates = example_relation()
+        .directives
+        .into_iter()
+        .filter_map(|directive| match directive {
+            Directive::Gate(gate) => Some(gate),
+            Directive::Function(_) => None,
+        })
+        .collect::<Vec<_>>();
+    let order = topological_sort(&gates)?;
+    check_data_flow(&gates, &order);
+
+    // Gates in reverse data-flow order
+    let gates = vec![
+        AssertZero(0, 2),
+        Add(0, 2, 0, 1),
+        Delete(0, 0, 2),
+        Constant(0, 1, vec![3]),
+        Constant(0, 0, vec![4]),
+    ];
+    let order = topological_sort(&gates)?;
+    assert_eq!(order, vec![3, 4, 1, 0, 2]);
+    check_data_flow(&gates, &order);
+
+    // Cycle
+    let gates = vec![Add(0, 0, 1, 1), Add(0, 1, 0, 0)];
+    assert!(topological_sort(&gates).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_circuit_checksum() {
+    use crate::producers::examples::example_relation;
+    use crate::structs::IR_VERSION;
+    use crate::Gate::*;
+
+    let relation_with_functions = |functions: Vec<Function>, gates: Vec<Gate>| Relation {
+        version: IR_VERSION.to_string(),
+        plugins: vec![],
+        types: vec![Type::Field(vec![101])],
+        conversions: vec![],
+        directives: functions
+            .into_iter()
+            .map(Directive::Function)
+            .chain(gates.into_iter().map(Directive::Gate))
+            .collect(),
+    };
+    let relation = |gates: Vec<Gate>| relation_with_functions(vec![], gates);
+
+    let checksum = circuit_checksum(&relation(vec![
+        Private(0, 0),
+        Private(0, 1),
+        Mul(0, 2, 0, 1),
+        AddConstant(0, 3, 2, vec![100]),
+        AssertZero(0, 3),
+    ]));
+
+    // Same circuit with another wire numbering
+    let renumbered = circuit_checksum(&relation(vec![
+        Private(0, 10),
+        Private(0, 5),
+        Mul(0, 7, 10, 5),
+        AddConstant(0, 2, 7, vec![100]),
+        AssertZero(0, 2),
+    ]));
+    assert_eq!(checksum, renumbered);
+
+    // Different circuit
+    let different = circuit_checksum(&relation(vec![
+        Private(0, 0),
+        Private(0, 1),
+        Add(0, 2, 0, 1),
+        AddConstant(0, 3, 2, vec![100]),
+        AssertZero(0, 3),
+    ]));
+    assert_ne!(checksum, different);
+
+    // Wires are also normalized inside function bodies
+    let square_plus_one = |tmp_wire: WireId| {
+        Function::new(
+            "square_plus_one".to_string(),
+            vec![Count::new(0, 1)],
+            vec![Count::new(0, 1)],
+            FunctionBody::Gates(vec![
+                Mul(0, tmp_wire, 1, 1),
+                AddConstant(0, 0, tmp_wire, vec![1]),
+            ]),
+        )
+    };
+    let call = vec![
+        Private(0, 0),
+        Call(
+            "square_plus_one".to_string(),
+            vec![WireRange::new(1, 1)],
+            vec![WireRange::new(0, 0)],
+        ),
+        AssertZero(0, 1),
+    ];
+    assert_eq!(
+        circuit_checksum(&relation_with_functions(
+            vec![square_plus_one(2)],
+            call.clone()
+        )),
+        circuit_checksum(&relation_with_functions(
+            vec![square_plus_one(8)],
+            call.clone()
+        ))
+    );
+    // but the outputs and inputs of a function cannot be renumbered
+    let square_plus_one_input = Function::new(
+        "square_plus_one".to_string(),
+        vec![Count::new(0, 1)],
+        vec![Count::new(0, 1)],
+        FunctionBody::Gates(vec![Mul(0, 2, 1, 1), AddConstant(0, 1, 2, vec![1])]),
+    );
+    assert_ne!(
+        circuit_checksum(&relation_with_functions(
+            vec![square_plus_one(2)],
+            call.clone()
+        )),
+        circuit_checksum(&relation_with_functions(
+            vec![square_plus_one_input],
+            call.clone()
+        ))
+    );
+
+    // Functions and Call gates are supported, even when the relation is invalid
+    assert_eq!(
+        circuit_checksum(&example_relation()),
+        circuit_checksum(&example_relation())
+    );
+    assert_ne!(
+        circuit_checksum(&relation(call.clone())),
+        circuit_checksum(&relation_with_functions(vec![square_plus_one(2)], call))
+    );
+}
@@ -1,3 +1,4 @@
+use crate::consumers::source::Source;
 use crate::plugins::evaluate_plugin::evaluate_plugin_for_plaintext_backend;
 use crate::structs::count::Count;
 use crate::structs::directives::Directive;
@@ -31,6 +32,9 @@ pub trait ZKBackend {
     /// it returns an Err.
     fn from_bytes_le(val: &[u8]) -> Result<Self::TypeElement>;
     /// Set the underlying type of the running backend.
+    /// It is called with the types declared so far, after each relation message: the types
+    /// already set are kept, and the following ones (if any) have been declared since the
+    /// previous call.
     /// If the type is not compatible with this ZKBackend, then it should return Err
     fn set_types(&mut self, types: &[Type]) -> Result<()>;
 
@@ -154,7 +158,10 @@ pub struct Evaluator<B: ZKBackend> {
     // name => (body, output_count, input_count)
     known_functions: BTreeMap<String, FunctionDeclaration>,
 
-    found_error: Option<String>,
+    // (index of the failing gate in its Relation message, error message)
+    violations: Vec<(usize, String)>,
+    // Index of the gate being (or last) evaluated in the current Relation message
+    current_gate_index: usize,
 }
 
 impl<B: ZKBackend> Default for Evaluator<B> {
@@ -163,7 +170,8 @@ impl<B: ZKBackend> Default for Evaluator<B> {
             values: Default::default(),
             known_functions: Default::default(),
             inputs: Default::default(),
-            found_error: None,
+            violations: vec![],
+            current_gate_index: 0,
         }
     }
 }
@@ -172,6 +180,8 @@ pub struct EvaluatorInputs<B: ZKBackend> {
     types: Vec<Type>,
     public_inputs_queue: BTreeMap<Type, VecDeque<B::TypeElement>>,
     private_inputs_queue: BTreeMap<Type, VecDeque<B::TypeElement>>,
+    // Whether private inputs may be missing (i.e. the circuit is evaluated by a verifier)
+    verifier_mode: bool,
 }
 
 impl<B: ZKBackend> Default for EvaluatorInputs<B> {
@@ -180,6 +190,7 @@ impl<B: ZKBackend> Default for EvaluatorInputs<B> {
             types: vec![],
             public_inputs_queue: Default::default(),
             private_inputs_queue: Default::default(),
+            verifier_mode: false,
         }
     }
 }
@@ -197,23 +208,29 @@ impl<B: ZKBackend> Evaluator<B> {
     /// Returns the list of violations detected when evaluating the IR circuit.
     /// It consumes `self`.
     pub fn get_violations(self) -> Vec<String> {
-        let mut violations = vec![];
-        if let Some(err) = self.found_error {
-            violations.push(err);
-        }
-        violations
+        self.violations.into_iter().map(|(_, err)| err).collect()
+    }
+
+    /// Returns the violations, each one with the 0-based index of the gate causing it in the
+    /// directives of its Relation message.
+    /// A violation which is not caused by a gate (e.g. an invalid input message) gets the index
+    /// of the last gate evaluated (0 if none).
+    pub fn get_violations_with_gate_indices(&self) -> Vec<(usize, String)> {
+        self.violations.clone()
     }
 
     /// Ingests a `Message` using the ZKBackend given in parameter.
     /// If a error was found in previous Messages, then it does nothing but returns,
     /// otherwise it ingests the message.
     pub fn ingest_message(&mut self, msg: &Message, backend: &mut B) {
-        if self.found_error.is_some() {
+        if !self.violations.is_empty() {
             return;
         }
 
         match self.ingest_message_(msg, backend) {
-            Err(err) => self.found_error = Some(err.to_string()),
+            Err(err) => self
+                .violations
+                .push((self.current_gate_index, err.to_string())),
             Ok(()) => {}
         }
     }
@@ -266,14 +283,14 @@ impl<B: ZKBackend> Evaluator<B> {
 
     /// Ingest a `Relation` message
     pub fn ingest_relation(&mut self, relation: &Relation, backend: &mut B) -> Result<()> {
-        if self.inputs.types.is_empty() {
-            relation.types.iter().for_each(|type_value| {
-                self.inputs.types.push(type_value.cleaned_type());
-            });
-        }
-        backend.set_types(&relation.types)?;
+        self.current_gate_index = 0;
+        // The types of a relation are appended to those of the previous relations
+        relation.types.iter().for_each(|type_value| {
+            self.inputs.types.push(type_value.cleaned_type());
+        });
+        backend.set_types(&self.inputs.types)?;
 
-        for directive in relation.directives.iter() {
+        for (index, directive) in relation.directives.iter().enumerate() {
             match directive {
                 Directive::Function(function) => {
                     self.known_functions.insert(
@@ -286,6 +303,7 @@ impl<B: ZKBackend> Evaluator<B> {
                     );
                 }
                 Directive::Gate(gate) => {
+                    self.current_gate_index = index;
                     Self::ingest_gate(
                         gate,
                         backend,
@@ -296,7 +314,6 @@ impl<B: ZKBackend> Evaluator<B> {
                 }
             };
         }
-
         Ok(())
     }
 
@@ -548,9 +565,15 @@ impl<B: ZKBackend> Evaluator<B> {
                             .private_count
                             .iter()
                             .try_for_each::<_, Result<()>>(|(type_id, count)| {
-                                let values =
-                                    Self::get_input_values(inputs, type_id, *count, false)?;
-                                private_inputs.insert(*type_id, values);
+                                // Private values are missing for the verifier.
+                                // In that case, the backend receives no private inputs for this type.
+                                match Self::get_input_values(inputs, type_id, *count, false) {
+                                    Ok(values) => {
+                                        private_inputs.insert(*type_id, values);
+                                    }
+                                    Err(_) if inputs.verifier_mode => {}
+                                    Err(err) => return Err(err),
+                                }
                                 Ok(())
                             })?;
 
@@ -736,7 +759,7 @@ fn remove<I: ZKBackend>(
 /// can even be slower than a secure backend if the evaluated circuit contains a lot of such
 /// operations.
 /// Currently, this backend does not support 'verifier' mode, and requires private inputs to be provided.
-#[derive(Default)]
+#[derive(Clone, Default)]
 pub struct PlaintextBackend {
     pub types: Vec<PlaintextType>,
 }
@@ -758,8 +781,8 @@ impl ZKBackend for PlaintextBackend {
     }
 
     fn set_types(&mut self, types: &[Type]) -> Result<()> {
-        if self.types.is_empty() {
-            types
+        if self.types.len() < types.len() {
+            types[self.types.len()..]
                 .iter()
                 .try_for_each::<_, Result<()>>(|type_value| match type_value {
                     Type::Field(modulo) => {
@@ -943,6 +966,562 @@ impl PlaintextBackend {
     }
 }
 
+/// VerifierBackend evaluates a circuit in plaintext without private inputs.
+/// A wire is `None` if its value depends on a private input, and `Some(value)` otherwise.
+/// `AssertZero` gates on known wires are checked, and failures are recorded in `violations`
+/// instead of stopping the evaluation.
+#[derive(Default)]
+struct VerifierBackend {
+    plaintext: PlaintextBackend,
+    violations: Vec<String>,
+}
+
+impl VerifierBackend {
+    fn binary_op(
+        a: &Option<BigUint>,
+        b: &Option<BigUint>,
+        op: impl FnOnce(&BigUint, &BigUint) -> Result<BigUint>,
+    ) -> Result<Option<BigUint>> {
+        match (a, b) {
+            (Some(a), Some(b)) => Ok(Some(op(a, b)?)),
+            _ => Ok(None),
+        }
+    }
+}
+
+impl ZKBackend for VerifierBackend {
+    type Wire = Option<BigUint>;
+    type TypeElement = BigUint;
+
+    fn from_bytes_le(val: &[u8]) -> Result<Self::TypeElement> {
+        PlaintextBackend::from_bytes_le(val)
+    }
+
+    fn set_types(&mut self, types: &[Type]) -> Result<()> {
+        self.plaintext.set_types(types)
+    }
+
+    fn one(&self) -> Result<Self::TypeElement> {
+        self.plaintext.one()
+    }
+
+    fn minus_one(&self, type_id: &TypeId) -> Result<Self::TypeElement> {
+        self.plaintext.minus_one(type_id)
+    }
+
+    fn zero(&self) -> Result<Self::TypeElement> {
+        self.plaintext.zero()
+    }
+
+    fn copy(&mut self, _type_id: &TypeId, wire: &Self::Wire) -> Result<Self::Wire> {
+        Ok(wire.clone())
+    }
+
+    fn constant(&mut self, type_id: &TypeId, val: Self::TypeElement) -> Result<Self::Wire> {
+        Ok(Some(self.plaintext.constant(type_id, val)?))
+    }
+
+    fn assert_zero(&mut self, type_id: &TypeId, wire: &Self::Wire) -> Result<()> {
+        if let Some(value) = wire {
+            if self.plaintext.assert_zero(type_id, value).is_err() {
+                self.violations.push(format!(
+                    "AssertZero on a public wire of type {} failed (value {}).",
+                    type_id, value
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn add(&mut self, type_id: &TypeId, a: &Self::Wire, b: &Self::Wire) -> Result<Self::Wire> {
+        let plaintext = &mut self.plaintext;
+        Self::binary_op(a, b, |a, b| plaintext.add(type_id, a, b))
+    }
+
+    fn multiply(&mut self, type_id: &TypeId, a: &Self::Wire, b: &Self::Wire) -> Result<Self::Wire> {
+        // A product with a known zero is known, even if the other factor is not.
+        if matches!(a, Some(a) if a.is_zero()) || matches!(b, Some(b) if b.is_zero()) {
+            return Ok(Some(BigUint::zero()));
+        }
+        let plaintext = &mut self.plaintext;
+        Self::binary_op(a, b, |a, b| plaintext.multiply(type_id, a, b))
+    }
+
+    fn add_constant(
+        &mut self,
+        type_id: &TypeId,
+        a: &Self::Wire,
+        b: Self::TypeElement,
+    ) -> Result<Self::Wire> {
+        match a {
+            Some(a) => Ok(Some(self.plaintext.add_constant(type_id, a, b)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn mul_constant(
+        &mut self,
+        type_id: &TypeId,
+        a: &Self::Wire,
+        b: Self::TypeElement,
+    ) -> Result<Self::Wire> {
+        match a {
+            Some(a) => Ok(Some(self.plaintext.mul_constant(type_id, a, b)?)),
+            None => Ok(None),
+        }
+    }
+
+    fn public_input(&mut self, type_id: &TypeId, val: Self::TypeElement) -> Result<Self::Wire> {
+        Ok(Some(self.plaintext.public_input(type_id, val)?))
+    }
+
+    fn private_input(
+        &mut self,
+        _type_id: &TypeId,
+        _val: Option<Self::TypeElement>,
+    ) -> Result<Self::Wire> {
+        // Private inputs are symbolic unknowns, even if a value is provided.
+        Ok(None)
+    }
+
+    fn gate_new(&mut self, _: &TypeId, _: WireId, _: WireId) -> Result<()> {
+        Ok(())
+    }
+
+    fn convert(
+        &mut self,
+        output_type: &TypeId,
+        output_wire_count: u64,
+        input_type: &TypeId,
+        inputs: &[&Self::Wire],
+    ) -> Result<Vec<Self::Wire>> {
+        match inputs
+            .iter()
+            .map(|wire| wire.as_ref())
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(inputs) => Ok(self
+                .plaintext
+                .convert(output_type, output_wire_count, input_type, &inputs)?
+                .into_iter()
+                .map(Some)
+                .collect()),
+            None => Ok(vec![None; usize::try_from(output_wire_count)?]),
+        }
+    }
+
+    fn evaluate_plugin(
+        &mut self,
+        output_count: &[Count],
+        input_count: &[Count],
+        inputs: &[&Self::Wire],
+        public_inputs: &BTreeMap<TypeId, Vec<Self::TypeElement>>,
+        _private_inputs: &BTreeMap<TypeId, Vec<Self::TypeElement>>,
+        plugin_body: &PluginBody,
+    ) -> Result<Vec<Self::Wire>> {
+        let known_inputs = inputs
+            .iter()
+            .map(|wire| wire.as_ref())
+            .collect::<Option<Vec<_>>>();
+        match known_inputs {
+            // The plugin can only be evaluated if it does not depend on any private value.
+            Some(known_inputs) if plugin_body.private_count.values().all(|count| *count == 0) => {
+                Ok(self
+                    .plaintext
+                    .evaluate_plugin(
+                        output_count,
+                        input_count,
+                        &known_inputs,
+                        public_inputs,
+                        &BTreeMap::new(),
+                        plugin_body,
+                    )?
+                    .into_iter()
+                    .map(Some)
+                    .collect())
+            }
+            _ => {
+                let count = output_count.iter().map(|count| count.count).sum::<u64>();
+                Ok(vec![None; usize::try_from(count)?])
+            }
+        }
+    }
+}
+
+/// evaluate_as_verifier evaluates all messages from `source` except the private inputs.
+/// Wires depending on private inputs are unknown, and only the `AssertZero` gates on wires
+/// computable from the public inputs are checked.
+/// It returns the list of violations (empty if no checkable assertion failed).
+pub fn evaluate_as_verifier(source: &Source) -> Result<Vec<String>> {
+    let mut backend = VerifierBackend::default();
+    let mut evaluator = Evaluator::default();
+    evaluator.inputs.verifier_mode = true;
+    for msg in source.iter_messages() {
+        match msg? {
+            Message::PrivateInputs(_) => {}
+            msg => evaluator.ingest_message(&msg, &mut backend),
+        }
+    }
+    let mut violations = backend.violations;
+    violations.extend(evaluator.get_violations());
+    Ok(violations)
+}
+
+/// A gate of a relation together with the public/private inputs it will consume.
+/// `index` is the position of the gate in the relation.
+struct ParallelNode<'a> {
+    index: usize,
+    gate: &'a Gate,
+    input_wires: Vec<(TypeId, WireId)>,
+    inputs: EvaluatorInputs<PlaintextBackend>,
+}
+
+/// ParallelEvaluator evaluates a circuit in plaintext (as `Evaluator<PlaintextBackend>`)
+/// by partitioning the gates of each relation into levels.
+/// A level contains all gates whose inputs were produced by earlier levels,
+/// and all gates of a level are evaluated in parallel using rayon.
+/// Public/private inputs are assigned to each gate in the order of the relation before evaluation.
+///
+/// # Example
+/// ```
+/// use zki_sieve::consumers::evaluator::ParallelEvaluator;
+/// use zki_sieve::producers::simple_examples::*;
+///
+/// let mut evaluator = ParallelEvaluator::default();
+/// let _ = evaluator.ingest_public_inputs(&simple_example_public_inputs());
+/// let _ = evaluator.ingest_private_inputs(&simple_example_private_inputs());
+/// let _ = evaluator.ingest_relation(&simple_example_relation());
+/// assert_eq!(evaluator.get_violations(), Vec::<String>::new());
+/// ```
+#[derive(Default)]
+pub struct ParallelEvaluator {
+    evaluator: Evaluator<PlaintextBackend>,
+    backend: PlaintextBackend,
+}
+
+impl ParallelEvaluator {
+    /// Creates a ParallelEvaluator for an iterator over `Messages`
+    pub fn from_messages(messages: impl Iterator<Item = Result<Message>>) -> Self {
+        let mut evaluator = ParallelEvaluator::default();
+        messages.for_each(|msg| evaluator.ingest_message(&msg.unwrap()));
+        evaluator
+    }
+
+    /// Returns the list of violations detected when evaluating the IR circuit.
+    /// It consumes `self`.
+    pub fn get_violations(self) -> Vec<String> {
+        self.evaluator.get_violations()
+    }
+
+    /// See `Evaluator::get_violations_with_gate_indices`.
+    pub fn get_violations_with_gate_indices(&self) -> Vec<(usize, String)> {
+        self.evaluator.get_violations_with_gate_indices()
+    }
+
+    /// Ingests a `Message`.
+    /// If a error was found in previous Messages, then it does nothing but returns,
+    /// otherwise it ingests the message.
+    pub fn ingest_message(&mut self, msg: &Message) {
+        if !self.evaluator.violations.is_empty() {
+            return;
+        }
+
+        let result = match msg {
+            Message::PublicInputs(i) => self.ingest_public_inputs(i),
+            Message::PrivateInputs(w) => self.ingest_private_inputs(w),
+            Message::Relation(r) => self.ingest_relation(r),
+        };
+        if let Err(err) = result {
+            let index = self.evaluator.current_gate_index;
+            self.evaluator.violations.push((index, err.to_string()));
+        }
+    }
+
+    pub fn ingest_public_inputs(&mut self, public_inputs: &PublicInputs) -> Result<()> {
+        self.evaluator.ingest_public_inputs(public_inputs)
+    }
+
+    pub fn ingest_private_inputs(&mut self, private_inputs: &PrivateInputs) -> Result<()> {
+        self.evaluator.ingest_private_inputs(private_inputs)
+    }
+
+    /// Ingest a `Relation` message.
+    /// If several gates fail, the returned error is the one of the first failing gate
+    /// in the order of the relation.
+    pub fn ingest_relation(&mut self, relation: &Relation) -> Result<()> {
+        use rayon::prelude::*;
+
+        let evaluator = &mut self.evaluator;
+        evaluator.current_gate_index = 0;
+        relation.types.iter().for_each(|type_value| {
+            evaluator.inputs.types.push(type_value.cleaned_type());
+        });
+        self.backend.set_types(&evaluator.inputs.types)?;
+
+        // Partition the gates into levels.
+        let mut levels: Vec<Vec<ParallelNode>> = vec![];
+        let mut wire_levels: BTreeMap<(TypeId, WireId), usize> = BTreeMap::new();
+        let mut deletes: Vec<(usize, &Gate)> = vec![];
+        for (index, directive) in relation.directives.iter().enumerate() {
+            let gate = match directive {
+                Directive::Function(function) => {
+                    evaluator.known_functions.insert(
+                        function.name.clone(),
+                        FunctionDeclaration {
+                            body: function.body.clone(),
+                            output_count: function.output_count.clone(),
+                            input_count: function.input_count.clone(),
+                        },
+                    );
+                    continue;
+                }
+                Directive::Gate(gate) => gate,
+            };
+            // Wires are removed once all levels have been evaluated.
+            if let Gate::Delete(_, _, _) = gate {
+                deletes.push((index, gate));
+                continue;
+            }
+            evaluator.current_gate_index = index;
+
+            let (input_wires, output_wires) = Self::gate_wires(gate, &evaluator.known_functions)?;
+            let (public_count, private_count) =
+                Self::gate_input_counts(gate, &evaluator.known_functions)?;
+            let inputs = Self::take_inputs(&mut evaluator.inputs, &public_count, &private_count);
+
+            let level = input_wires
+                .iter()
+                .map(|wire| wire_levels.get(wire).map(|level| level + 1).unwrap_or(0))
+                .max()
+                .unwrap_or(0);
+            output_wires.into_iter().for_each(|wire| {
+                wire_levels.insert(wire, level);
+            });
+            if levels.len() <= level {
+                levels.resize_with(level + 1, Vec::new);
+            }
+            levels[level].push(ParallelNode {
+                index,
+                gate,
+                input_wires,
+                inputs,
+            });
+        }
+
+        // Evaluate each level in parallel.
+        let mut errors: Vec<(usize, String)> = vec![];
+        for level in levels {
+            let values = &evaluator.values;
+            let known_functions = &evaluator.known_functions;
+            let backend = &self.backend;
+            let results = level
+                .into_par_iter()
+                .map(|mut node| {
+                    let result = Self::evaluate_node(&mut node, backend, values, known_functions)
+                        .map_err(|err| err.to_string());
+                    (node.index, result)
+                })
+                .collect::<Vec<_>>();
+
+            for (index, result) in results {
+                let outputs = match result {
+                    Ok(outputs) => outputs,
+                    Err(err) => {
+                        errors.push((index, err));
+                        continue;
+                    }
+                };
+                for ((type_id, wire_id), value) in outputs {
+                    if let Err(err) =
+                        set::<PlaintextBackend>(&mut evaluator.values, type_id, wire_id, value)
+                    {
+                        errors.push((index, err.to_string()));
+                    }
+                }
+            }
+        }
+
+        if let Some((index, err)) = errors.into_iter().min_by_key(|(index, _)| *index) {
+            evaluator.current_gate_index = index;
+            return Err(err.into());
+        }
+
+        for (index, gate) in deletes {
+            evaluator.current_gate_index = index;
+            Evaluator::ingest_gate(
+                gate,
+                &mut self.backend,
+                &mut evaluator.values,
+                &evaluator.known_functions,
+                &mut evaluator.inputs,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Returns the value of a given wire if it has *NOT* been deleted yet,
+    /// otherwise it will return an Err.
+    pub fn get(&self, type_id: TypeId, wire_id: WireId) -> Result<&BigUint> {
+        self.evaluator.get(type_id, wire_id)
+    }
+
+    /// Evaluates the gate of `node` in a local scope containing only its input wires.
+    /// Returns the values of its output wires.
+    fn evaluate_node(
+        node: &mut ParallelNode,
+        backend: &PlaintextBackend,
+        values: &BTreeMap<(TypeId, WireId), BigUint>,
+        known_functions: &BTreeMap<String, FunctionDeclaration>,
+    ) -> Result<BTreeMap<(TypeId, WireId), BigUint>> {
+        let mut local_backend = backend.clone();
+        let mut scope = BTreeMap::new();
+        for (type_id, wire_id) in node.input_wires.iter() {
+            let value = get::<PlaintextBackend>(values, *type_id, *wire_id)?;
+            scope.insert((*type_id, *wire_id), value.clone());
+        }
+        Evaluator::ingest_gate(
+            node.gate,
+            &mut local_backend,
+            &mut scope,
+            known_functions,
+            &mut node.inputs,
+        )?;
+        for wire in node.input_wires.iter() {
+            scope.remove(wire);
+        }
+        Ok(scope)
+    }
+
+    /// Returns the list of input wires and the list of output wires of `gate`.
+    fn gate_wires(
+        gate: &Gate,
+        known_functions: &BTreeMap<String, FunctionDeclaration>,
+    ) -> Result<(Vec<(TypeId, WireId)>, Vec<(TypeId, WireId)>)> {
+        use Gate::*;
+
+        let flatten = |ranges: &[WireRangeWithType]| {
+            ranges
+                .iter()
+                .flat_map(|range| {
+                    (range.first_id..=range.last_id).map(move |wire_id| (range.type_id, wire_id))
+                })
+                .collect::<Vec<_>>()
+        };
+        Ok(match gate {
+            Constant(type_id, out, _) | Public(type_id, out) | Private(type_id, out) => {
+                (vec![], vec![(*type_id, *out)])
+            }
+            AssertZero(type_id, inp) => (vec![(*type_id, *inp)], vec![]),
+            Copy(type_id, out, inp)
+            | AddConstant(type_id, out, inp, _)
+            | MulConstant(type_id, out, inp, _) => (vec![(*type_id, *inp)], vec![(*type_id, *out)]),
+            Add(type_id, out, left, right) | Mul(type_id, out, left, right) => (
+                vec![(*type_id, *left), (*type_id, *right)],
+                vec![(*type_id, *out)],
+            ),
+            New(_, _, _) | Delete(_, _, _) => (vec![], vec![]),
+            Convert(out_type_id, out_first, out_last, in_type_id, in_first, in_last) => (
+                (*in_first..=*in_last)
+                    .map(|wire_id| (*in_type_id, wire_id))
+                    .collect(),
+                (*out_first..=*out_last)
+                    .map(|wire_id| (*out_type_id, wire_id))
+                    .collect(),
+            ),
+            Call(name, out_ids, in_ids) => {
+                let function = known_functions.get(name).ok_or("Unknown function")?;
+                (
+                    flatten(&add_types_to_wire_ranges(in_ids, &function.input_count)?),
+                    flatten(&add_types_to_wire_ranges(out_ids, &function.output_count)?),
+                )
+            }
+        })
+    }
+
+    /// Returns the number of public and private inputs (per type) consumed by `gate`.
+    fn gate_input_counts(
+        gate: &Gate,
+        known_functions: &BTreeMap<String, FunctionDeclaration>,
+    ) -> Result<(BTreeMap<TypeId, u64>, BTreeMap<TypeId, u64>)> {
+        let mut public_count = BTreeMap::new();
+        let mut private_count = BTreeMap::new();
+        match gate {
+            Gate::Public(type_id, _) => {
+                public_count.insert(*type_id, 1);
+            }
+            Gate::Private(type_id, _) => {
+                private_count.insert(*type_id, 1);
+            }
+            Gate::Call(name, _, _) => {
+                let function = known_functions.get(name).ok_or("Unknown function")?;
+                match &function.body {
+                    FunctionBody::PluginBody(plugin_body) => {
+                        public_count = plugin_body.public_count.clone();
+                        private_count = plugin_body.private_count.clone();
+                    }
+                    FunctionBody::Gates(gates) => {
+                        for sub_gate in gates.iter() {
+                            let (sub_public_count, sub_private_count) =
+                                Self::gate_input_counts(sub_gate, known_functions)?;
+                            for (type_id, count) in sub_public_count {
+                                *public_count.entry(type_id).or_insert(0) += count;
+                            }
+                            for (type_id, count) in sub_private_count {
+                                *private_count.entry(type_id).or_insert(0) += count;
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok((public_count, private_count))
+    }
+
+    /// Moves (at most) the requested number of public/private inputs from `inputs`
+    /// into new EvaluatorInputs.
+    fn take_inputs(
+        inputs: &mut EvaluatorInputs<PlaintextBackend>,
+        public_count: &BTreeMap<TypeId, u64>,
+        private_count: &BTreeMap<TypeId, u64>,
+    ) -> EvaluatorInputs<PlaintextBackend> {
+        let mut taken = EvaluatorInputs {
+            types: inputs.types.clone(),
+            verifier_mode: inputs.verifier_mode,
+            ..Default::default()
+        };
+        let types = &inputs.types;
+        let take = |queues: &mut BTreeMap<Type, VecDeque<BigUint>>,
+                    taken_queues: &mut BTreeMap<Type, VecDeque<BigUint>>,
+                    counts: &BTreeMap<TypeId, u64>| {
+            for (type_id, count) in counts.iter() {
+                let type_value = match types.get(*type_id as usize) {
+                    Some(type_value) => type_value,
+                    None => continue,
+                };
+                if let Some(queue) = queues.get_mut(type_value) {
+                    let count = std::cmp::min(*count as usize, queue.len());
+                    taken_queues
+                        .entry(type_value.clone())
+                        .or_insert_with(VecDeque::new)
+                        .extend(queue.drain(..count));
+                }
+            }
+        };
+        take(
+            &mut inputs.public_inputs_queue,
+            &mut taken.public_inputs_queue,
+            public_count,
+        );
+        take(
+            &mut inputs.private_inputs_queue,
+            &mut taken.private_inputs_queue,
+            private_count,
+        );
+        taken
+    }
+}
+
 #[test]
 fn test_evaluator() {
     use crate::consumers::evaluator::Evaluator;
@@ -1285,3 +1864,151 @@ fn test_evaluator_with_functions_with_several_input_output_types() {
 
     assert_eq!(simulator.get_violations(), Vec::<String>::new());
 }
+
+#[test]
+fn test_parallel_evaluator() {
+    use crate::producers::examples::*;
+
+    let relation = example_relation();
+    let public_inputs = example_public_inputs();
+
+    for private_inputs in [example_private_inputs(), example_incorrect_private_inputs()] {
+        let mut zkbackend = PlaintextBackend::default();
+        let mut simulator: Evaluator<PlaintextBackend> = Evaluator::default();
+        let mut parallel_simulator = ParallelEvaluator::default();
+
+        for inputs in public_inputs.iter() {
+            simulator.ingest_message(&Message::PublicInputs(inputs.clone()), &mut zkbackend);
+            parallel_simulator.ingest_message(&Message::PublicInputs(inputs.clone()));
+        }
+        for inputs in private_inputs.iter() {
+            simulator.ingest_message(&Message::PrivateInputs(inputs.clone()), &mut zkbackend);
+            parallel_simulator.ingest_message(&Message::PrivateInputs(inputs.clone()));
+        }
+        simulator.ingest_message(&Message::Relation(relation.clone()), &mut zkbackend);
+        parallel_simulator.ingest_message(&Message::Relation(relation.clone()));
+
+        assert_eq!(
+            parallel_simulator.get_violations(),
+            simulator.get_violations()
+        );
+    }
+}
+
+#[test]
+fn test_evaluate_as_verifier() -> Result<()> {
+    use crate::producers::builder::{BuildGate::*, GateBuilder, GateBuilderT};
+    use crate::producers::examples::*;
+    use crate::producers::sink::MemorySink;
+
+    let example_source = |private_inputs: Vec<PrivateInputs>| -> Result<Source<'static>> {
+        let mut buffer = vec![];
+        for msg in example_public_inputs() {
+            msg.write_into(&mut buffer)?;
+        }
+        for msg in private_inputs {
+            msg.write_into(&mut buffer)?;
+        }
+        example_relation().write_into(&mut buffer)?;
+        Ok(Source::from_buffers(vec![buffer]))
+    };
+
+    let source = example_source(example_private_inputs())?;
+    assert_eq!(evaluate_as_verifier(&source)?, Vec::<String>::new());
+
+    // Assertions on private values cannot be checked by the verifier,
+    // so incorrect private inputs are not detected.
+    let source = example_source(example_incorrect_private_inputs())?;
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(evaluator.get_violations().len(), 1);
+    assert_eq!(evaluate_as_verifier(&source)?, Vec::<String>::new());
+
+    // The private input of the plugin call is missing: the prover must fail,
+    // while the verifier does not need it.
+    let mut private_inputs = example_private_inputs();
+    private_inputs.remove(1);
+    let source = example_source(private_inputs)?;
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(evaluator.get_violations().len(), 1);
+    assert_eq!(evaluate_as_verifier(&source)?, Vec::<String>::new());
+
+    // An assertion on public values only is checked.
+    let mut b = GateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![101])], &[]);
+    let x = b.create_gate(Public(0, Some(vec![3])))?;
+    let y = b.create_gate(Private(0, Some(vec![4])))?;
+    let wrong_public = b.create_gate(AddConstant(0, x, vec![97]))?;
+    b.create_gate(AssertZero(0, wrong_public))?;
+    let wrong_private = b.create_gate(AddConstant(0, y, vec![1]))?;
+    b.create_gate(AssertZero(0, wrong_private))?;
+    let y_times_zero = b.create_gate(MulConstant(0, y, vec![0]))?;
+    let zero = b.create_gate(Mul(0, y, y_times_zero))?;
+    b.create_gate(AssertZero(0, zero))?;
+
+    let source: Source = b.finish().into();
+    let violations = evaluate_as_verifier(&source)?;
+    assert_eq!(violations.len(), 1);
+    assert!(violations[0].contains("value 100"));
+    Ok(())
+}
+
+#[test]
+fn test_get_violations_with_gate_indices() -> Result<()> {
+    use crate::producers::examples::*;
+    use crate::Gate;
+
+    // The public input (wire 0 of type 0) is not zero.
+    let relation = example_relation().with_gate_insertions(vec![(3, Gate::AssertZero(0, 0))])?;
+
+    let mut zkbackend = PlaintextBackend::default();
+    let mut simulator: Evaluator<PlaintextBackend> = Evaluator::default();
+    let mut parallel_simulator = ParallelEvaluator::default();
+    for inputs in example_public_inputs() {
+        simulator.ingest_message(&Message::PublicInputs(inputs.clone()), &mut zkbackend);
+        parallel_simulator.ingest_message(&Message::PublicInputs(inputs));
+    }
+    for inputs in example_private_inputs() {
+        simulator.ingest_message(&Message::PrivateInputs(inputs.clone()), &mut zkbackend);
+        parallel_simulator.ingest_message(&Message::PrivateInputs(inputs));
+    }
+    simulator.ingest_message(&Message::Relation(relation.clone()), &mut zkbackend);
+    parallel_simulator.ingest_message(&Message::Relation(relation));
+
+    let violations = simulator.get_violations_with_gate_indices();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].0, 3);
+    assert_eq!(
+        parallel_simulator.get_violations_with_gate_indices(),
+        violations
+    );
+    assert_eq!(simulator.get_violations(), vec![violations[0].1.clone()]);
+
+    // A violation which is not caused by a gate gets the index of the last gate evaluated
+    let mut simulator: Evaluator<PlaintextBackend> = Evaluator::default();
+    let mut relation = example_relation();
+    relation.types[0] = Type::Field(vec![0]);
+    simulator.ingest_message(
+        &Message::Relation(relation),
+        &mut PlaintextBackend::default(),
+    );
+    let violations = simulator.get_violations_with_gate_indices();
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].0, 0);
+
+    // No violations for the original relation
+    let evaluator = Evaluator::from_messages(
+        example_public_inputs()
+            .into_iter()
+            .map(|inputs| Ok(Message::PublicInputs(inputs)))
+            .chain(
+                example_private_inputs()
+                    .into_iter()
+                    .map(|inputs| Ok(Message::PrivateInputs(inputs))),
+            )
+            .chain(std::iter::once(Ok(Message::Relation(example_relation())))),
+        &mut PlaintextBackend::default(),
+    );
+    assert!(evaluator.get_violations_with_gate_indices().is_empty());
+    Ok(())
+}
@@ -51,18 +51,22 @@ impl<S: Sink> ZKBackend for IRFlattener<S> {
     }
 
     fn set_types(&mut self, types: &[Type]) -> Result<()> {
-        if self.gate_builder.is_none() {
-            types
-                .iter()
-                .try_for_each::<_, Result<()>>(|type_value| match type_value {
-                    Type::Field(modulo) => {
-                        self.moduli.push(value_to_biguint(modulo));
-                        Ok(())
-                    }
-                    Type::PluginType(_, _, _) => {
-                        Err("Not possible to flatten circuit containing plugin types".into())
+        let new_types = &types[self.moduli.len().min(types.len())..];
+        new_types
+            .iter()
+            .try_for_each::<_, Result<()>>(|type_value| match type_value {
+                Type::Field(modulo) => {
+                    self.moduli.push(value_to_biguint(modulo));
+                    if let Some(gate_builder) = &mut self.gate_builder {
+                        gate_builder.push_type(modulo.clone())?;
                     }
-                })?;
+                    Ok(())
+                }
+                Type::PluginType(_, _, _) => {
+                    Err("Not possible to flatten circuit containing plugin types".into())
+                }
+            })?;
+        if self.gate_builder.is_none() {
             self.gate_builder = Some(GateBuilder::new(self.sink.take().unwrap(), &[], types, &[]));
         }
         Ok(())
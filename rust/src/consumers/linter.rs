@@ -0,0 +1,164 @@
+use crate::structs::value::value_to_biguint;
+use crate::Gate;
+use num_traits::Zero;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum LintSeverity {
+    /// The gates can be written in a simpler or cheaper way.
+    Info,
+    /// The gates are valid but probably not what was intended.
+    Warning,
+    /// The gates make the statement false whatever the inputs.
+    Error,
+}
+
+/// LintWarning describes a pattern found by `lint_gates` at the gate `gate_index`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LintWarning {
+    pub gate_index: usize,
+    pub message: String,
+    pub severity: LintSeverity,
+}
+
+impl LintWarning {
+    fn new(gate_index: usize, severity: LintSeverity, message: String) -> Self {
+        LintWarning {
+            gate_index,
+            message,
+            severity,
+        }
+    }
+}
+
+/// Looks for stylistic issues in `gates`, whose constants are elements of the field of
+/// modulo `modulus` (little-endian):
+/// - `Add(t, out, w, w)`, which is `MulConstant(t, out, w, 2)`,
+/// - constants which are not reduced modulo `modulus`,
+/// - `Copy(t, out, w)` immediately followed by the deletion of `w`: `w` could be used instead,
+/// - `AssertZero(t, w)` immediately following `Constant(t, w, value)`: the assertion
+///   can be decided without any input.
+///
+/// Unlike the Validator, the linter does not report invalid statements, only suspicious ones.
+pub fn lint_gates(gates: &[Gate], modulus: &[u8]) -> Vec<LintWarning> {
+    let modulo = value_to_biguint(modulus);
+    let mut warnings = vec![];
+    for (index, gate) in gates.iter().enumerate() {
+        match gate {
+            Gate::Add(_, _, left, right) if left == right => warnings.push(LintWarning::new(
+                index,
+                LintSeverity::Info,
+                format!(
+                    "Add({}, {}) computes 2 * {}, which is better written with a MulConstant gate.",
+                    left, right, left
+                ),
+            )),
+            Gate::Constant(_, _, value)
+            | Gate::AddConstant(_, _, _, value)
+            | Gate::MulConstant(_, _, _, value) => {
+                if value_to_biguint(value) >= modulo {
+                    warnings.push(LintWarning::new(
+                        index,
+                        LintSeverity::Warning,
+                        format!(
+                            "The constant {} is not reduced modulo {}.",
+                            value_to_biguint(value),
+                            modulo
+                        ),
+                    ));
+                }
+            }
+            _ => {}
+        }
+
+        let next = match gates.get(index + 1) {
+            Some(next) => next,
+            None => continue,
+        };
+        match (gate, next) {
+            (Gate::Copy(type_id, out, wire), Gate::Delete(delete_type_id, first, last))
+                if type_id == delete_type_id && first <= wire && wire <= last =>
+            {
+                warnings.push(LintWarning::new(
+                    index,
+                    LintSeverity::Info,
+                    format!(
+                        "The wire ({}: {}) is deleted right after being copied into ({}: {}), \
+                        so the Copy gate can be eliminated.",
+                        type_id, wire, type_id, out
+                    ),
+                ))
+            }
+            (Gate::Constant(type_id, out, value), Gate::AssertZero(assert_type_id, wire))
+                if type_id == assert_type_id && out == wire =>
+            {
+                if (value_to_biguint(value) % &modulo).is_zero() {
+                    warnings.push(LintWarning::new(
+                        index + 1,
+                        LintSeverity::Warning,
+                        format!(
+                            "The wire ({}: {}) is the constant 0, \
+                            so the AssertZero gate always passes.",
+                            type_id, wire
+                        ),
+                    ))
+                } else {
+                    warnings.push(LintWarning::new(
+                        index + 1,
+                        LintSeverity::Error,
+                        format!(
+                            "The wire ({}: {}) is a non-zero constant, \
+                            so the AssertZero gate always fails.",
+                            type_id, wire
+                        ),
+                    ))
+                }
+            }
+            _ => {}
+        }
+    }
+    warnings
+}
+
+#[test]
+fn test_lint_gates() {
+    use crate::producers::examples::example_relation;
+    use crate::structs::directives::Directive;
+
+    let gates = vec![
+        Gate::Private(0, 0),
+        Gate::Add(0, 1, 0, 0),
+        Gate::Copy(0, 2, 1),
+        Gate::Delete(0, 0, 1),
+        Gate::Constant(0, 3, vec![0]),
+        Gate::AssertZero(0, 3),
+        Gate::Constant(0, 4, vec![105]),
+        Gate::AssertZero(0, 4),
+        Gate::MulConstant(0, 5, 2, vec![2]),
+    ];
+    let warnings = lint_gates(&gates, &[101]);
+    let found = warnings
+        .iter()
+        .map(|warning| (warning.gate_index, warning.severity))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        found,
+        vec![
+            (1, LintSeverity::Info),
+            (2, LintSeverity::Info),
+            (5, LintSeverity::Warning),
+            (6, LintSeverity::Warning),
+            (7, LintSeverity::Error),
+        ]
+    );
+
+    // The gates of the example are fine
+    let gates = example_relation()
+        .directives
+        .into_iter()
+        .filter_map(|directive| match directive {
+            Directive::Gate(gate) => Some(gate),
+            Directive::Function(_) => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(lint_gates(&gates, &[101]), vec![]);
+}
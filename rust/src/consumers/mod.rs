@@ -13,6 +13,12 @@ pub mod stats;
 /// Helper functions to read buffers.
 pub mod utils;
 
+/// Analysis of circuits (call graph, ...).
+pub mod analysis;
+
+/// The linter flags suspicious or redundant patterns in lists of gates.
+pub mod linter;
+
 // Flattening SIEVE IR.
 pub mod flattening;
 
@@ -1,5 +1,12 @@
-use crate::consumers::utils::read_buffer;
-use crate::{Message, Messages, Result, FILE_EXTENSION};
+use crate::consumers::evaluator::{evaluate_as_verifier, Evaluator, PlaintextBackend};
+use crate::consumers::utils::{read_buffer, split_messages};
+use crate::consumers::validator::Validator;
+use crate::sieve_ir_generated::sieve_ir as generated;
+use crate::structs::directives::Directive;
+use crate::structs::relation::relation_directives;
+use crate::{
+    Gate, Message, Messages, PrivateInputs, PublicInputs, Relation, Result, FILE_EXTENSION,
+};
 use std::convert::TryFrom;
 use std::ffi::OsStr;
 use std::fs::{read_dir, File};
@@ -10,7 +17,7 @@ use std::path::{Path, PathBuf};
 /// Source finds and reads IR messages from a directory.
 /// It supports reading messages one-by-one from large files or from many files.
 /// It supports reading from stdin using dash (-) as a special filename.
-/// It supports reading from given buffers.
+/// It supports reading from given buffers, owned or borrowed (see `from_flatbuffer_slices`).
 ///
 /// # Example
 /// ```
@@ -42,21 +49,22 @@ use std::path::{Path, PathBuf};
 /// assert_eq!(got, vec!["PUBLIC", "PRIVATE", "PRIVATE", "RELATION", "RELATION"]);
 /// ```
 #[derive(Clone, Debug, Eq, PartialEq)]
-pub struct Source {
+pub struct Source<'a> {
     /// Set to true to print the paths of files as they are read.
     pub print_filenames: bool,
 
-    buffer_source: BufferSource,
+    buffer_source: BufferSource<'a>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
-enum BufferSource {
+enum BufferSource<'a> {
     Stdin,
     Files(Vec<PathBuf>),
     Memory(Vec<Vec<u8>>),
+    Slices(&'a [&'a [u8]]),
 }
 
-impl Source {
+impl<'a> Source<'a> {
     pub fn from_directory(path: &Path) -> Result<Self> {
         Self::from_dirs_and_files(&[path.to_path_buf()])
     }
@@ -95,17 +103,35 @@ impl Source {
         }
     }
 
+    /// Creates a Source reading the messages from borrowed `slices`, each of which may contain
+    /// several size-prefixed messages. The bytes are not copied: `iter_messages` parses the
+    /// messages in place (`iter_buffers` still returns owned copies).
+    pub fn from_flatbuffer_slices(slices: &'a [&'a [u8]]) -> Self {
+        Source {
+            buffer_source: BufferSource::Slices(slices),
+            print_filenames: false,
+        }
+    }
+
     pub fn iter_buffers<'w>(&'w self) -> Box<dyn Iterator<Item = Vec<u8>> + 'w> {
         match &self.buffer_source {
             BufferSource::Stdin => Box::new(iterate_stream(stdin())),
             BufferSource::Files(paths) => Box::new(iterate_files(&paths[..], self.print_filenames)),
             BufferSource::Memory(buffers) => Box::new(iterate_buffers(&buffers[..])),
+            BufferSource::Slices(slices) => {
+                Box::new(iterate_slices(slices).map(|buffer| buffer.to_vec()))
+            }
         }
     }
 
-    pub fn iter_messages(&self) -> impl Iterator<Item = Result<Message>> + '_ {
-        self.iter_buffers()
-            .map(|buffer| Message::try_from(&buffer[..]))
+    pub fn iter_messages(&self) -> Box<dyn Iterator<Item = Result<Message>> + '_> {
+        match &self.buffer_source {
+            BufferSource::Slices(slices) => Box::new(iterate_slices(slices).map(Message::try_from)),
+            _ => Box::new(
+                self.iter_buffers()
+                    .map(|buffer| Message::try_from(&buffer[..])),
+            ),
+        }
     }
 
     pub fn read_all_messages(&self) -> Result<Messages> {
@@ -115,6 +141,113 @@ impl Source {
         }
         Ok(messages)
     }
+
+    /// Reads all messages and partitions them by type, whatever their order in the source.
+    /// Messages of the same type keep their relative order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a message cannot be read (see `read_all_messages` to handle this error).
+    pub fn split_by_message_type(self) -> (Vec<PublicInputs>, Vec<PrivateInputs>, Vec<Relation>) {
+        let messages = self
+            .read_all_messages()
+            .unwrap_or_else(|err| panic!("Cannot read the messages of the source: {}", err));
+        (
+            messages.public_inputs,
+            messages.private_inputs,
+            messages.relations,
+        )
+    }
+
+    /// Evaluates the circuit with the plaintext backend and returns the violations
+    /// (e.g. a failed assertion), or an empty vector if the statement is true.
+    pub fn verify(self) -> Vec<String> {
+        verify_messages(self.iter_messages(), None, Some(Evaluator::default()))
+    }
+
+    /// Validates the messages as a prover (including the private inputs) and evaluates the
+    /// circuit, and returns the violations of both.
+    pub fn verify_as_prover(self) -> Vec<String> {
+        verify_messages(
+            self.iter_messages(),
+            Some(Validator::new_as_prover()),
+            Some(Evaluator::default()),
+        )
+    }
+
+    /// Validates the messages as a verifier and evaluates the assertions computable from the
+    /// public inputs (see `evaluate_as_verifier`), and returns the violations of both.
+    /// PrivateInputs messages are skipped since the verifier does not see them.
+    pub fn verify_as_verifier(self) -> Vec<String> {
+        let evaluation = match evaluate_as_verifier(&self) {
+            Ok(violations) => violations,
+            Err(err) => return vec![err.to_string()],
+        };
+        let messages = self
+            .iter_messages()
+            .filter(|msg| !matches!(msg, Ok(Message::PrivateInputs(_))));
+        let mut violations = verify_messages(messages, Some(Validator::new_as_verifier()), None);
+        violations.extend(evaluation);
+        violations
+    }
+
+    /// Returns true if the evaluation of the circuit reports no violation (see `verify`).
+    pub fn is_valid(self) -> bool {
+        self.verify().is_empty()
+    }
+
+    /// Returns a new in-memory Source where `f` is applied to each top-level gate of each
+    /// Relation message (gates in function bodies are left unchanged).
+    /// Relation messages are re-serialized, other messages are copied as they are.
+    pub fn map_gates<F: Fn(Gate) -> Gate>(self, f: F) -> Result<Source<'static>> {
+        self.map_relations(|relation| {
+            relation.directives = std::mem::take(&mut relation.directives)
+                .into_iter()
+                .map(|directive| match directive {
+                    Directive::Gate(gate) => Directive::Gate(f(gate)),
+                    Directive::Function(_) => directive,
+                })
+                .collect();
+        })
+    }
+
+    /// Returns a new in-memory Source keeping only the top-level gates for which `f`
+    /// returns true (see `map_gates`).
+    pub fn filter_gates<F: Fn(&Gate) -> bool>(self, f: F) -> Result<Source<'static>> {
+        self.map_relations(|relation| {
+            relation.directives.retain(|directive| match directive {
+                Directive::Gate(gate) => f(gate),
+                Directive::Function(_) => true,
+            });
+        })
+    }
+
+    /// Iterates over the top-level gates of all Relation messages, converting them one at a time
+    /// from the Flatbuffers buffers (see `gates_from_flatbuffer`). Other messages are skipped.
+    pub fn iter_gates(&self) -> impl Iterator<Item = Result<Gate>> + '_ {
+        self.iter_buffers()
+            .filter(|buffer| {
+                generated::get_size_prefixed_root_as_root(buffer).message_type()
+                    == generated::Message::Relation
+            })
+            .flat_map(OwnedRelationGates::new)
+    }
+
+    fn map_relations(self, mut f: impl FnMut(&mut Relation)) -> Result<Source<'static>> {
+        let mut buffers = vec![];
+        for buffer in self.iter_buffers() {
+            match Message::try_from(&buffer[..])? {
+                Message::Relation(mut relation) => {
+                    f(&mut relation);
+                    let mut new_buffer = vec![];
+                    relation.write_into(&mut new_buffer)?;
+                    buffers.push(new_buffer);
+                }
+                _ => buffers.push(buffer),
+            }
+        }
+        Ok(Source::from_buffers(buffers))
+    }
 }
 
 pub fn iterate_files(paths: &[PathBuf], print: bool) -> impl Iterator<Item = Vec<u8>> + '_ {
@@ -142,6 +275,11 @@ pub fn iterate_buffers(buffers: &[Vec<u8>]) -> impl Iterator<Item = Vec<u8>> + '
         .flat_map(|buffer| iterate_stream(&buffer[..]))
 }
 
+/// Iterates over the size-prefixed messages of `slices` without copying them.
+pub fn iterate_slices<'s>(slices: &'s [&'s [u8]]) -> impl Iterator<Item = &'s [u8]> + 's {
+    slices.iter().flat_map(|slice| split_messages(slice))
+}
+
 pub fn iterate_stream<'s>(mut stream: impl Read + 's) -> impl Iterator<Item = Vec<u8>> + 's {
     iter::from_fn(move || match read_buffer(&mut stream) {
         Err(err) => {
@@ -191,3 +329,275 @@ pub fn list_workspace_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
     }
     Ok(all_paths)
 }
+
+/// Feeds all messages to `validator` and `evaluator` (reading the source only once),
+/// and returns their violations. A message which cannot be read is reported as a violation.
+fn verify_messages(
+    messages: impl Iterator<Item = Result<Message>>,
+    mut validator: Option<Validator>,
+    mut evaluator: Option<Evaluator<PlaintextBackend>>,
+) -> Vec<String> {
+    let mut backend = PlaintextBackend::default();
+    for msg in messages {
+        let msg = match msg {
+            Ok(msg) => msg,
+            Err(err) => return vec![err.to_string()],
+        };
+        if let Some(validator) = validator.as_mut() {
+            validator.ingest_message(&msg);
+        }
+        if let Some(evaluator) = evaluator.as_mut() {
+            evaluator.ingest_message(&msg, &mut backend);
+        }
+    }
+    let mut violations = validator.map_or(vec![], Validator::get_violations);
+    violations.extend(evaluator.map_or(vec![], Evaluator::get_violations));
+    violations
+}
+
+/// OwnedRelationGates iterates over the top-level gates of the Relation message it owns,
+/// reading one directive at a time from the Flatbuffers buffer.
+struct OwnedRelationGates {
+    buffer: Vec<u8>,
+    len: usize,
+    index: usize,
+    error: Option<Box<dyn std::error::Error>>,
+}
+
+impl OwnedRelationGates {
+    fn new(buffer: Vec<u8>) -> Self {
+        let (len, error) = match relation_directives(&buffer) {
+            Ok(g_directives) => (g_directives.len(), None),
+            Err(err) => (0, Some(err)),
+        };
+        OwnedRelationGates {
+            buffer,
+            len,
+            index: 0,
+            error,
+        }
+    }
+}
+
+impl Iterator for OwnedRelationGates {
+    type Item = Result<Gate>;
+
+    fn next(&mut self) -> Option<Result<Gate>> {
+        if let Some(err) = self.error.take() {
+            return Some(Err(err));
+        }
+        while self.index < self.len {
+            let g_directive = match relation_directives(&self.buffer) {
+                Ok(g_directives) => g_directives.get(self.index),
+                Err(err) => return Some(Err(err)),
+            };
+            self.index += 1;
+            match Directive::try_from(g_directive) {
+                Ok(Directive::Gate(gate)) => return Some(Ok(gate)),
+                Ok(Directive::Function(_)) => {}
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        None
+    }
+}
+
+#[test]
+fn test_split_by_message_type() -> Result<()> {
+    use crate::producers::simple_examples::*;
+    use crate::producers::sink::{MemorySink, Sink};
+
+    // Push interleaved messages into a sink
+    let mut sink = MemorySink::default();
+    sink.push_relation_message(&simple_example_relation())?;
+    sink.push_private_inputs_message(&simple_example_private_inputs())?;
+    sink.push_relation_message(&simple_example_relation())?;
+    sink.push_public_inputs_message(&simple_example_public_inputs())?;
+    sink.push_private_inputs_message(&simple_example_private_inputs())?;
+
+    let source: Source = sink.into();
+    let (public_inputs, private_inputs, relations) = source.split_by_message_type();
+    assert_eq!(public_inputs, vec![simple_example_public_inputs()]);
+    assert_eq!(private_inputs.len(), 2);
+    assert_eq!(relations.len(), 2);
+
+    // Round trip through a new sink
+    let mut sink = MemorySink::default();
+    for inputs in public_inputs.iter() {
+        sink.push_public_inputs_message(inputs)?;
+    }
+    for inputs in private_inputs.iter() {
+        sink.push_private_inputs_message(inputs)?;
+    }
+    for relation in relations.iter() {
+        sink.push_relation_message(relation)?;
+    }
+    let source: Source = sink.into();
+    assert_eq!(
+        source.split_by_message_type(),
+        (public_inputs, private_inputs, relations)
+    );
+    Ok(())
+}
+
+#[test]
+fn test_source_map_and_filter_gates() -> Result<()> {
+    use crate::consumers::stats::Stats;
+    use crate::producers::builder::{BuildGate::*, GateBuilder, GateBuilderT};
+    use crate::producers::sink::MemorySink;
+    use crate::structs::types::Type;
+
+    let mut b = GateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![101])], &[]);
+    b.set_max_len(3)?;
+    let x = b.create_gate(Private(0, Some(vec![3])))?;
+    let y = b.create_gate(Private(0, Some(vec![4])))?;
+    let sum = b.create_gate(Add(0, x, y))?;
+    let product = b.create_gate(Mul(0, sum, y))?;
+    let sum2 = b.create_gate(Add(0, product, x))?;
+    b.create_gate(AssertZero(0, sum2))?;
+    let source: Source = b.finish().into();
+    let original = source.read_all_messages()?;
+    assert!(original.relations.len() > 1);
+
+    // Replace Add gates with a Copy of their left input
+    let mapped = source.clone().map_gates(|gate| match gate {
+        Gate::Add(type_id, out, left, _) => Gate::Copy(type_id, out, left),
+        gate => gate,
+    })?;
+    let messages = mapped.read_all_messages()?;
+    assert_eq!(messages.relations.len(), original.relations.len());
+    assert_eq!(messages.private_inputs, original.private_inputs);
+    let gates = messages
+        .relations
+        .iter()
+        .flat_map(|relation| relation.directives.iter())
+        .filter_map(|directive| match directive {
+            Directive::Gate(gate) => Some(gate.clone()),
+            Directive::Function(_) => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        gates,
+        vec![
+            Gate::Private(0, x),
+            Gate::Private(0, y),
+            Gate::Copy(0, sum, x),
+            Gate::Mul(0, product, sum, y),
+            Gate::Copy(0, sum2, product),
+            Gate::AssertZero(0, sum2),
+        ]
+    );
+
+    let filtered = source.filter_gates(|gate| !matches!(gate, Gate::AssertZero(_, _)))?;
+    let stats = Stats::from_messages(filtered.iter_messages());
+    assert_eq!(stats.gate_stats.assert_zero_gates, 0);
+    assert_eq!(stats.gate_stats.add_gates, 2);
+    assert_eq!(stats.gate_stats.mul_gates, 1);
+    Ok(())
+}
+
+#[test]
+fn test_source_iter_gates() -> Result<()> {
+    use crate::producers::examples::*;
+    use crate::structs::relation::gates_from_flatbuffer;
+
+    let mut relation_buffer = vec![];
+    example_relation().write_into(&mut relation_buffer)?;
+    let mut buffers = vec![];
+    for inputs in example_public_inputs() {
+        let mut buffer = vec![];
+        inputs.write_into(&mut buffer)?;
+        buffers.push(buffer);
+    }
+    buffers.push(relation_buffer.clone());
+    buffers.push(relation_buffer.clone());
+    let source = Source::from_buffers(buffers);
+
+    let gates = source.iter_gates().collect::<Result<Vec<_>>>()?;
+    let expected = gates_from_flatbuffer(&relation_buffer).collect::<Result<Vec<_>>>()?;
+    assert!(!expected.is_empty());
+    assert_eq!(gates, [expected.clone(), expected].concat());
+    Ok(())
+}
+
+#[test]
+fn test_source_verify() -> Result<()> {
+    use crate::producers::builder::{BuildGate::*, GateBuilder, GateBuilderT};
+    use crate::producers::examples::*;
+    use crate::producers::sink::MemorySink;
+    use crate::structs::types::Type;
+
+    let new_source = |private_inputs: &[PrivateInputs]| -> Result<Source<'static>> {
+        let mut buffer = vec![];
+        for inputs in example_public_inputs() {
+            inputs.write_into(&mut buffer)?;
+        }
+        for inputs in private_inputs {
+            inputs.write_into(&mut buffer)?;
+        }
+        example_relation().write_into(&mut buffer)?;
+        Ok(Source::from_buffers(vec![buffer]))
+    };
+
+    let source = new_source(&example_private_inputs())?;
+    assert_eq!(source.clone().verify(), Vec::<String>::new());
+    assert_eq!(source.clone().verify_as_prover(), Vec::<String>::new());
+    assert_eq!(source.clone().verify_as_verifier(), Vec::<String>::new());
+    assert!(source.is_valid());
+
+    // The verifier does not need the private inputs
+    let source = new_source(&[])?;
+    assert_eq!(source.verify_as_verifier(), Vec::<String>::new());
+
+    // Incorrect private inputs are only detected by the evaluation
+    let source = new_source(&example_incorrect_private_inputs())?;
+    assert!(!source.clone().verify().is_empty());
+    assert!(!source.clone().verify_as_prover().is_empty());
+    assert_eq!(source.clone().verify_as_verifier(), Vec::<String>::new());
+    assert!(!source.is_valid());
+
+    // A failed assertion on public values only is detected by the verifier
+    let mut b = GateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![101])], &[]);
+    let x = b.create_gate(Public(0, Some(vec![3])))?;
+    let wrong_public = b.create_gate(AddConstant(0, x, vec![97]))?;
+    b.create_gate(AssertZero(0, wrong_public))?;
+    let source: Source = b.finish().into();
+    assert_eq!(source.verify_as_verifier().len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_source_from_flatbuffer_slices() -> Result<()> {
+    use crate::producers::simple_examples::*;
+
+    let mut first = vec![];
+    simple_example_public_inputs().write_into(&mut first)?;
+    simple_example_private_inputs().write_into(&mut first)?;
+    let mut second = vec![];
+    simple_example_relation().write_into(&mut second)?;
+
+    // Copy the messages into stack-allocated arrays
+    let mut first_array = [0u8; 4096];
+    first_array[..first.len()].copy_from_slice(&first);
+    let mut second_array = [0u8; 4096];
+    second_array[..second.len()].copy_from_slice(&second);
+    let slices: [&[u8]; 2] = [&first_array[..first.len()], &second_array[..]];
+
+    let source = Source::from_flatbuffer_slices(&slices);
+    let messages = source.read_all_messages()?;
+    assert_eq!(messages.public_inputs, vec![simple_example_public_inputs()]);
+    assert_eq!(
+        messages.private_inputs,
+        vec![simple_example_private_inputs()]
+    );
+    assert_eq!(messages.relations, vec![simple_example_relation()]);
+
+    // Same messages as an owned Source
+    let owned = Source::from_buffers(vec![first, second]);
+    assert_eq!(
+        source.iter_buffers().collect::<Vec<_>>(),
+        owned.iter_buffers().collect::<Vec<_>>()
+    );
+    assert!(source.verify_as_prover().is_empty());
+    Ok(())
+}
@@ -135,12 +135,10 @@ impl Stats {
     }
 
     fn ingest_types(&mut self, types: &[Type]) {
-        if self.types.is_empty() {
-            // Types have not yet been ingested
-            types
-                .iter()
-                .for_each(|type_value| self.types.push(type_value.clone()));
-        }
+        // The types of a relation are appended to those of the previous relations
+        types
+            .iter()
+            .for_each(|type_value| self.types.push(type_value.clone()));
     }
 }
 
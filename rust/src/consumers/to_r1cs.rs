@@ -103,10 +103,10 @@ impl<S: Sink> ZKBackend for ToR1CSConverter<S> {
     }
 
     fn set_types(&mut self, types: &[Type]) -> Result<()> {
+        if types.len() != 1 {
+            return Err("One type must be defined to convert to R1CS.".into());
+        }
         if self.src_modulus.is_zero() {
-            if types.len() != 1 {
-                return Err("One type must be defined to convert to R1CS.".into());
-            }
             let first_type = types
                 .get(0)
                 .ok_or("One type must be defined to convert to R1CS.")?;
@@ -5,14 +5,17 @@ use regex::Regex;
 use std::collections::{BTreeMap, BTreeSet};
 use std::convert::TryFrom;
 
+use crate::consumers::source::Source;
+use crate::plugins::evaluate_plugin::extract_number;
 use crate::structs::conversion::Conversion;
 use crate::structs::count::Count;
 use crate::structs::directives::Directive;
 use crate::structs::function::{FunctionBody, FunctionCounts};
+use crate::structs::plugin::PluginBody;
 use crate::structs::types::Type;
 use crate::structs::value::value_to_biguint;
 use crate::structs::wirerange::{add_types_to_wire_ranges, WireRange};
-use crate::{Gate, Message, PrivateInputs, PublicInputs, Relation, TypeId, WireId};
+use crate::{Gate, Message, PrivateInputs, PublicInputs, Relation, Result, TypeId, WireId};
 
 type TypeElement = BigUint;
 
@@ -24,6 +27,13 @@ const VERSION_REGEX: &str = r"^\d+.\d+.\d+$";
 /// Used to check the validity of names of functions, names of plugins, names of operation, string params in plugin
 const STRING_REGEX: &str = r"^[a-zA-Z_][\w]*((\.|::)[a-zA-Z_][\w]*)*$";
 const NUMBER_REGEX: &str = r"^((\d+)|(0x[0-9a-fA-F]+))$";
+/// Ends of the violation messages selected by `check_ssa` and `check_wire_liveness`.
+const SSA_VIOLATION: &str = "This violates the SSA property.";
+const DELETED_WIRE_SET_VIOLATION: &str = "It is no longer possible to re-use it.";
+const UNSET_WIRE_USED_VIOLATION: &str =
+    "is used but was not assigned a value, or has been deleted already.";
+const UNSET_WIRE_DELETED_VIOLATION: &str =
+    "is being deleted, but was not defined previously, or has been already deleted";
 const IMPLEMENTED_CHECKS: &str = r"
 VERSION_REGEX = “^\d+.\d+.\d+$“
 STRING_REGEX = “^[a-zA-Z_][\w]*((\.|::)[a-zA-Z_][\w]*)*$“
@@ -113,6 +123,8 @@ pub struct Validator {
     public_inputs_counts: BTreeMap<ValidatorType, u64>,
     private_inputs_counts: BTreeMap<ValidatorType, u64>,
     live_wires: BTreeSet<(TypeId, WireId)>,
+    // high-water mark of live_wires.len()
+    max_live_wires: usize,
     deleted_wires: BTreeSet<(TypeId, WireId)>,
     // (type_id, first_wire, last_wire)
     allocations: BTreeSet<(TypeId, WireId, WireId)>,
@@ -148,6 +160,65 @@ impl Validator {
         }
     }
 
+    /// Validates all `messages` with a new prover-mode validator and returns the violations.
+    pub fn ingest_all<'a>(messages: impl Iterator<Item = &'a Message>) -> Vec<String> {
+        let mut validator = Validator::new_as_prover();
+        messages.for_each(|msg| validator.ingest_message(msg));
+        validator.get_violations()
+    }
+
+    /// Same as `ingest_all` with the messages read from `source`.
+    /// Messages which cannot be parsed are reported as violations.
+    pub fn ingest_all_from_source(source: &Source) -> Vec<String> {
+        let mut validator = Validator::new_as_prover();
+        for msg in source.iter_messages() {
+            match msg {
+                Ok(msg) => validator.ingest_message(&msg),
+                Err(err) => validator.violate(format!("Invalid message: {}", err)),
+            }
+        }
+        validator.get_violations()
+    }
+
+    /// Checks the Static Single Assignment property of `gates`: each wire is set at most once,
+    /// and never set again once deleted.
+    /// It runs `gates` through a temporary validator, so it does not modify any validator and does
+    /// not need the types of the relation. As the wires of a Call gate are typed by the declaration
+    /// of the called function, which is unknown here, Call gates are reported as violations.
+    pub fn check_ssa(gates: &[Gate]) -> Vec<String> {
+        Validator::check_gates(gates, |violation| {
+            violation.ends_with(SSA_VIOLATION) || violation.ends_with(DELETED_WIRE_SET_VIOLATION)
+        })
+    }
+
+    /// Checks that `gates` only use or delete wires which are set and not yet deleted.
+    /// As `check_ssa`, it runs `gates` through a temporary validator and reports Call gates.
+    pub fn check_wire_liveness(gates: &[Gate]) -> Vec<String> {
+        Validator::check_gates(gates, |violation| {
+            violation.ends_with(UNSET_WIRE_USED_VIOLATION)
+                || violation.ends_with(UNSET_WIRE_DELETED_VIOLATION)
+        })
+    }
+
+    /// Runs `gates` through a temporary prover validator, and returns the violations selected by
+    /// `is_checked` and those of the Call gates, prefixed with the index of the violating gate.
+    fn check_gates(gates: &[Gate], is_checked: impl Fn(&str) -> bool) -> Vec<String> {
+        let mut validator = Validator::new_as_prover();
+        let mut violations = vec![];
+        for (index, gate) in gates.iter().enumerate() {
+            let previous_count = validator.how_many_violations();
+            validator.ingest_gate(gate);
+            let is_call = matches!(gate, Gate::Call(_, _, _));
+            violations.extend(
+                validator.violations[previous_count..]
+                    .iter()
+                    .filter(|violation| is_call || is_checked(violation))
+                    .map(|violation| format!("Gate {}: {}", index, violation)),
+            );
+        }
+        violations
+    }
+
     pub fn print_implemented_checks() {
         println!("{}", IMPLEMENTED_CHECKS);
     }
@@ -165,6 +236,22 @@ impl Validator {
         self.violations.len()
     }
 
+    /// Returns the violations detected so far (without the final checks of `get_violations`).
+    pub(crate) fn current_violations(&self) -> &[String] {
+        &self.violations
+    }
+
+    /// Returns the number of wires which are currently set and not yet deleted.
+    pub fn current_live_wire_count(&self) -> usize {
+        self.live_wires.len()
+    }
+
+    /// Returns the maximum number of wires simultaneously live since the creation of the validator.
+    /// Wires defined inside function bodies are not taken into account.
+    pub fn max_live_wire_count(&self) -> usize {
+        self.max_live_wires
+    }
+
     pub fn ingest_message(&mut self, msg: &Message) {
         match msg {
             Message::PublicInputs(i) => self.ingest_public_inputs(i),
@@ -244,6 +331,28 @@ impl Validator {
         *count += private_inputs.inputs.len() as u64;
     }
 
+    /// Checks and appends `types` to the known types.
+    fn ingest_types(&mut self, types: &[Type]) {
+        types.iter().for_each(|ir_type| {
+            // Convert IR type into ValidatorType
+            let validator_type = match &ir_type {
+                Type::Field(modulo) => {
+                    let biguint_modulo = value_to_biguint(modulo);
+                    ValidatorType::Field(biguint_modulo)
+                }
+                Type::PluginType(name, operation, params) => {
+                    ValidatorType::PluginType(name.clone(), operation.clone(), params.clone())
+                }
+            };
+            // Check type
+            if !self.check_validator_type(&validator_type) {
+                self.violate("When declaring a Plugin type, the plugin name should be declared");
+            }
+            // Insert ValidatorType into types
+            self.types.push(validator_type);
+        });
+    }
+
     pub fn ingest_relation(&mut self, relation: &Relation) {
         // Check version
         self.check_version(&relation.version);
@@ -264,26 +373,7 @@ impl Validator {
                 self.known_plugins.insert(plugin_name.clone());
             });
             // Check and ingest types
-            relation.types.iter().for_each(|ir_type| {
-                // Convert IR type into ValidatorType
-                let validator_type = match &ir_type {
-                    Type::Field(modulo) => {
-                        let biguint_modulo = value_to_biguint(modulo);
-                        ValidatorType::Field(biguint_modulo)
-                    }
-                    Type::PluginType(name, operation, params) => {
-                        ValidatorType::PluginType(name.clone(), operation.clone(), params.clone())
-                    }
-                };
-                // Check type
-                if !self.check_validator_type(&validator_type) {
-                    self.violate(
-                        "When declaring a Plugin type, the plugin name should be declared",
-                    );
-                }
-                // Insert ValidatorType into types
-                self.types.push(validator_type);
-            });
+            self.ingest_types(&relation.types);
             // Check and ingest conversions
             relation.conversions.iter().for_each(|conversion| {
                 // Check conversion
@@ -307,13 +397,12 @@ impl Validator {
                 self.known_conversions.insert(conversion.clone());
             });
         } else {
-            // It is not the first relation that we ingest, plugins, types and conversion must be empty
+            // It is not the first relation that we ingest, plugins and conversion must be empty,
+            // whereas types are appended to the previous ones
             if !relation.plugins.is_empty() {
                 self.violate("It is not the first ingested relation, plugins should be empty.");
             }
-            if !relation.types.is_empty() {
-                self.violate("It is not the first ingested relation, types should be empty.");
-            }
+            self.ingest_types(&relation.types);
             if !relation.conversions.is_empty() {
                 self.violate("It is not the first ingested relation, conversions should be empty.");
             }
@@ -414,7 +503,7 @@ impl Validator {
         }
     }
 
-    fn ingest_gate(&mut self, gate: &Gate) {
+    pub(crate) fn ingest_gate(&mut self, gate: &Gate) {
         use Gate::*;
 
         match gate {
@@ -787,6 +876,7 @@ impl Validator {
                 BTreeMap::new()
             },
             live_wires: Default::default(),
+            max_live_wires: 0,
             deleted_wires: Default::default(),
             allocations: Default::default(),
             version: self.version.clone(),
@@ -851,11 +941,15 @@ impl Validator {
 
     fn declare(&mut self, type_id: &TypeId, id: WireId) {
         self.live_wires.insert((*type_id, id));
+        self.max_live_wires = self.max_live_wires.max(self.live_wires.len());
     }
 
     fn remove(&mut self, type_id: &TypeId, id: WireId) {
         if !self.live_wires.remove(&(*type_id, id)) {
-            self.violate(format!("The variable ({}: {}) is being deleted, but was not defined previously, or has been already deleted", *type_id,id));
+            self.violate(format!(
+                "The variable ({}: {}) {}",
+                *type_id, id, UNSET_WIRE_DELETED_VIOLATION
+            ));
         }
         self.deleted_wires.insert((*type_id, id));
     }
@@ -927,9 +1021,8 @@ impl Validator {
                 // in this case, this is a violation, since all variables must have been defined
                 // previously
                 self.violate(format!(
-                    "The wire ({}: {}) is used but was not assigned a value, or has been deleted already.",
-                    *type_id,
-                    id
+                    "The wire ({}: {}) {}",
+                    *type_id, id, UNSET_WIRE_USED_VIOLATION
                 ));
             }
             // this line is useful to avoid having many times the same message if the validator already
@@ -941,9 +1034,8 @@ impl Validator {
     fn ensure_undefined(&mut self, type_id: &TypeId, id: WireId) {
         if self.is_defined(type_id, id) {
             self.violate(format!(
-                "The wire ({}: {}) has already been initialized before. This violates the SSA property.",
-                *type_id,
-                id
+                "The wire ({}: {}) has already been initialized before. {}",
+                *type_id, id, SSA_VIOLATION
             ));
         }
     }
@@ -980,8 +1072,8 @@ impl Validator {
     fn ensure_not_deleted(&mut self, type_id: &TypeId, id: WireId) {
         if self.deleted_wires.contains(&(*type_id, id)) {
             self.violate(format!(
-                "The wire ({}: {}) has been deleted before. It is no longer possible to re-use it.",
-                *type_id, id
+                "The wire ({}: {}) has been deleted before. {}",
+                *type_id, id, DELETED_WIRE_SET_VIOLATION
             ));
         }
     }
@@ -1181,6 +1273,137 @@ impl Validator {
     }
 }
 
+/// A PluginValidator checks that a PluginBody is compliant with the specifications of the plugin
+/// (operations, number of params, number of public/private inputs consumed).
+pub trait PluginValidator {
+    fn validate_body(&self, body: &PluginBody) -> Result<()>;
+}
+
+/// StandardPluginValidator knows the plugins implemented in this crate:
+/// `zkif_vector` (add/mul), `zkif_assert_equal` (public/private) and `zkif_ring` (add/mul/equal),
+/// and the `iter` plugin (map/reduce).
+/// Any other plugin is rejected.
+/// Params are checked by each plugin (e.g. a type id may be 0, and `iter` takes a function name),
+/// so `PluginBody::validate` is not called.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StandardPluginValidator;
+
+impl StandardPluginValidator {
+    /// Returns the params of `body` as numbers, after checking their count.
+    fn numeric_params(body: &PluginBody, expected: &[&str]) -> Result<Vec<u64>> {
+        if body.params.len() != expected.len() {
+            return Err(format!(
+                "plugin({}, {}) must be declared with {} params ({}).",
+                body.name,
+                body.operation,
+                expected.len(),
+                expected.join(", ")
+            )
+            .into());
+        }
+        body.params
+            .iter()
+            .map(|param| extract_number(param))
+            .collect()
+    }
+
+    fn check_counts(
+        body: &PluginBody,
+        public_count: &BTreeMap<TypeId, u64>,
+        private_count: &BTreeMap<TypeId, u64>,
+    ) -> Result<()> {
+        if body.public_count != *public_count || body.private_count != *private_count {
+            return Err(format!(
+                "plugin({}, {}) must consume {:?} public and {:?} private inputs (and not {:?} and {:?}).",
+                body.name,
+                body.operation,
+                public_count,
+                private_count,
+                body.public_count,
+                body.private_count
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
+
+impl PluginValidator for StandardPluginValidator {
+    fn validate_body(&self, body: &PluginBody) -> Result<()> {
+        body.validate_name_and_operation()?;
+        let no_inputs = BTreeMap::new();
+        match (body.name.as_str(), body.operation.as_str()) {
+            ("zkif_vector", "add") | ("zkif_vector", "mul") => {
+                let params = Self::numeric_params(body, &["type_id", "length"])?;
+                TypeId::try_from(params[0])?;
+                if params[1] == 0 {
+                    return Err(
+                        "plugin(zkif_vector, add/mul) cannot be called without inputs.".into(),
+                    );
+                }
+                Self::check_counts(body, &no_inputs, &no_inputs)
+            }
+            ("zkif_assert_equal", operation @ "public")
+            | ("zkif_assert_equal", operation @ "private") => {
+                let params = Self::numeric_params(body, &["type_id", "length"])?;
+                let type_id = TypeId::try_from(params[0])?;
+                if params[1] == 0 {
+                    return Err(
+                        "plugin(zkif_assert_equal, public/private) cannot be called without inputs."
+                            .into(),
+                    );
+                }
+                let mut inputs = BTreeMap::new();
+                inputs.insert(type_id, params[1]);
+                if operation == "public" {
+                    Self::check_counts(body, &inputs, &no_inputs)
+                } else {
+                    Self::check_counts(body, &no_inputs, &inputs)
+                }
+            }
+            ("zkif_ring", "add") | ("zkif_ring", "mul") | ("zkif_ring", "equal") => {
+                let params = Self::numeric_params(body, &["type_id"])?;
+                TypeId::try_from(params[0])?;
+                Self::check_counts(body, &no_inputs, &no_inputs)
+            }
+            ("iter", "map") | ("iter", "reduce") => {
+                if body.params.len() != 2 || body.params[0].is_empty() {
+                    return Err(format!(
+                        "plugin(iter, {}) must be declared with 2 params (function, count).",
+                        body.operation
+                    )
+                    .into());
+                }
+                let count = extract_number(&body.params[1])?;
+                if count == 0 {
+                    return Err(format!(
+                        "plugin(iter, {}) cannot be called with 0 iterations.",
+                        body.operation
+                    )
+                    .into());
+                }
+                // Each iteration consumes the public/private inputs of the body function.
+                if body
+                    .public_count
+                    .values()
+                    .chain(body.private_count.values())
+                    .any(|inputs_count| inputs_count % count != 0)
+                {
+                    return Err(format!(
+                        "plugin(iter, {}) must consume a multiple of {} public and private inputs.",
+                        body.operation, count
+                    )
+                    .into());
+                }
+                Ok(())
+            }
+            (name, operation) => {
+                Err(format!("Unknown plugin operation plugin({}, {}).", name, operation).into())
+            }
+        }
+    }
+}
+
 #[test]
 fn test_validator() {
     use crate::producers::examples::*;
@@ -1202,6 +1425,66 @@ fn test_validator() {
     assert_eq!(validator.get_violations(), Vec::<String>::new());
 }
 
+#[test]
+fn test_validator_live_wire_count() {
+    use crate::producers::examples::*;
+
+    let mut validator = Validator::new_as_prover();
+    assert_eq!(validator.max_live_wire_count(), 0);
+
+    example_public_inputs()
+        .iter()
+        .for_each(|inputs| validator.ingest_public_inputs(inputs));
+    example_private_inputs()
+        .iter()
+        .for_each(|inputs| validator.ingest_private_inputs(inputs));
+    validator.ingest_relation(&example_relation());
+
+    assert!(validator.max_live_wire_count() > 0);
+    assert!(validator.max_live_wire_count() < 20);
+    assert!(validator.current_live_wire_count() <= validator.max_live_wire_count());
+    assert_eq!(validator.get_violations(), Vec::<String>::new());
+}
+
+#[test]
+fn test_validator_ingest_all() {
+    use crate::producers::examples::*;
+    use crate::producers::sink::MemorySink;
+    use crate::Sink;
+
+    let mut messages = vec![];
+    example_public_inputs()
+        .into_iter()
+        .for_each(|inputs| messages.push(Message::PublicInputs(inputs)));
+    example_private_inputs()
+        .into_iter()
+        .for_each(|inputs| messages.push(Message::PrivateInputs(inputs)));
+    messages.push(Message::Relation(example_relation()));
+    assert_eq!(Validator::ingest_all(messages.iter()), Vec::<String>::new());
+
+    // Without private inputs, some private inputs are missing
+    assert!(!Validator::ingest_all(
+        messages
+            .iter()
+            .filter(|msg| !matches!(msg, Message::PrivateInputs(_)))
+    )
+    .is_empty());
+
+    let mut sink = MemorySink::default();
+    example_public_inputs()
+        .iter()
+        .for_each(|inputs| sink.push_public_inputs_message(inputs).unwrap());
+    example_private_inputs()
+        .iter()
+        .for_each(|inputs| sink.push_private_inputs_message(inputs).unwrap());
+    sink.push_relation_message(&example_relation()).unwrap();
+    let source: Source = sink.into();
+    assert_eq!(
+        Validator::ingest_all_from_source(&source),
+        Vec::<String>::new()
+    );
+}
+
 #[test]
 fn test_validator_as_verifier() {
     use crate::producers::examples::*;
@@ -1449,6 +1732,139 @@ fn test_validator_convert_violations() {
     );
 }
 
+#[test]
+fn test_plugin_validator() {
+    use crate::producers::examples::example_relation;
+
+    let validator = StandardPluginValidator::default();
+    let mut plugin_bodies = 0;
+    for directive in example_relation().directives.iter() {
+        if let Directive::Function(function) = directive {
+            if let FunctionBody::PluginBody(body) = &function.body {
+                assert!(validator.validate_body(body).is_ok());
+                plugin_bodies += 1;
+            }
+        }
+    }
+    assert!(plugin_bodies > 0);
+
+    let vector_add = PluginBody::new(
+        "zkif_vector".to_string(),
+        "add".to_string(),
+        vec!["0".to_string(), "0x10".to_string()],
+        BTreeMap::new(),
+        BTreeMap::new(),
+    );
+    // The type id 0 is not a positive number, but is a valid param of zkif_vector
+    assert!(vector_add.validate().is_err());
+    assert!(validator.validate_body(&vector_add).is_ok());
+    let mut body = vector_add.clone();
+    body.params[0] = "1".to_string();
+    assert!(body.validate().is_ok());
+
+    // Generic checks
+    let mut body = vector_add.clone();
+    body.name = String::new();
+    assert!(body.validate().is_err());
+    let mut body = vector_add.clone();
+    body.operation = String::new();
+    assert!(body.validate().is_err());
+    let mut body = vector_add.clone();
+    body.params[1] = "-3".to_string();
+    assert!(body.validate().is_err());
+    assert!(validator.validate_body(&body).is_err());
+
+    // Plugin specific checks
+    let mut body = vector_add.clone();
+    body.params.pop();
+    assert!(validator.validate_body(&body).is_err());
+    let mut body = vector_add.clone();
+    body.params[0] = "256".to_string();
+    assert!(validator.validate_body(&body).is_err());
+    let mut body = vector_add.clone();
+    body.private_count.insert(0, 16);
+    assert!(validator.validate_body(&body).is_err());
+    let mut body = vector_add;
+    body.operation = "sub".to_string();
+    assert!(validator.validate_body(&body).is_err());
+
+    let mut private_count = BTreeMap::new();
+    private_count.insert(1, 2);
+    let assert_equal = PluginBody::new(
+        "zkif_assert_equal".to_string(),
+        "private".to_string(),
+        vec!["1".to_string(), "2".to_string()],
+        BTreeMap::new(),
+        private_count.clone(),
+    );
+    assert!(validator.validate_body(&assert_equal).is_ok());
+    let mut body = assert_equal;
+    body.operation = "public".to_string();
+    assert!(validator.validate_body(&body).is_err());
+    body.public_count = private_count;
+    body.private_count.clear();
+    assert!(validator.validate_body(&body).is_ok());
+}
+
+#[test]
+fn test_plugin_validator_iter() -> Result<()> {
+    use crate::producers::builder::{BuildGate::*, GateBuilder};
+    use crate::producers::sink::MemorySink;
+    use crate::structs::wirerange::WireRange;
+
+    let mut b = GateBuilder::new(
+        MemorySink::default(),
+        &["iter".to_string()],
+        &[Type::Field(vec![101])],
+        &[],
+    );
+    // private_square() = x * x with x a private input
+    let mut fb =
+        b.new_function_builder("private_square".to_string(), vec![Count::new(0, 1)], vec![]);
+    let x = fb.create_gate(Private(0, None));
+    let x_squared = fb.create_gate(Mul(0, x, x));
+    let private_square = fb.finish(vec![WireRange::new(x_squared, x_squared)])?;
+    b.push_function(private_square)?;
+    b.push_iter_plugin(
+        "private_square_3".to_string(),
+        "private_square".to_string(),
+        3,
+        vec![Count::new(0, 3)],
+        vec![],
+    )?;
+
+    let source: crate::Source = b.finish().into();
+    let relation = &source.read_all_messages()?.relations[0];
+    let iter_body = relation
+        .directives
+        .iter()
+        .find_map(|directive| match directive {
+            Directive::Function(function) => match &function.body {
+                FunctionBody::PluginBody(body) => Some(body.clone()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .unwrap();
+
+    let validator = StandardPluginValidator::default();
+    assert!(validator.validate_body(&iter_body).is_ok());
+
+    let mut body = iter_body.clone();
+    body.operation = "filter".to_string();
+    assert!(validator.validate_body(&body).is_err());
+    let mut body = iter_body.clone();
+    body.params.pop();
+    assert!(validator.validate_body(&body).is_err());
+    let mut body = iter_body.clone();
+    body.params[1] = "0".to_string();
+    assert!(validator.validate_body(&body).is_err());
+    let mut body = iter_body;
+    body.private_count.insert(0, 4);
+    assert!(validator.validate_body(&body).is_err());
+    Ok(())
+}
+
 pub fn is_probably_prime(value: &BigUint) -> bool {
     let value = num_bigint_dig::BigUint::from_bytes_le(&value.to_bytes_le());
     probably_prime(&value, 10)
@@ -1754,3 +2170,69 @@ fn test_validator_with_new_delete_on_one_wire() {
 
     assert_eq!(validator.get_violations(), Vec::<String>::new());
 }
+
+#[test]
+fn test_check_ssa_and_wire_liveness() -> Result<()> {
+    use crate::consumers::evaluator::Evaluator;
+    use crate::consumers::flattening::IRFlattener;
+    use crate::producers::examples::example_relation;
+    use crate::producers::simple_examples::*;
+    use crate::producers::sink::MemorySink;
+
+    // The flattened example relation has no Call gate
+    let mut flattener = IRFlattener::new(MemorySink::default());
+    let mut evaluator = Evaluator::default();
+    evaluator.ingest_public_inputs(&simple_example_public_inputs())?;
+    evaluator.ingest_private_inputs(&simple_example_private_inputs())?;
+    evaluator.ingest_relation(&simple_example_relation(), &mut flattener)?;
+    let source: Source = flattener.finish().into();
+    let gates = source.iter_gates().collect::<Result<Vec<_>>>()?;
+    assert_eq!(Validator::check_ssa(&gates), Vec::<String>::new());
+    assert_eq!(Validator::check_wire_liveness(&gates), Vec::<String>::new());
+
+    // The wires of Call gates cannot be typed without the declaration of the called function
+    let gates = example_relation()
+        .directives
+        .into_iter()
+        .filter_map(|directive| match directive {
+            Directive::Gate(gate) => Some(gate),
+            Directive::Function(_) => None,
+        })
+        .take(9)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        Validator::check_ssa(&gates),
+        vec!["Gate 8: Unknown Function gate square"]
+    );
+    assert_eq!(
+        Validator::check_wire_liveness(&gates),
+        vec!["Gate 8: Unknown Function gate square"]
+    );
+
+    let gates = vec![
+        Gate::Private(0, 0),
+        Gate::Constant(0, 1, vec![1]),
+        Gate::Add(0, 1, 0, 0),
+        Gate::Mul(1, 1, 0, 0),
+        Gate::Delete(0, 0, 1),
+        Gate::AssertZero(0, 1),
+        Gate::Delete(0, 2, 2),
+        Gate::Constant(0, 0, vec![1]),
+    ];
+    assert_eq!(
+        Validator::check_ssa(&gates),
+        vec![
+            "Gate 2: The wire (0: 1) has already been initialized before. This violates the SSA property.",
+            "Gate 7: The wire (0: 0) has been deleted before. It is no longer possible to re-use it.",
+        ]
+    );
+    assert_eq!(
+        Validator::check_wire_liveness(&gates),
+        vec![
+            "Gate 3: The wire (1: 0) is used but was not assigned a value, or has been deleted already.",
+            "Gate 5: The wire (0: 1) is used but was not assigned a value, or has been deleted already.",
+            "Gate 6: The wire (0: 2) is used but was not assigned a value, or has been deleted already.",
+        ]
+    );
+    Ok(())
+}
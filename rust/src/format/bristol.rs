@@ -0,0 +1,327 @@
+use std::collections::BTreeMap;
+
+use crate::structs::directives::Directive;
+use crate::structs::types::Type;
+use crate::structs::IR_VERSION;
+use crate::{Gate, Relation, Result, TypeId, WireId};
+
+/// All wires of a BRISTOL circuit belong to the Boolean type (field of characteristic 2).
+const TYPE_ID: TypeId = 0;
+
+/// Parses a line of whitespace-separated numbers.
+fn parse_numbers(line: &str) -> Result<Vec<u64>> {
+    line.split_whitespace()
+        .map(|token| {
+            token
+                .parse::<u64>()
+                .map_err(|_| format!("BRISTOL: '{}' is not a valid number.", token).into())
+        })
+        .collect()
+}
+
+/// Parses a list of the form `n c_1 ... c_n` and returns `[c_1, ..., c_n]`.
+fn parse_wire_counts(line: Option<&str>, what: &str) -> Result<Vec<u64>> {
+    let numbers = parse_numbers(line.ok_or(format!("BRISTOL: missing {} line.", what))?)?;
+    match numbers.split_first() {
+        Some((n, counts)) if *n as usize == counts.len() => Ok(counts.to_vec()),
+        _ => Err(format!("BRISTOL: malformed {} line.", what).into()),
+    }
+}
+
+/// from_bristol parses a circuit in BRISTOL fashion format and converts it into a Relation
+/// over the Boolean type (a Field with modulo 2).
+///
+/// - Input wires are consumed from the private inputs, in order.
+/// - AND, XOR, INV, EQ and EQW gates are converted into Mul, Add, AddConstant, Constant and
+///   Copy gates respectively.
+/// - Output wires are not constrained.
+/// BRISTOL wire ids are kept as is in the Relation.
+pub fn from_bristol(input: &str) -> Result<Relation> {
+    let mut lines = input.lines().map(str::trim).filter(|line| !line.is_empty());
+
+    let header = parse_numbers(lines.next().ok_or("BRISTOL: missing header line.")?)?;
+    if header.len() != 2 {
+        return Err("BRISTOL: the header line must contain the number of gates and wires.".into());
+    }
+    let (gate_count, wire_count) = (header[0], header[1]);
+    let input_count: u64 = parse_wire_counts(lines.next(), "input")?.iter().sum();
+    let output_count: u64 = parse_wire_counts(lines.next(), "output")?.iter().sum();
+    if input_count + output_count > wire_count {
+        return Err("BRISTOL: there are more input and output wires than wires.".into());
+    }
+
+    let check_wire = |wire: WireId| -> Result<WireId> {
+        if wire >= wire_count {
+            Err(format!("BRISTOL: wire {} is out of range.", wire).into())
+        } else {
+            Ok(wire)
+        }
+    };
+
+    let mut directives = (0..input_count)
+        .map(|wire| Directive::Gate(Gate::Private(TYPE_ID, wire)))
+        .collect::<Vec<_>>();
+
+    let mut parsed_gates = 0;
+    for line in lines {
+        let tokens = line.split_whitespace().collect::<Vec<_>>();
+        let (operation, numbers) = tokens.split_last().ok_or("BRISTOL: empty gate line.")?;
+        let numbers = parse_numbers(&numbers.join(" "))?;
+        if numbers.len() < 2 || numbers.len() as u64 != 2 + numbers[0] + numbers[1] {
+            return Err(format!("BRISTOL: malformed gate line '{}'.", line).into());
+        }
+        let ins = &numbers[2..2 + numbers[0] as usize];
+        let outs = &numbers[2 + numbers[0] as usize..];
+
+        let gate = match (*operation, ins, outs) {
+            ("AND", [left, right], [out]) => Gate::Mul(
+                TYPE_ID,
+                check_wire(*out)?,
+                check_wire(*left)?,
+                check_wire(*right)?,
+            ),
+            ("XOR", [left, right], [out]) => Gate::Add(
+                TYPE_ID,
+                check_wire(*out)?,
+                check_wire(*left)?,
+                check_wire(*right)?,
+            ),
+            ("INV", [inp], [out]) => {
+                Gate::AddConstant(TYPE_ID, check_wire(*out)?, check_wire(*inp)?, vec![1])
+            }
+            ("EQ", [constant], [out]) if *constant <= 1 => {
+                Gate::Constant(TYPE_ID, check_wire(*out)?, vec![*constant as u8])
+            }
+            ("EQW", [inp], [out]) => Gate::Copy(TYPE_ID, check_wire(*out)?, check_wire(*inp)?),
+            _ => return Err(format!("BRISTOL: unsupported gate '{}'.", line).into()),
+        };
+        directives.push(Directive::Gate(gate));
+        parsed_gates += 1;
+    }
+
+    if parsed_gates != gate_count {
+        return Err(format!(
+            "BRISTOL: the header announces {} gates but {} gates were found.",
+            gate_count, parsed_gates
+        )
+        .into());
+    }
+
+    Ok(Relation {
+        version: IR_VERSION.to_string(),
+        plugins: vec![],
+        types: vec![Type::Field(vec![2])],
+        conversions: vec![],
+        directives,
+    })
+}
+
+/// Returns the parity of a little-endian value, i.e. its value in the Boolean field.
+fn parity(value: &[u8]) -> u8 {
+    value.first().map(|byte| byte & 1).unwrap_or(0)
+}
+
+/// to_bristol converts a Relation over the Boolean type (a Field with modulo 2) into a
+/// circuit in BRISTOL fashion format.
+///
+/// - Public inputs then private inputs are mapped to the first BRISTOL wires.
+/// - Gates are linearized in the order of the Relation and output wires are numbered sequentially.
+/// - Each wire asserted to be zero becomes an output of the BRISTOL circuit
+///   (the circuit is satisfied if all outputs are 0).
+/// It returns an error if the relation uses another type, functions or conversions.
+pub fn to_bristol(relation: &Relation) -> Result<String> {
+    if relation.types != [Type::Field(vec![2])] {
+        return Err("BRISTOL: only relations over the Boolean type can be exported.".into());
+    }
+
+    let mut wire_map: BTreeMap<WireId, u64> = BTreeMap::new();
+
+    // Inputs must be the first wires of a BRISTOL circuit.
+    let mut input_counts = vec![];
+    for is_public in [true, false] {
+        let mut count = 0;
+        for directive in relation.directives.iter() {
+            let out = match directive {
+                Directive::Gate(Gate::Public(_, out)) if is_public => out,
+                Directive::Gate(Gate::Private(_, out)) if !is_public => out,
+                _ => continue,
+            };
+            wire_map.insert(*out, wire_map.len() as u64);
+            count += 1;
+        }
+        if count > 0 {
+            input_counts.push(count.to_string());
+        }
+    }
+
+    let mut gates: Vec<String> = vec![];
+    let mut asserted: Vec<u64> = vec![];
+    for directive in relation.directives.iter() {
+        let gate = match directive {
+            Directive::Gate(gate) => gate,
+            Directive::Function(function) => {
+                return Err(
+                    format!("BRISTOL: function {} cannot be exported.", function.name).into(),
+                )
+            }
+        };
+        let get = |wire: &WireId| -> Result<u64> {
+            wire_map
+                .get(wire)
+                .cloned()
+                .ok_or_else(|| format!("BRISTOL: wire {} is not defined.", wire).into())
+        };
+        let next = wire_map.len() as u64;
+        let (out, line) = match gate {
+            Gate::Constant(_, out, value) => (out, format!("1 1 {} {} EQ", parity(value), next)),
+            Gate::Copy(_, out, inp) => (out, format!("1 1 {} {} EQW", get(inp)?, next)),
+            Gate::Add(_, out, left, right) => (
+                out,
+                format!("2 1 {} {} {} XOR", get(left)?, get(right)?, next),
+            ),
+            Gate::Mul(_, out, left, right) => (
+                out,
+                format!("2 1 {} {} {} AND", get(left)?, get(right)?, next),
+            ),
+            Gate::AddConstant(_, out, inp, value) => {
+                let operation = if parity(value) == 1 { "INV" } else { "EQW" };
+                (out, format!("1 1 {} {} {}", get(inp)?, next, operation))
+            }
+            Gate::MulConstant(_, out, inp, value) => {
+                if parity(value) == 1 {
+                    (out, format!("1 1 {} {} EQW", get(inp)?, next))
+                } else {
+                    (out, format!("1 1 0 {} EQ", next))
+                }
+            }
+            Gate::AssertZero(_, inp) => {
+                asserted.push(get(inp)?);
+                continue;
+            }
+            // Inputs have already been allocated
+            Gate::Public(_, _) | Gate::Private(_, _) => continue,
+            // Memory management is meaningless in BRISTOL
+            Gate::New(_, _, _) | Gate::Delete(_, _, _) => continue,
+            Gate::Convert(_, _, _, _, _, _) | Gate::Call(_, _, _) => {
+                return Err("BRISTOL: Convert and Call gates cannot be exported.".into());
+            }
+        };
+        if wire_map.insert(*out, next).is_some() {
+            return Err(format!("BRISTOL: wire {} is defined twice.", out).into());
+        }
+        gates.push(line);
+    }
+
+    // Outputs must be the last wires of a BRISTOL circuit.
+    let mut wire_count = wire_map.len() as u64;
+    for wire in asserted.iter() {
+        gates.push(format!("1 1 {} {} EQW", wire, wire_count));
+        wire_count += 1;
+    }
+
+    let mut bristol = format!("{} {}\n", gates.len(), wire_count);
+    input_counts.insert(0, input_counts.len().to_string());
+    bristol += &input_counts.join(" ");
+    if asserted.is_empty() {
+        bristol += "\n0\n\n";
+    } else {
+        bristol += &format!("\n1 {}\n\n", asserted.len());
+    }
+    for gate in gates {
+        bristol += &gate;
+        bristol += "\n";
+    }
+    Ok(bristol)
+}
+
+#[cfg(test)]
+/// A full adder in BRISTOL fashion format.
+/// Inputs: a (wire 0), b (wire 1), carry_in (wire 2).
+/// Outputs: sum (wire 6), carry_out (wire 7).
+const FULL_ADDER: &str = "5 8
+3 1 1 1
+2 1 1
+
+2 1 0 1 3 XOR
+2 1 0 1 4 AND
+2 1 3 2 5 AND
+2 1 3 2 6 XOR
+2 1 4 5 7 XOR
+";
+
+#[test]
+fn test_from_bristol() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::PrivateInputs;
+    use num_traits::ToPrimitive;
+
+    let relation = from_bristol(FULL_ADDER)?;
+    assert_eq!(relation.types, vec![Type::Field(vec![2])]);
+    // 3 private inputs and 5 gates
+    assert_eq!(relation.directives.len(), 8);
+
+    for (a, b, carry_in) in [(0, 0, 0), (1, 0, 1), (1, 1, 0), (1, 1, 1)] {
+        let private_inputs = PrivateInputs {
+            version: IR_VERSION.to_string(),
+            type_value: Type::Field(vec![2]),
+            inputs: vec![vec![a], vec![b], vec![carry_in]],
+        };
+        let mut backend = PlaintextBackend::default();
+        let mut evaluator = Evaluator::default();
+        evaluator.ingest_private_inputs(&private_inputs)?;
+        evaluator.ingest_relation(&relation, &mut backend)?;
+
+        let total = a + b + carry_in;
+        assert_eq!(evaluator.get(0, 6)?.to_u8().unwrap(), total % 2);
+        assert_eq!(evaluator.get(0, 7)?.to_u8().unwrap(), total / 2);
+    }
+
+    // Unsupported gates and wrong gate counts are rejected
+    assert!(from_bristol("1 3\n2 1 1\n1 1\n\n2 1 0 1 2 OR\n").is_err());
+    assert!(from_bristol("2 3\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n").is_err());
+    assert!(from_bristol("1 2\n1 1\n1 1\n\n1 1 0 1 INV\n").is_ok());
+    Ok(())
+}
+
+#[test]
+fn test_to_bristol() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::PrivateInputs;
+
+    // Prove that we know x and y such that x XOR y = 0
+    let relation = Relation {
+        version: IR_VERSION.to_string(),
+        plugins: vec![],
+        types: vec![Type::Field(vec![2])],
+        conversions: vec![],
+        directives: vec![
+            Directive::Gate(Gate::Private(0, 10)),
+            Directive::Gate(Gate::Private(0, 11)),
+            Directive::Gate(Gate::Add(0, 12, 10, 11)),
+            Directive::Gate(Gate::AssertZero(0, 12)),
+        ],
+    };
+    let bristol = to_bristol(&relation)?;
+    assert_eq!(bristol, "2 4\n1 2\n1 1\n\n2 1 0 1 2 XOR\n1 1 2 3 EQW\n");
+
+    // The BRISTOL circuit can be re-imported and evaluated
+    let reimported = from_bristol(&bristol)?;
+    for (x, y) in [(0, 0), (0, 1), (1, 1)] {
+        let private_inputs = PrivateInputs {
+            version: IR_VERSION.to_string(),
+            type_value: Type::Field(vec![2]),
+            inputs: vec![vec![x], vec![y]],
+        };
+        let mut backend = PlaintextBackend::default();
+        let mut evaluator = Evaluator::default();
+        evaluator.ingest_private_inputs(&private_inputs)?;
+        evaluator.ingest_relation(&reimported, &mut backend)?;
+        assert_eq!(*evaluator.get(0, 3)?, num_bigint::BigUint::from(x ^ y));
+    }
+
+    // Non-Boolean relations cannot be exported
+    let mut arithmetic_relation = relation;
+    arithmetic_relation.types = vec![Type::Field(vec![101])];
+    assert!(to_bristol(&arithmetic_relation).is_err());
+    Ok(())
+}
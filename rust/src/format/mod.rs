@@ -0,0 +1,2 @@
+/// Import and export of Boolean circuits in the BRISTOL fashion format.
+pub mod bristol;
@@ -37,6 +37,9 @@ pub mod consumers;
 /// Tools and helpers to evaluate plugins.
 pub mod plugins;
 
+/// Conversions between SIEVE IR and other circuit formats.
+pub mod format;
+
 // Exports.
 pub use consumers::source::Source;
 pub use producers::sink::{clean_workspace, FilesSink, Sink};
@@ -1,9 +1,13 @@
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::convert::TryFrom;
 use std::mem::take;
 
+use num_bigint::BigUint;
+use num_traits::One;
+
 use super::build_gates::NO_OUTPUT;
 pub use super::build_gates::{BuildComplexGate, BuildGate};
+use crate::consumers::analysis::{call_graph_cycles, called_functions, format_cycle};
 use crate::producers::sink::MemorySink;
 use crate::structs::conversion::Conversion;
 use crate::structs::count::Count;
@@ -12,9 +16,10 @@ use crate::structs::function::{Function, FunctionBody, FunctionCounts};
 use crate::structs::gates::replace_output_wires;
 use crate::structs::plugin::PluginBody;
 use crate::structs::types::Type;
-use crate::structs::value::Value;
+use crate::structs::value::{value_to_biguint, Value};
+use crate::structs::value_arithmetic::{value_add, value_lt_modulus, value_mul};
 use crate::structs::wirerange::{
-    add_types_to_wire_ranges, check_wire_ranges_with_counts, WireRange,
+    add_types_to_wire_ranges, check_wire_ranges_with_counts, WireRange, WireRangeWithType,
 };
 use crate::structs::IR_VERSION;
 use crate::Result;
@@ -37,14 +42,41 @@ pub trait GateBuilderT {
     ) -> Result<Vec<WireRange>>;
 }
 
+/// TypedInputBuffer holds the public or private input messages being built, one per type,
+/// and the number of values of each type to hold before flushing a message, when it differs
+/// from the default `max_len` (see `GateBuilder::set_per_type_max_len`).
+struct TypedInputBuffer<I> {
+    buffers: BTreeMap<TypeId, I>,
+    max_lens: BTreeMap<TypeId, usize>,
+}
+
+impl<I> Default for TypedInputBuffer<I> {
+    fn default() -> Self {
+        TypedInputBuffer {
+            buffers: BTreeMap::new(),
+            max_lens: BTreeMap::new(),
+        }
+    }
+}
+
+impl<I> TypedInputBuffer<I> {
+    /// Returns the number of values of type `type_id` to hold before flushing a message.
+    fn max_len(&self, type_id: TypeId, default_max_len: usize) -> usize {
+        self.max_lens
+            .get(&type_id)
+            .cloned()
+            .unwrap_or(default_max_len)
+    }
+}
+
 /// MessageBuilder builds messages by buffering sequences of gates and public/private values.
 /// Flush completed messages to a Sink.
 /// finish() must be called.
 struct MessageBuilder<S: Sink> {
     sink: S,
 
-    public_inputs: BTreeMap<TypeId, PublicInputs>,
-    private_inputs: BTreeMap<TypeId, PrivateInputs>,
+    public_inputs: TypedInputBuffer<PublicInputs>,
+    private_inputs: TypedInputBuffer<PrivateInputs>,
     relation: Relation,
 
     /// Types
@@ -53,6 +85,15 @@ struct MessageBuilder<S: Sink> {
     /// Current size (sum of the number of gates) of the relation's functions vector
     functions_size: usize,
 
+    /// Number of messages pushed to the sink so far
+    flushed_messages: usize,
+
+    /// Number of gates pushed so far, including those already flushed
+    gates_count: usize,
+
+    /// Whether the first relation message (declaring plugins, types and conversions) has been flushed
+    relation_header_flushed: bool,
+
     /// Maximum number of gates or public or private values to hold at once.
     /// Default 100,000 or ~12MB of memory.
     /// Size estimation: 40 per public_input + 40 per private_input + 48 per gate = 128 bytes.
@@ -63,8 +104,8 @@ impl<S: Sink> MessageBuilder<S> {
     fn new(sink: S, plugins: &[String], types: &[Type], conversions: &[Conversion]) -> Self {
         Self {
             sink,
-            public_inputs: BTreeMap::new(),
-            private_inputs: BTreeMap::new(),
+            public_inputs: TypedInputBuffer::default(),
+            private_inputs: TypedInputBuffer::default(),
             types: types.to_vec(),
             relation: Relation {
                 version: IR_VERSION.to_string(),
@@ -74,10 +115,23 @@ impl<S: Sink> MessageBuilder<S> {
                 directives: vec![],
             },
             functions_size: 0,
+            flushed_messages: 0,
+            gates_count: 0,
+            relation_header_flushed: false,
             max_len: 100 * 1000,
         }
     }
 
+    /// Appends `type_value` to the types. It is declared in the next relation message flushed,
+    /// after the types declared by the previous relation messages.
+    fn push_type(&mut self, type_value: Type) -> Result<TypeId> {
+        let type_id = TypeId::try_from(self.types.len())
+            .map_err(|_| format!("Cannot add more than {} types.", self.types.len()))?;
+        self.types.push(type_value.clone());
+        self.relation.types.push(type_value);
+        Ok(type_id)
+    }
+
     fn push_public_input_value(&mut self, type_id: TypeId, value: Value) -> Result<()> {
         let type_value = self.types.get(usize::try_from(type_id)?).ok_or(format!(
             "When pushing a public input value, the type id ({}) is unknown.",
@@ -85,14 +139,19 @@ impl<S: Sink> MessageBuilder<S> {
         ))?;
 
         // Push value into the PublicInputs corresponding to type_id
-        let public_input = self.public_inputs.entry(type_id).or_insert(PublicInputs {
-            version: self.relation.version.clone(),
-            type_value: type_value.clone(),
-            inputs: vec![],
-        });
+        let max_len = self.public_inputs.max_len(type_id, self.max_len);
+        let public_input = self
+            .public_inputs
+            .buffers
+            .entry(type_id)
+            .or_insert(PublicInputs {
+                version: self.relation.version.clone(),
+                type_value: type_value.clone(),
+                inputs: vec![],
+            });
         public_input.inputs.push(value);
 
-        if public_input.inputs.len() == self.max_len {
+        if public_input.inputs.len() >= max_len {
             self.flush_public_inputs(type_id);
         }
         Ok(())
@@ -105,14 +164,19 @@ impl<S: Sink> MessageBuilder<S> {
         ))?;
 
         // Push value into the PrivateInputs corresponding to type_id
-        let private_input = self.private_inputs.entry(type_id).or_insert(PrivateInputs {
-            version: self.relation.version.clone(),
-            type_value: type_value.clone(),
-            inputs: vec![],
-        });
+        let max_len = self.private_inputs.max_len(type_id, self.max_len);
+        let private_input = self
+            .private_inputs
+            .buffers
+            .entry(type_id)
+            .or_insert(PrivateInputs {
+                version: self.relation.version.clone(),
+                type_value: type_value.clone(),
+                inputs: vec![],
+            });
         private_input.inputs.push(value);
 
-        if private_input.inputs.len() == self.max_len {
+        if private_input.inputs.len() >= max_len {
             self.flush_private_inputs(type_id);
         }
         Ok(())
@@ -120,6 +184,7 @@ impl<S: Sink> MessageBuilder<S> {
 
     fn push_gate(&mut self, gate: Gate) {
         self.relation.directives.push(Directive::Gate(gate));
+        self.gates_count += 1;
         if self.relation.directives.len()
             + self.relation.plugins.len()
             + self.relation.conversions.len()
@@ -150,15 +215,17 @@ impl<S: Sink> MessageBuilder<S> {
     fn flush_public_inputs(&mut self, type_id: TypeId) {
         let type_value_opt = self.types.get(usize::try_from(type_id).unwrap());
         if let Some(type_value) = type_value_opt {
-            let public_input =
-                self.public_inputs
-                    .remove(&type_id)
-                    .unwrap_or_else(|| PublicInputs {
-                        version: IR_VERSION.to_string(),
-                        type_value: type_value.clone(),
-                        inputs: vec![],
-                    });
+            let public_input = self
+                .public_inputs
+                .buffers
+                .remove(&type_id)
+                .unwrap_or_else(|| PublicInputs {
+                    version: IR_VERSION.to_string(),
+                    type_value: type_value.clone(),
+                    inputs: vec![],
+                });
             self.sink.push_public_inputs_message(&public_input).unwrap();
+            self.flushed_messages += 1;
         }
     }
 
@@ -170,17 +237,19 @@ impl<S: Sink> MessageBuilder<S> {
     fn flush_private_inputs(&mut self, type_id: TypeId) {
         let type_value_opt = self.types.get(usize::try_from(type_id).unwrap());
         if let Some(type_value) = type_value_opt {
-            let private_input =
-                self.private_inputs
-                    .remove(&type_id)
-                    .unwrap_or_else(|| PrivateInputs {
-                        version: IR_VERSION.to_string(),
-                        type_value: type_value.clone(),
-                        inputs: vec![],
-                    });
+            let private_input = self
+                .private_inputs
+                .buffers
+                .remove(&type_id)
+                .unwrap_or_else(|| PrivateInputs {
+                    version: IR_VERSION.to_string(),
+                    type_value: type_value.clone(),
+                    inputs: vec![],
+                });
             self.sink
                 .push_private_inputs_message(&private_input)
                 .unwrap();
+            self.flushed_messages += 1;
         }
     }
 
@@ -191,6 +260,8 @@ impl<S: Sink> MessageBuilder<S> {
 
     fn flush_relation(&mut self) {
         self.sink.push_relation_message(&self.relation).unwrap();
+        self.flushed_messages += 1;
+        self.relation_header_flushed = true;
         self.relation.plugins.clear();
         self.relation.types.clear();
         self.relation.conversions.clear();
@@ -230,6 +301,25 @@ pub struct GateBuilder<S: Sink> {
     known_plugins: BTreeSet<String>,
     known_conversions: BTreeSet<Conversion>,
     next_available_id: BTreeMap<TypeId, WireId>,
+
+    // Number of Mul gates created at top-level or inside the bodies of pushed functions
+    mul_count: usize,
+    // name => names of the functions called by this function
+    call_graph: BTreeMap<String, BTreeSet<String>>,
+    // Symbolic names given to wires for debugging (not serialized)
+    aliases: BTreeMap<String, (TypeId, WireId)>,
+    // Human-readable names of the types, used in error messages (not serialized)
+    type_names: Vec<String>,
+    // Whether create_gate checks that constants are elements of their Field type
+    validate_constants: bool,
+    // Private input values used for Private gates created without a value (prover mode)
+    prover_context: Option<ProverContext>,
+    // Values of the wires computable from the input values and constants (see wire_value)
+    wire_values: BTreeMap<(TypeId, WireId), Value>,
+    // (label, number of gates created before the checkpoint)
+    checkpoints: Vec<(String, usize)>,
+    // Called with each gate created by create_gate and its output wire (see with_gate_callback)
+    gate_callback: Option<Box<dyn Fn(&Gate, WireId)>>,
 }
 
 pub fn create_plugin_function(
@@ -286,11 +376,23 @@ impl<S: Sink> GateBuilderT for GateBuilder<S> {
             )
             .into());
         }
+        if self.validate_constants {
+            self.check_constant(&gate)?;
+        }
+        if let (BuildGate::Private(_, value @ None), Some(context)) =
+            (&mut gate, &mut self.prover_context)
+        {
+            *value = Some(context.pop_witness(type_id, &self.type_names)?);
+        }
+        let out_value = self.gate_value(&gate);
         let out_id = if gate.has_output() {
             alloc(type_id, &mut self.next_available_id)
         } else {
             NO_OUTPUT
         };
+        if let Some(value) = out_value {
+            self.wire_values.insert((type_id, out_id), value);
+        }
 
         match gate {
             BuildGate::Public(_, Some(ref mut value)) => {
@@ -299,10 +401,25 @@ impl<S: Sink> GateBuilderT for GateBuilder<S> {
             BuildGate::Private(_, Some(ref mut value)) => {
                 self.push_private_input_value(type_id, take(value))?;
             }
+            BuildGate::Mul(_, _, _) => self.mul_count += 1,
+            BuildGate::Delete(_, first_id, last_id) => {
+                let deleted = self
+                    .wire_values
+                    .range((type_id, first_id)..=(type_id, last_id))
+                    .map(|(key, _)| *key)
+                    .collect::<Vec<_>>();
+                for key in deleted {
+                    self.wire_values.remove(&key);
+                }
+            }
             _ => {}
         }
 
-        self.msg_build.push_gate(gate.with_output(out_id));
+        let gate = gate.with_output(out_id);
+        if let Some(callback) = &self.gate_callback {
+            callback(&gate, out_id);
+        }
+        self.msg_build.push_gate(gate);
 
         Ok(out_id)
     }
@@ -409,6 +526,188 @@ impl<S: Sink> GateBuilderT for GateBuilder<S> {
     }
 }
 
+/// ProverContext holds the private input values of the prover, for each type in the order of the
+/// Private gates (see `GateBuilder::new_with_prover_mode`).
+#[derive(Clone, Debug, Default)]
+pub struct ProverContext {
+    pub private_witnesses: BTreeMap<TypeId, VecDeque<Value>>,
+}
+
+impl ProverContext {
+    /// Appends `value` to the private input values of type `type_id`.
+    pub fn push_witness(&mut self, type_id: TypeId, value: Value) {
+        self.private_witnesses
+            .entry(type_id)
+            .or_default()
+            .push_back(value);
+    }
+
+    /// Removes and returns the next private input value of type `type_id`.
+    fn pop_witness(&mut self, type_id: TypeId, type_names: &[String]) -> Result<Value> {
+        self.private_witnesses
+            .get_mut(&type_id)
+            .and_then(VecDeque::pop_front)
+            .ok_or_else(|| {
+                format!(
+                    "The prover context has no more private input values for {}",
+                    describe_type(type_names, type_id)
+                )
+                .into()
+            })
+    }
+}
+
+/// BuilderRegistry contains the known functions, plugins and conversions of a GateBuilder
+/// (see `GateBuilder::clone_registry` and `GateBuilder::new_with_registry`).
+pub type BuilderRegistry = (
+    BTreeMap<String, FunctionCounts>,
+    BTreeSet<String>,
+    BTreeSet<Conversion>,
+);
+
+/// BuilderSnapshot captures the state of a GateBuilder (see `GateBuilder::snapshot`).
+#[derive(Clone, Debug)]
+pub struct BuilderSnapshot {
+    next_available_id: BTreeMap<TypeId, WireId>,
+    directives_len: usize,
+    public_inputs_len: BTreeMap<TypeId, usize>,
+    private_inputs_len: BTreeMap<TypeId, usize>,
+    functions_count: usize,
+    flushed_messages: usize,
+    gates_count: usize,
+    mul_count: usize,
+    types_len: usize,
+    relation_types_len: usize,
+    known_conversions: BTreeSet<Conversion>,
+    relation_conversions_len: usize,
+    aliases: BTreeMap<String, (TypeId, WireId)>,
+    prover_context: Option<ProverContext>,
+    wire_values: BTreeMap<(TypeId, WireId), Value>,
+    checkpoints_len: usize,
+}
+
+/// FunctionOutputs holds the output wire ranges of a Call gate (see `GateBuilder::create_call`),
+/// one per output count of the called function.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FunctionOutputs {
+    pub ranges: Vec<WireRange>,
+}
+
+impl FunctionOutputs {
+    /// Returns the output wire range number `range_index`.
+    pub fn range(&self, range_index: usize) -> Result<WireRange> {
+        self.ranges.get(range_index).cloned().ok_or_else(|| {
+            format!(
+                "The function has {} output ranges, range {} does not exist.",
+                self.ranges.len(),
+                range_index
+            )
+            .into()
+        })
+    }
+
+    /// Returns the single wire of the output range number `range_index`,
+    /// or an error if this range contains several wires.
+    pub fn wire(&self, range_index: usize) -> Result<WireId> {
+        let range = self.range(range_index)?;
+        if range.first_id != range.last_id {
+            return Err(format!(
+                "The output range {} contains several wires ({}..={}).",
+                range_index, range.first_id, range.last_id
+            )
+            .into());
+        }
+        Ok(range.first_id)
+    }
+
+    /// Returns all the wires of the output range number `range_index`.
+    pub fn all_wires(&self, range_index: usize) -> Result<Vec<WireId>> {
+        let range = self.range(range_index)?;
+        Ok((range.first_id..=range.last_id).collect())
+    }
+}
+
+/// CircuitEstimate is the size of the circuit built by a `GateBuilder::dry_run`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct CircuitEstimate {
+    /// Number of top-level gates.
+    pub estimated_gate_count: usize,
+    /// Number of top-level Mul gates, plus the Mul gates in the bodies of the functions
+    /// pushed during the dry run (see `GateBuilder::total_mul_count`).
+    pub estimated_mul_count: usize,
+    /// Number of public input values consumed, including by the called functions.
+    pub estimated_public_input_count: u64,
+    /// Number of private input values consumed, including by the called functions.
+    pub estimated_private_input_count: u64,
+    /// The error returned by the closure of the dry run, if any.
+    /// The estimate then only covers the gates built before the error.
+    pub error: Option<String>,
+}
+
+/// DiscardSink drops all the messages pushed into it.
+struct DiscardSink(std::io::Sink);
+
+impl Sink for DiscardSink {
+    type Write = std::io::Sink;
+
+    fn get_public_inputs_writer(&mut self, _: Type) -> Result<&mut Self::Write> {
+        Ok(&mut self.0)
+    }
+    fn get_private_inputs_writer(&mut self, _: Type) -> Result<&mut Self::Write> {
+        Ok(&mut self.0)
+    }
+    fn get_relation_writer(&mut self) -> &mut Self::Write {
+        &mut self.0
+    }
+}
+
+/// DryRunBuilder is the GateBuilderT given to the closure of `GateBuilder::dry_run`.
+/// It checks and counts the gates like the original builder would, but does not emit them.
+pub struct DryRunBuilder {
+    builder: GateBuilder<DiscardSink>,
+    public_input_count: u64,
+    private_input_count: u64,
+}
+
+impl GateBuilderT for DryRunBuilder {
+    fn create_gate(&mut self, gate: BuildGate) -> Result<WireId> {
+        let (public_count, private_count) = match gate {
+            BuildGate::Public(_, _) => (1, 0),
+            BuildGate::Private(_, _) => (0, 1),
+            _ => (0, 0),
+        };
+        let out_id = self.builder.create_gate(gate)?;
+        self.public_input_count += public_count;
+        self.private_input_count += private_count;
+        Ok(out_id)
+    }
+
+    fn create_complex_gate(
+        &mut self,
+        gate: BuildComplexGate,
+        public_inputs: Vec<Vec<Value>>,
+        private_inputs: Vec<Vec<Value>>,
+    ) -> Result<Vec<WireRange>> {
+        let (public_count, private_count) = match &gate {
+            BuildComplexGate::Call(name, _) => {
+                let counts =
+                    FunctionCounts::get_function_counts(&self.builder.known_functions, name)?;
+                (
+                    counts.public_count.values().sum(),
+                    counts.private_count.values().sum(),
+                )
+            }
+            BuildComplexGate::Convert(_, _, _, _, _) => (0, 0),
+        };
+        let out_ranges = self
+            .builder
+            .create_complex_gate(gate, public_inputs, private_inputs)?;
+        self.public_input_count += public_count;
+        self.private_input_count += private_count;
+        Ok(out_ranges)
+    }
+}
+
 impl<S: Sink> GateBuilder<S> {
     /// new creates a new builder.
     pub fn new(sink: S, plugins: &[String], types: &[Type], conversions: &[Conversion]) -> Self {
@@ -429,7 +728,180 @@ impl<S: Sink> GateBuilder<S> {
             known_conversions,
             known_functions: BTreeMap::new(),
             next_available_id: BTreeMap::new(),
+            mul_count: 0,
+            call_graph: BTreeMap::new(),
+            aliases: BTreeMap::new(),
+            type_names: vec![],
+            validate_constants: true,
+            prover_context: None,
+            wire_values: BTreeMap::new(),
+            checkpoints: vec![],
+            gate_callback: None,
+        }
+    }
+
+    /// new_named creates a new builder over the Field types given as (name, modulo).
+    /// The names are used in error messages (see `with_type_names`).
+    pub fn new_named(sink: S, types: &[(String, Value)]) -> Self {
+        let fields = types
+            .iter()
+            .map(|(_, modulo)| Type::Field(modulo.clone()))
+            .collect::<Vec<_>>();
+        let names = types.iter().map(|(name, _)| name.clone()).collect();
+        Self::new(sink, &[], &fields, &[]).with_type_names(names)
+    }
+
+    /// new_with_prover_mode creates a new builder over the Field types given by their modulo.
+    /// Private gates created without a value take their value from `context`,
+    /// and `create_gate` returns an error once `context` has no more values for their type.
+    pub fn new_with_prover_mode(sink: S, types: &[Value], context: ProverContext) -> Self {
+        let fields = types
+            .iter()
+            .map(|modulo| Type::Field(modulo.clone()))
+            .collect::<Vec<_>>();
+        let mut builder = Self::new(sink, &[], &fields, &[]);
+        builder.prover_context = Some(context);
+        builder
+    }
+
+    /// new_with_registry creates a new builder over the Field types given by their modulo,
+    /// knowing the functions, plugins and conversions of `registry` (see `clone_registry`).
+    /// The plugins and conversions are declared in the relation header, whereas the functions
+    /// are not redefined: the resulting relation must be appended to the relation defining them.
+    pub fn new_with_registry(sink: S, types: &[Value], registry: BuilderRegistry) -> Self {
+        let (known_functions, known_plugins, known_conversions) = registry;
+        let fields = types
+            .iter()
+            .map(|modulo| Type::Field(modulo.clone()))
+            .collect::<Vec<_>>();
+        let plugins = known_plugins.into_iter().collect::<Vec<_>>();
+        let conversions = known_conversions.into_iter().collect::<Vec<_>>();
+        let mut builder = Self::new(sink, &plugins, &fields, &conversions);
+        builder.known_functions = known_functions;
+        builder
+    }
+
+    /// Creates a builder extending an existing circuit.
+    /// `relation`, `public_inputs` and `private_inputs` are first pushed into `sink`,
+    /// and the builder state (known plugins, conversions and functions, next available wire ids)
+    /// is reconstructed from `relation`, such that new gates can be appended to the circuit.
+    /// The input messages of the other types, if any, must be pushed into `sink` beforehand.
+    pub fn from_relation(
+        mut sink: S,
+        relation: &Relation,
+        public_inputs: &PublicInputs,
+        private_inputs: &PrivateInputs,
+    ) -> Result<Self> {
+        sink.push_public_inputs_message(public_inputs)?;
+        sink.push_private_inputs_message(private_inputs)?;
+        sink.push_relation_message(relation)?;
+
+        let mut builder = GateBuilder::new(
+            sink,
+            &relation.plugins,
+            &relation.types,
+            &relation.conversions,
+        );
+        builder.msg_build.flushed_messages = 3;
+        builder.msg_build.relation_header_flushed = true;
+        builder.msg_build.relation.plugins.clear();
+        builder.msg_build.relation.types.clear();
+        builder.msg_build.relation.conversions.clear();
+
+        for directive in relation.directives.iter() {
+            match directive {
+                Directive::Function(function) => {
+                    let (public_count, private_count) =
+                        body_input_counts(&function.body, &builder.known_functions)?;
+                    builder.known_functions.insert(
+                        function.name.clone(),
+                        FunctionCounts {
+                            input_count: function.input_count.clone(),
+                            output_count: function.output_count.clone(),
+                            public_count,
+                            private_count,
+                        },
+                    );
+                    if let FunctionBody::Gates(gates) = &function.body {
+                        builder
+                            .call_graph
+                            .insert(function.name.clone(), called_functions(gates));
+                        builder.mul_count += count_mul_gates(gates);
+                    }
+                }
+                Directive::Gate(gate) => {
+                    for (type_id, last_id) in
+                        allocated_wires(gate, &builder.known_functions)?.into_iter()
+                    {
+                        let next_id = builder.next_available_id.entry(type_id).or_insert(0);
+                        *next_id = (*next_id).max(last_id + 1);
+                    }
+                    if let Gate::Mul(_, _, _, _) = gate {
+                        builder.mul_count += 1;
+                    }
+                }
+            }
+        }
+        Ok(builder)
+    }
+
+    /// Returns the maximum number of gates or public or private values held before flushing
+    /// a message to the sink.
+    pub fn max_len(&self) -> usize {
+        self.msg_build.max_len
+    }
+
+    /// Sets the maximum number of gates or public or private values held before flushing
+    /// a message to the sink (default 100,000).
+    /// It must be called before any gate or value is pushed.
+    pub fn set_max_len(&mut self, max_len: usize) -> Result<()> {
+        if max_len == 0 {
+            return Err("max_len must be positive".into());
+        }
+        let msg_build = &self.msg_build;
+        if msg_build.flushed_messages > 0
+            || !msg_build.relation.directives.is_empty()
+            || !msg_build.public_inputs.buffers.is_empty()
+            || !msg_build.private_inputs.buffers.is_empty()
+        {
+            return Err("max_len cannot be changed once gates or values have been pushed".into());
+        }
+        self.msg_build.max_len = max_len;
+        Ok(())
+    }
+
+    /// Sets the maximum number of public or private values of type `type_id` held before
+    /// flushing a message to the sink, instead of `max_len`. Types whose values are numerous
+    /// can then be flushed in large batches without holding as many values of the other types.
+    /// It can be called at any time: the new limit applies from the next value of this type.
+    pub fn set_per_type_max_len(&mut self, type_id: TypeId, max_len: usize) -> Result<()> {
+        if max_len == 0 {
+            return Err("max_len must be positive".into());
+        }
+        if usize::from(type_id) >= self.msg_build.types.len() {
+            return Err(format!(
+                "Type id {} is not defined, we cannot set its max_len",
+                type_id
+            )
+            .into());
         }
+        self.msg_build
+            .public_inputs
+            .max_lens
+            .insert(type_id, max_len);
+        self.msg_build
+            .private_inputs
+            .max_lens
+            .insert(type_id, max_len);
+        Ok(())
+    }
+
+    /// Appends a new Field type of modulo `modulus` and returns its type id.
+    /// Existing wires are not affected, so the type can be added after gates have been created,
+    /// even once relation messages have been flushed to the sink: the type is then declared
+    /// in the next relation message.
+    pub fn push_type(&mut self, modulus: Value) -> Result<TypeId> {
+        self.msg_build.push_type(Type::Field(modulus))
     }
 
     pub fn new_function_builder(
@@ -439,13 +911,13 @@ impl<S: Sink> GateBuilder<S> {
         input_count: Vec<Count>,
     ) -> FunctionBuilder {
         let mut next_available_id = BTreeMap::new();
-        output_count.iter().for_each(|count| {
-            next_available_id.insert(count.type_id, count.count);
-        });
-        input_count.iter().for_each(|count| {
-            let type_id_count = next_available_id.entry(count.type_id).or_insert(0);
-            *type_id_count += count.count;
-        });
+        output_count
+            .iter()
+            .chain(input_count.iter())
+            .for_each(|count| {
+                let type_id_count = next_available_id.entry(count.type_id).or_insert(0);
+                *type_id_count += count.count;
+            });
         FunctionBuilder {
             name,
             output_count,
@@ -453,12 +925,173 @@ impl<S: Sink> GateBuilder<S> {
             gates: vec![],
             public_count: BTreeMap::new(),
             private_count: BTreeMap::new(),
+            types: &self.msg_build.types,
             known_conversions: &self.known_conversions,
             known_functions: &self.known_functions,
             next_available_id,
         }
     }
 
+    /// Returns the modulo of the type `type_id` if it is a Field.
+    pub(crate) fn get_field_modulo(&self, type_id: TypeId) -> Result<&Value> {
+        field_modulo(&self.msg_build.types, &self.type_names, type_id)
+    }
+
+    /// Returns the value '-1' in the Field type `type_id`.
+    pub(crate) fn minus_one(&self, type_id: TypeId) -> Result<Value> {
+        field_minus_one(&self.msg_build.types, &self.type_names, type_id)
+    }
+
+    /// Returns the value of the wire `wire_id` of type `type_id` if it is known to the builder,
+    /// i.e. if it is computed from the values given to Public and Private gates and from
+    /// constants, only through Copy, Add, Mul, AddConstant and MulConstant gates.
+    /// Gadgets use it to compute the values of the private inputs they create.
+    pub fn wire_value(&self, type_id: TypeId, wire_id: WireId) -> Option<&Value> {
+        self.wire_values.get(&(type_id, wire_id))
+    }
+
+    /// Returns the value of the output wire of `gate` if it can be computed (see `wire_value`).
+    fn gate_value(&self, gate: &BuildGate) -> Option<Value> {
+        let type_id = gate.get_type_id();
+        let modulo = match self.msg_build.types.get(usize::from(type_id)) {
+            Some(Type::Field(modulo)) => modulo,
+            _ => return None,
+        };
+        let known = |wire_id: &WireId| self.wire_value(type_id, *wire_id);
+        match gate {
+            BuildGate::Constant(_, value)
+            | BuildGate::Public(_, Some(value))
+            | BuildGate::Private(_, Some(value)) => Some(value.clone()),
+            BuildGate::Copy(_, input) => known(input).cloned(),
+            BuildGate::Add(_, left, right) => Some(value_add(known(left)?, known(right)?, modulo)),
+            BuildGate::Mul(_, left, right) => Some(value_mul(known(left)?, known(right)?, modulo)),
+            BuildGate::AddConstant(_, input, constant) => {
+                Some(value_add(known(input)?, constant, modulo))
+            }
+            BuildGate::MulConstant(_, input, constant) => {
+                Some(value_mul(known(input)?, constant, modulo))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the id of the first Field type with the modulo `modulo`, if any.
+    pub(crate) fn field_type_id(&self, modulo: &[u8]) -> Option<TypeId> {
+        let field = Type::Field(modulo.to_vec()).cleaned_type();
+        self.msg_build
+            .types
+            .iter()
+            .position(|type_value| type_value.cleaned_type() == field)
+            .and_then(|index| TypeId::try_from(index).ok())
+    }
+
+    /// Gives a human-readable name to each type (in the order of the types).
+    /// Names are only used in error messages, they are not serialized.
+    pub fn with_type_names(mut self, names: Vec<String>) -> Self {
+        self.type_names = names;
+        self
+    }
+
+    /// Calls `callback` with each gate created by `create_gate` and its output wire id
+    /// (`NO_OUTPUT` for gates without output), e.g. to profile or trace the circuit generation.
+    /// Gates created by `create_complex_gate` and gates in function bodies are not reported.
+    pub fn with_gate_callback<F: Fn(&Gate, WireId) + 'static>(mut self, callback: F) -> Self {
+        self.gate_callback = Some(Box::new(callback));
+        self
+    }
+
+    /// Returns the name given to `type_id` with `with_type_names`, if any.
+    pub fn type_name(&self, type_id: TypeId) -> Option<&str> {
+        self.type_names.get(type_id as usize).map(String::as_str)
+    }
+
+    /// Disables the check, done when creating Constant, AddConstant and MulConstant gates,
+    /// that the constant is smaller than the modulo of its Field type.
+    pub fn skip_constant_validation(&mut self) {
+        self.validate_constants = false;
+    }
+
+    /// Returns an error if `gate` holds a constant which is not an element of its Field type.
+    fn check_constant(&self, gate: &BuildGate) -> Result<()> {
+        let (type_id, value) = match gate {
+            BuildGate::Constant(type_id, value)
+            | BuildGate::AddConstant(type_id, _, value)
+            | BuildGate::MulConstant(type_id, _, value) => (*type_id, value),
+            _ => return Ok(()),
+        };
+        if let Some(Type::Field(modulo)) = self.msg_build.types.get(usize::try_from(type_id)?) {
+            if !value_lt_modulus(value, modulo) {
+                return Err(format!(
+                    "The constant {} is not an element of {}",
+                    value_to_biguint(value),
+                    self.describe_type(type_id)
+                )
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Describes `type_id` in error messages (see `with_type_names`).
+    pub(crate) fn describe_type(&self, type_id: TypeId) -> String {
+        describe_type(&self.type_names, type_id)
+    }
+
+    /// Creates a wire holding the constant 0.
+    pub fn create_zero(&mut self, type_id: TypeId) -> Result<WireId> {
+        self.create_gate(BuildGate::Constant(type_id, vec![0]))
+    }
+
+    /// Creates a wire holding the constant 1.
+    pub fn create_one(&mut self, type_id: TypeId) -> Result<WireId> {
+        self.create_gate(BuildGate::Constant(type_id, vec![1]))
+    }
+
+    /// Creates a wire holding the constant -1 (i.e. `p - 1` where `p` is the modulo of
+    /// the Field type `type_id`).
+    pub fn create_neg_one(&mut self, type_id: TypeId) -> Result<WireId> {
+        let minus_one = self.minus_one(type_id)?;
+        self.create_gate(BuildGate::Constant(type_id, minus_one))
+    }
+
+    /// Calls the known function `function_name` on `input_ranges` and returns its outputs,
+    /// to access them by range (see `FunctionOutputs`).
+    /// `public_inputs` and `private_inputs` are the input values consumed by the function
+    /// for each type, as in `create_complex_gate`.
+    pub fn create_call(
+        &mut self,
+        function_name: &str,
+        input_ranges: Vec<WireRange>,
+        public_inputs: Vec<Vec<Value>>,
+        private_inputs: Vec<Vec<Value>>,
+    ) -> Result<FunctionOutputs> {
+        let ranges = self.create_complex_gate(
+            BuildComplexGate::Call(function_name.to_string(), input_ranges),
+            public_inputs,
+            private_inputs,
+        )?;
+        Ok(FunctionOutputs { ranges })
+    }
+
+    /// Returns the next wire id which will be allocated for each type.
+    pub(crate) fn next_available_ids(&self) -> &BTreeMap<TypeId, WireId> {
+        &self.next_available_id
+    }
+
+    pub(crate) fn known_functions(&self) -> &BTreeMap<String, FunctionCounts> {
+        &self.known_functions
+    }
+
+    /// Returns a copy of the known functions, plugins and conversions of this builder,
+    /// to create another builder sharing them (see `new_with_registry`).
+    pub fn clone_registry(&self) -> BuilderRegistry {
+        (
+            self.known_functions.clone(),
+            self.known_plugins.clone(),
+            self.known_conversions.clone(),
+        )
+    }
+
     pub(crate) fn push_private_input_value(&mut self, type_id: TypeId, val: Value) -> Result<()> {
         self.msg_build.push_private_input_value(type_id, val)
     }
@@ -480,6 +1113,23 @@ impl<S: Sink> GateBuilder<S> {
             .into());
         }
 
+        // Check that the function is not recursive
+        let name = &function_with_infos.function.name;
+        if let FunctionBody::Gates(gates) = &function_with_infos.function.body {
+            self.call_graph
+                .insert(name.clone(), called_functions(gates));
+            let cycles = call_graph_cycles(&self.call_graph);
+            if !cycles.is_empty() {
+                self.call_graph.remove(name);
+                return Err(cycles
+                    .iter()
+                    .map(|cycle| format_cycle(cycle))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+                    .into());
+            }
+        }
+
         // Add the function into known_functions
         self.known_functions.insert(
             function_with_infos.function.name.clone(),
@@ -498,6 +1148,10 @@ impl<S: Sink> GateBuilder<S> {
             }
         }
 
+        if let FunctionBody::Gates(gates) = &function_with_infos.function.body {
+            self.mul_count += count_mul_gates(gates);
+        }
+
         // Add the function into the list of functions in the Relation
         self.msg_build.push_function(function_with_infos.function);
         Ok(())
@@ -517,79 +1171,808 @@ impl<S: Sink> GateBuilder<S> {
         }
     }
 
-    pub fn finish(self) -> S {
-        self.msg_build.finish()
+    /// Declares the function `name` whose body is the already built list of `gates`
+    /// (e.g. gates imported from another format), and pushes it (see `push_function`).
+    /// The wires of `gates` are numbered in the scope of the function: the output wires first,
+    /// then the input wires, as for a FunctionBuilder.
+    /// Returns an error if a gate uses a wire which is not an input wire nor set by a previous
+    /// gate, if a wire is set twice, or if an output wire is not set.
+    pub fn register_function_from_gates(
+        &mut self,
+        name: String,
+        output_count: Vec<Count>,
+        input_count: Vec<Count>,
+        gates: Vec<Gate>,
+    ) -> Result<()> {
+        check_function_body_wires(
+            &name,
+            &gates,
+            &output_count,
+            &input_count,
+            &self.known_functions,
+        )?;
+        let function = Function::new(name, output_count, input_count, FunctionBody::Gates(gates));
+        let (public_count, private_count) =
+            body_input_counts(&function.body, &self.known_functions)?;
+        self.push_function(FunctionWithInfos {
+            function,
+            public_count,
+            private_count,
+        })
     }
-}
 
-pub fn new_example_builder() -> GateBuilder<MemorySink> {
-    GateBuilder::new(
-        MemorySink::default(),
-        &[],
-        &[Type::new_field_type(vec![2])],
-        &[],
-    )
-}
+    /// Declares the function `function_name` backed by the `iter` plugin,
+    /// which applies the already declared function `body_function` `count` times.
+    /// The operation of the plugin is deduced from the signature of `function_name`:
+    /// - `map` if its outputs and inputs are those of `body_function` repeated `count` times,
+    /// - `reduce` if `body_function` takes an accumulator (of the same shape as its outputs)
+    ///   followed by one element, and `function_name` takes the initial accumulator followed by
+    ///   `count` elements and returns the final accumulator.
+    /// The params of the plugin are `[body_function, count]`.
+    pub fn push_iter_plugin(
+        &mut self,
+        function_name: String,
+        body_function: String,
+        count: u64,
+        output_count: Vec<Count>,
+        input_count: Vec<Count>,
+    ) -> Result<()> {
+        let body = FunctionCounts::get_function_counts(&self.known_functions, &body_function)?;
+        let repeat = |counts: &[Count]| {
+            counts
+                .iter()
+                .map(|c| Count::new(c.type_id, c.count * count))
+                .collect::<Vec<_>>()
+        };
+        let accumulator_len = body.output_count.len();
+        let operation = if output_count == repeat(&body.output_count)
+            && input_count == repeat(&body.input_count)
+        {
+            "map"
+        } else if body.input_count.starts_with(&body.output_count)
+            && output_count == body.output_count
+            && input_count
+                == [
+                    body.output_count.clone(),
+                    repeat(&body.input_count[accumulator_len..]),
+                ]
+                .concat()
+        {
+            "reduce"
+        } else {
+            return Err(format!(
+                "The signature of {} is not compatible with a map or a reduce of {} over {} iterations.",
+                function_name, body_function, count
+            )
+            .into());
+        };
 
-pub struct FunctionWithInfos {
-    function: Function,
-    public_count: BTreeMap<TypeId, u64>,
-    private_count: BTreeMap<TypeId, u64>,
-}
+        let repeat_inputs = |counts: &BTreeMap<TypeId, u64>| {
+            counts
+                .iter()
+                .map(|(type_id, c)| (*type_id, c * count))
+                .collect::<BTreeMap<_, _>>()
+        };
+        let plugin_body = PluginBody::new(
+            "iter".to_string(),
+            operation.to_string(),
+            vec![body_function, count.to_string()],
+            repeat_inputs(&body.public_count),
+            repeat_inputs(&body.private_count),
+        );
+        self.push_plugin_function(create_plugin_function(
+            function_name,
+            output_count,
+            input_count,
+            plugin_body,
+        )?)
+    }
 
-/// FunctionBuilder builds a Function by allocating wire IDs and building gates.
-/// finish() must be called to obtain the function.
-/// The number of public and private inputs consumed by the function are evaluated on the fly.
-///
-/// # Example
-/// ```
-/// use std::collections::BTreeMap;
-/// use zki_sieve::producers::builder::{FunctionBuilder, GateBuilder,  BuildGate::*};
-/// use zki_sieve::producers::sink::MemorySink;
-/// use zki_sieve::structs::count::Count;
-/// use zki_sieve::structs::types::Type;
-/// use zki_sieve::structs::wirerange::WireRange;
-///
-/// let mut b = GateBuilder::new(MemorySink::default(), &[], &[Type::new_field_type(vec![7])], &[]);
-///
-///  let private_square = {
-///     let mut fb = b.new_function_builder("private_square".to_string(), vec![Count::new(0, 1)], vec![]);
-///     let private_input_wire = fb.create_gate(Private(0, None));
-///     let output_wire = fb.create_gate(Mul(0, private_input_wire, private_input_wire));
-///
-///     fb.finish(vec![WireRange::new(output_wire, output_wire)]).unwrap()
-///  };
-/// ```
-pub struct FunctionBuilder<'a> {
-    name: String,
-    output_count: Vec<Count>,
-    input_count: Vec<Count>,
-    gates: Vec<Gate>,
+    /// Creates one Public gate per value in `values`, and returns the range of their output wires.
+    pub fn create_public_input_array(
+        &mut self,
+        type_id: TypeId,
+        values: Vec<Value>,
+    ) -> Result<WireRange> {
+        self.create_input_array(type_id, values, true)
+    }
 
-    public_count: BTreeMap<TypeId, u64>,  // evaluated on the fly
-    private_count: BTreeMap<TypeId, u64>, // evaluated on the fly
-    known_conversions: &'a BTreeSet<Conversion>,
-    known_functions: &'a BTreeMap<String, FunctionCounts>,
+    /// Creates one Private gate per value in `values`, and returns the range of their output wires.
+    pub fn create_private_input_array(
+        &mut self,
+        type_id: TypeId,
+        values: Vec<Value>,
+    ) -> Result<WireRange> {
+        self.create_input_array(type_id, values, false)
+    }
+
+    fn create_input_array(
+        &mut self,
+        type_id: TypeId,
+        values: Vec<Value>,
+        is_public: bool,
+    ) -> Result<WireRange> {
+        if values.is_empty() {
+            return Err("Cannot create an empty array of inputs.".into());
+        }
+        let mut wires = vec![];
+        for value in values {
+            let gate = if is_public {
+                BuildGate::Public(type_id, Some(value))
+            } else {
+                BuildGate::Private(type_id, Some(value))
+            };
+            wires.push(self.create_gate(gate)?);
+        }
+        // Wire ids of a given type are allocated sequentially
+        Ok(WireRange::new(wires[0], wires[wires.len() - 1]))
+    }
+
+    /// Declares `conversion` if it is not already known.
+    /// Conversions are declared in the first relation message, so it fails if this message
+    /// has already been flushed into the sink.
+    fn register_conversion(&mut self, conversion: Conversion) -> Result<()> {
+        if self.known_conversions.contains(&conversion) {
+            return Ok(());
+        }
+        if self.msg_build.relation_header_flushed {
+            return Err(format!(
+                "Impossible to declare the conversion {:?}: the first relation message has already been flushed.",
+                conversion
+            )
+            .into());
+        }
+        self.msg_build.relation.conversions.push(conversion.clone());
+        self.known_conversions.insert(conversion);
+        Ok(())
+    }
+
+    /// Converts `first_wires` (of type `first_type`) through a sequence of Convert gates.
+    /// `conversions` is a list of `(output_type, output_wire_count)`: the output of each
+    /// conversion is the input of the next one.
+    /// Undeclared conversions are declared on the fly (see `register_conversion`).
+    /// It returns the output wires of the last conversion (of type `conversions.last().0`).
+    pub fn create_conversion_chain(
+        &mut self,
+        conversions: &[(TypeId, u64)],
+        first_type: TypeId,
+        first_wires: WireRange,
+    ) -> Result<WireRange> {
+        let mut in_type = first_type;
+        let mut in_wires = first_wires;
+        for (out_type, out_wire_count) in conversions.iter() {
+            if in_wires.last_id < in_wires.first_id {
+                return Err("create_conversion_chain: invalid input wire range.".into());
+            }
+            self.register_conversion(Conversion::new(
+                Count::new(*out_type, *out_wire_count),
+                Count::new(in_type, in_wires.last_id - in_wires.first_id + 1),
+            ))?;
+            let out_ids = self.create_complex_gate(
+                BuildComplexGate::Convert(
+                    *out_type,
+                    *out_wire_count,
+                    in_type,
+                    in_wires.first_id,
+                    in_wires.last_id,
+                ),
+                vec![],
+                vec![],
+            )?;
+            in_type = *out_type;
+            in_wires = out_ids
+                .into_iter()
+                .next()
+                .ok_or("create_conversion_chain: a Convert gate has no output.")?;
+        }
+        Ok(in_wires)
+    }
+
+    /// Gives the symbolic name `name` to the wire `wire_id` of type `type_id`.
+    /// Aliases are only available for debugging and are not serialized.
+    /// An existing alias with the same name is replaced.
+    pub fn alias_wire(&mut self, wire_id: WireId, type_id: TypeId, name: &str) {
+        self.aliases.insert(name.to_string(), (type_id, wire_id));
+    }
+
+    /// Returns the wire with the symbolic name `name`, if any.
+    pub fn resolve_alias(&self, name: &str) -> Option<(TypeId, WireId)> {
+        self.aliases.get(name).cloned()
+    }
+
+    /// Returns all aliases (name => (type_id, wire_id)).
+    pub fn dump_aliases(&self) -> BTreeMap<String, (TypeId, WireId)> {
+        self.aliases.clone()
+    }
+
+    /// Returns the number of Mul gates created so far, either at top-level or inside the body
+    /// of a pushed function (each function body is counted once, whatever the number of calls).
+    pub fn total_mul_count(&self) -> usize {
+        self.mul_count
+    }
+
+    /// Runs `f` on a DryRunBuilder starting from the state of this builder (types, known
+    /// functions, plugins and conversions, next wire ids), and returns this builder, unchanged,
+    /// with the size of the circuit built by `f`. Nothing is emitted, e.g. to estimate the size
+    /// of a circuit before generating it.
+    /// An error returned by `f` is reported in `CircuitEstimate::error`.
+    pub fn dry_run<F: FnOnce(&mut DryRunBuilder) -> Result<()>>(
+        self,
+        f: F,
+    ) -> (GateBuilder<S>, CircuitEstimate) {
+        let plugins = self.known_plugins.iter().cloned().collect::<Vec<_>>();
+        let conversions = self.known_conversions.iter().cloned().collect::<Vec<_>>();
+        let mut builder = GateBuilder::new(
+            DiscardSink(std::io::sink()),
+            &plugins,
+            &self.msg_build.types,
+            &conversions,
+        );
+        builder.known_functions = self.known_functions.clone();
+        builder.call_graph = self.call_graph.clone();
+        builder.next_available_id = self.next_available_id.clone();
+        builder.type_names = self.type_names.clone();
+        builder.validate_constants = self.validate_constants;
+        builder.prover_context = self.prover_context.clone();
+        builder.wire_values = self.wire_values.clone();
+        builder.msg_build.max_len = self.msg_build.max_len;
+
+        let mut dry_run_builder = DryRunBuilder {
+            builder,
+            public_input_count: 0,
+            private_input_count: 0,
+        };
+        let error = f(&mut dry_run_builder).err().map(|err| err.to_string());
+        let estimate = CircuitEstimate {
+            estimated_gate_count: dry_run_builder.builder.current_gate_count(),
+            estimated_mul_count: dry_run_builder.builder.total_mul_count(),
+            estimated_public_input_count: dry_run_builder.public_input_count,
+            estimated_private_input_count: dry_run_builder.private_input_count,
+            error,
+        };
+        (self, estimate)
+    }
+
+    /// Captures the current state of the builder (allocated wire ids, gates, public/private
+    /// inputs, types, conversions, aliases, known wire values and remaining prover values),
+    /// in order to come back to it later with `rollback`.
+    pub fn snapshot(&self) -> BuilderSnapshot {
+        BuilderSnapshot {
+            next_available_id: self.next_available_id.clone(),
+            directives_len: self.msg_build.relation.directives.len(),
+            public_inputs_len: self
+                .msg_build
+                .public_inputs
+                .buffers
+                .iter()
+                .map(|(type_id, public_inputs)| (*type_id, public_inputs.inputs.len()))
+                .collect(),
+            private_inputs_len: self
+                .msg_build
+                .private_inputs
+                .buffers
+                .iter()
+                .map(|(type_id, private_inputs)| (*type_id, private_inputs.inputs.len()))
+                .collect(),
+            functions_count: self.known_functions.len(),
+            flushed_messages: self.msg_build.flushed_messages,
+            gates_count: self.msg_build.gates_count,
+            mul_count: self.mul_count,
+            types_len: self.msg_build.types.len(),
+            relation_types_len: self.msg_build.relation.types.len(),
+            known_conversions: self.known_conversions.clone(),
+            relation_conversions_len: self.msg_build.relation.conversions.len(),
+            aliases: self.aliases.clone(),
+            prover_context: self.prover_context.clone(),
+            wire_values: self.wire_values.clone(),
+            checkpoints_len: self.checkpoints.len(),
+        }
+    }
+
+    /// Removes all gates, public/private inputs, types, conversions, aliases and checkpoints
+    /// created since `snapshot`, gives back the prover values consumed since then, and restores
+    /// the wire ids and gates counters.
+    /// It returns an error if a function has been pushed since `snapshot` (functions cannot be
+    /// unregistered) or if some messages have already been flushed into the sink.
+    pub fn rollback(&mut self, snapshot: BuilderSnapshot) -> Result<()> {
+        if self.known_functions.len() != snapshot.functions_count {
+            return Err(
+                "Impossible to rollback: some functions have been pushed since the snapshot."
+                    .into(),
+            );
+        }
+        if self.msg_build.flushed_messages != snapshot.flushed_messages {
+            return Err(
+                "Impossible to rollback: some messages have been flushed since the snapshot."
+                    .into(),
+            );
+        }
+
+        self.msg_build
+            .relation
+            .directives
+            .truncate(snapshot.directives_len);
+        for (type_id, public_inputs) in self.msg_build.public_inputs.buffers.iter_mut() {
+            let len = snapshot
+                .public_inputs_len
+                .get(type_id)
+                .cloned()
+                .unwrap_or(0);
+            public_inputs.inputs.truncate(len);
+        }
+        for (type_id, private_inputs) in self.msg_build.private_inputs.buffers.iter_mut() {
+            let len = snapshot
+                .private_inputs_len
+                .get(type_id)
+                .cloned()
+                .unwrap_or(0);
+            private_inputs.inputs.truncate(len);
+        }
+        self.msg_build.types.truncate(snapshot.types_len);
+        self.msg_build
+            .relation
+            .types
+            .truncate(snapshot.relation_types_len);
+        self.msg_build
+            .relation
+            .conversions
+            .truncate(snapshot.relation_conversions_len);
+        self.known_conversions = snapshot.known_conversions;
+        self.aliases = snapshot.aliases;
+        self.prover_context = snapshot.prover_context;
+        self.wire_values = snapshot.wire_values;
+        self.checkpoints.truncate(snapshot.checkpoints_len);
+        self.next_available_id = snapshot.next_available_id;
+        self.msg_build.gates_count = snapshot.gates_count;
+        self.mul_count = snapshot.mul_count;
+        Ok(())
+    }
+
+    /// Turns the relation built so far into its verifier view, and returns it: Private gates,
+    /// at top-level and in function bodies, are replaced with New gates allocating their output
+    /// wire (or removed if the wire is already allocated by a previous New gate), and the private
+    /// input values and known wire values held by the builder are discarded. The relation and
+    /// the public inputs emitted by `finish` can then be verified without any private input.
+    /// It returns an error if some messages have already been flushed into the sink.
+    pub fn build_verifier_view(&mut self) -> Result<Relation> {
+        if self.msg_build.flushed_messages > 0 {
+            return Err(
+                "Impossible to build the verifier view: some messages have already been flushed."
+                    .into(),
+            );
+        }
+        let mut gates = vec![];
+        let mut directives = vec![];
+        for directive in take(&mut self.msg_build.relation.directives) {
+            match directive {
+                Directive::Gate(gate) => gates.push(gate),
+                Directive::Function(mut function) => {
+                    directives.extend(verifier_gates(take(&mut gates)).map(Directive::Gate));
+                    if let FunctionBody::Gates(body) = &mut function.body {
+                        *body = verifier_gates(take(body)).collect();
+                    }
+                    directives.push(Directive::Function(function));
+                }
+            }
+        }
+        directives.extend(verifier_gates(gates).map(Directive::Gate));
+        self.msg_build.gates_count = directives
+            .iter()
+            .filter(|directive| matches!(directive, Directive::Gate(_)))
+            .count();
+        self.msg_build.relation.directives = directives;
+        self.msg_build.private_inputs.buffers.clear();
+        self.wire_values.clear();
+        Ok(self.msg_build.relation.clone())
+    }
+
+    /// Returns the next wire id which will be allocated for each type,
+    /// e.g. to report the progress of a long-running builder.
+    pub fn current_wire_counts(&self) -> BTreeMap<TypeId, WireId> {
+        self.next_available_id.clone()
+    }
+
+    /// Returns the number of top-level gates created so far,
+    /// including those already flushed to the sink.
+    pub fn current_gate_count(&self) -> usize {
+        self.msg_build.gates_count
+    }
+
+    /// Marks the current position in the gate stream with `label` (see `checkpoint_report`).
+    pub fn emit_checkpoint(&mut self, label: String) {
+        self.checkpoints.push((label, self.current_gate_count()));
+    }
+
+    /// Returns `(label, start_gate_index, gate_count_since_previous)` for each checkpoint,
+    /// where `start_gate_index` is the number of top-level gates created before the checkpoint
+    /// and `gate_count_since_previous` the number of gates created since the previous checkpoint
+    /// (or since the beginning for the first one).
+    pub fn checkpoint_report(&self) -> Vec<(String, usize, usize)> {
+        let mut previous = 0;
+        self.checkpoints
+            .iter()
+            .map(|(label, start)| {
+                let since_previous = start - previous;
+                previous = *start;
+                (label.clone(), *start, since_previous)
+            })
+            .collect()
+    }
+
+    pub fn finish(self) -> S {
+        self.msg_build.finish()
+    }
+}
+
+/// Returns the name of `type_id` to be used in error messages,
+/// i.e. `Type 'name'` if a name is given in `type_names` and `Type id {type_id}` otherwise.
+fn describe_type(type_names: &[String], type_id: TypeId) -> String {
+    match type_names.get(type_id as usize) {
+        Some(name) => format!("Type '{}'", name),
+        None => format!("Type id {}", type_id),
+    }
+}
+
+/// Returns the modulo of the type `type_id` in `types` if it is a Field.
+fn field_modulo<'a>(
+    types: &'a [Type],
+    type_names: &[String],
+    type_id: TypeId,
+) -> Result<&'a Value> {
+    match types.get(usize::try_from(type_id)?) {
+        Some(Type::Field(modulo)) => Ok(modulo),
+        Some(Type::PluginType(_, _, _)) => Err(format!(
+            "{} is not a Field type.",
+            describe_type(type_names, type_id)
+        )
+        .into()),
+        None => Err(format!("Type id {} is not defined.", type_id).into()),
+    }
+}
+
+/// Replaces the Private gates of `gates` with New gates allocating their output wire,
+/// or removes them if the wire is already allocated by a previous New gate of `gates`.
+fn verifier_gates(gates: Vec<Gate>) -> impl Iterator<Item = Gate> {
+    let mut allocations: Vec<(TypeId, WireId, WireId)> = vec![];
+    gates.into_iter().filter_map(move |gate| match gate {
+        Gate::New(type_id, first_id, last_id) => {
+            allocations.push((type_id, first_id, last_id));
+            Some(gate)
+        }
+        Gate::Private(type_id, out_id) => {
+            let allocated = allocations.iter().any(|(alloc_type_id, first, last)| {
+                *alloc_type_id == type_id && *first <= out_id && out_id <= *last
+            });
+            if allocated {
+                None
+            } else {
+                Some(Gate::New(type_id, out_id, out_id))
+            }
+        }
+        _ => Some(gate),
+    })
+}
+
+/// Returns the value '-1' in the Field type `type_id` of `types`.
+fn field_minus_one(types: &[Type], type_names: &[String], type_id: TypeId) -> Result<Value> {
+    let modulo = value_to_biguint(field_modulo(types, type_names, type_id)?);
+    if modulo <= BigUint::one() {
+        return Err(format!(
+            "{} has an invalid modulo.",
+            describe_type(type_names, type_id)
+        )
+        .into());
+    }
+    Ok((modulo - BigUint::one()).to_bytes_le())
+}
+
+/// Returns the number of public and private inputs (per type) consumed by a function body.
+fn body_input_counts(
+    body: &FunctionBody,
+    known_functions: &BTreeMap<String, FunctionCounts>,
+) -> Result<(BTreeMap<TypeId, u64>, BTreeMap<TypeId, u64>)> {
+    let gates = match body {
+        FunctionBody::PluginBody(plugin_body) => {
+            return Ok((
+                plugin_body.public_count.clone(),
+                plugin_body.private_count.clone(),
+            ))
+        }
+        FunctionBody::Gates(gates) => gates,
+    };
+    let mut public_count = BTreeMap::new();
+    let mut private_count = BTreeMap::new();
+    for gate in gates.iter() {
+        match gate {
+            Gate::Public(type_id, _) => *public_count.entry(*type_id).or_insert(0) += 1,
+            Gate::Private(type_id, _) => *private_count.entry(*type_id).or_insert(0) += 1,
+            Gate::Call(name, _, _) => {
+                let counts = FunctionCounts::get_function_counts(known_functions, name)?;
+                for (type_id, count) in counts.public_count.iter() {
+                    *public_count.entry(*type_id).or_insert(0) += count;
+                }
+                for (type_id, count) in counts.private_count.iter() {
+                    *private_count.entry(*type_id).or_insert(0) += count;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok((public_count, private_count))
+}
+
+/// Returns, for each wire range allocated or set by `gate`, its type and its last wire id.
+fn allocated_wires(
+    gate: &Gate,
+    known_functions: &BTreeMap<String, FunctionCounts>,
+) -> Result<Vec<(TypeId, WireId)>> {
+    Ok(match gate {
+        Gate::Constant(type_id, out, _)
+        | Gate::Copy(type_id, out, _)
+        | Gate::Add(type_id, out, _, _)
+        | Gate::Mul(type_id, out, _, _)
+        | Gate::AddConstant(type_id, out, _, _)
+        | Gate::MulConstant(type_id, out, _, _)
+        | Gate::Public(type_id, out)
+        | Gate::Private(type_id, out) => vec![(*type_id, *out)],
+        Gate::New(type_id, _, last_id) => vec![(*type_id, *last_id)],
+        Gate::Convert(type_id, _, last_id, _, _, _) => vec![(*type_id, *last_id)],
+        Gate::Call(name, out_ids, _) => {
+            let counts = FunctionCounts::get_function_counts(known_functions, name)?;
+            add_types_to_wire_ranges(out_ids, &counts.output_count)?
+                .iter()
+                .map(|range| (range.type_id, range.last_id))
+                .collect()
+        }
+        Gate::AssertZero(_, _) | Gate::Delete(_, _, _) => vec![],
+    })
+}
+
+/// Returns the wire ranges set by `gate` and the wire ranges it uses (including deleted ones).
+/// New gates only allocate wires, so they neither set nor use any wire.
+fn gate_wire_ranges(
+    gate: &Gate,
+    known_functions: &BTreeMap<String, FunctionCounts>,
+) -> Result<(Vec<WireRangeWithType>, Vec<WireRangeWithType>)> {
+    let wire =
+        |type_id: &TypeId, wire_id: &WireId| WireRangeWithType::new(*type_id, *wire_id, *wire_id);
+    Ok(match gate {
+        Gate::Constant(type_id, out, _)
+        | Gate::Public(type_id, out)
+        | Gate::Private(type_id, out) => (vec![wire(type_id, out)], vec![]),
+        Gate::AssertZero(type_id, input) => (vec![], vec![wire(type_id, input)]),
+        Gate::Copy(type_id, out, input)
+        | Gate::AddConstant(type_id, out, input, _)
+        | Gate::MulConstant(type_id, out, input, _) => {
+            (vec![wire(type_id, out)], vec![wire(type_id, input)])
+        }
+        Gate::Add(type_id, out, left, right) | Gate::Mul(type_id, out, left, right) => (
+            vec![wire(type_id, out)],
+            vec![wire(type_id, left), wire(type_id, right)],
+        ),
+        Gate::New(_, _, _) => (vec![], vec![]),
+        Gate::Delete(type_id, first_id, last_id) => (
+            vec![],
+            vec![WireRangeWithType::new(*type_id, *first_id, *last_id)],
+        ),
+        Gate::Convert(
+            out_type_id,
+            out_first_id,
+            out_last_id,
+            in_type_id,
+            in_first_id,
+            in_last_id,
+        ) => (
+            vec![WireRangeWithType::new(
+                *out_type_id,
+                *out_first_id,
+                *out_last_id,
+            )],
+            vec![WireRangeWithType::new(
+                *in_type_id,
+                *in_first_id,
+                *in_last_id,
+            )],
+        ),
+        Gate::Call(name, out_ids, in_ids) => {
+            let counts = FunctionCounts::get_function_counts(known_functions, name)?;
+            (
+                add_types_to_wire_ranges(out_ids, &counts.output_count)?,
+                add_types_to_wire_ranges(in_ids, &counts.input_count)?,
+            )
+        }
+    })
+}
+
+/// Checks that the body `gates` of the function `name` only uses its input wires and the wires
+/// set by previous gates, sets each wire at most once, and sets all its output wires.
+fn check_function_body_wires(
+    name: &str,
+    gates: &[Gate],
+    output_count: &[Count],
+    input_count: &[Count],
+    known_functions: &BTreeMap<String, FunctionCounts>,
+) -> Result<()> {
+    let mut next_ids = BTreeMap::new();
+    let output_ranges = wire_ranges_from_counts(output_count, &mut next_ids);
+    let input_ranges = wire_ranges_from_counts(input_count, &mut next_ids);
+    let mut set_wires = BTreeSet::new();
+    for (type_id, range) in input_ranges.iter() {
+        set_wires.extend((range.first_id..=range.last_id).map(|wire_id| (*type_id, wire_id)));
+    }
+
+    for (index, gate) in gates.iter().enumerate() {
+        let (outputs, inputs) = gate_wire_ranges(gate, known_functions)?;
+        for range in inputs.iter() {
+            for wire_id in range.first_id..=range.last_id {
+                if !set_wires.contains(&(range.type_id, wire_id)) {
+                    return Err(format!(
+                        "Function {}: gate {} uses the wire {} of type {} which is not set.",
+                        name, index, wire_id, range.type_id
+                    )
+                    .into());
+                }
+            }
+        }
+        if let Gate::Delete(type_id, first_id, last_id) = gate {
+            for wire_id in *first_id..=*last_id {
+                set_wires.remove(&(*type_id, wire_id));
+            }
+        }
+        for range in outputs.iter() {
+            for wire_id in range.first_id..=range.last_id {
+                if !set_wires.insert((range.type_id, wire_id)) {
+                    return Err(format!(
+                        "Function {}: gate {} sets the wire {} of type {} twice.",
+                        name, index, wire_id, range.type_id
+                    )
+                    .into());
+                }
+            }
+        }
+    }
+
+    for (type_id, range) in output_ranges.iter() {
+        for wire_id in range.first_id..=range.last_id {
+            if !set_wires.contains(&(*type_id, wire_id)) {
+                return Err(format!(
+                    "Function {}: the output wire {} of type {} is not set.",
+                    name, wire_id, type_id
+                )
+                .into());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Allocates one WireRange per Count (empty counts are not supported),
+/// starting from `next_ids` (type_id => next wire id) which is updated accordingly.
+fn wire_ranges_from_counts(
+    counts: &[Count],
+    next_ids: &mut BTreeMap<TypeId, WireId>,
+) -> Vec<(TypeId, WireRange)> {
+    counts
+        .iter()
+        .map(|count| {
+            (
+                count.type_id,
+                multiple_alloc(count.type_id, next_ids, count.count),
+            )
+        })
+        .collect()
+}
+
+fn count_mul_gates(gates: &[Gate]) -> usize {
+    gates
+        .iter()
+        .filter(|gate| matches!(gate, Gate::Mul(_, _, _, _)))
+        .count()
+}
+
+pub fn new_example_builder() -> GateBuilder<MemorySink> {
+    GateBuilder::new(
+        MemorySink::default(),
+        &[],
+        &[Type::new_field_type(vec![2])],
+        &[],
+    )
+}
+
+pub struct FunctionWithInfos {
+    function: Function,
+    public_count: BTreeMap<TypeId, u64>,
+    private_count: BTreeMap<TypeId, u64>,
+}
+
+impl FunctionWithInfos {
+    pub(crate) fn function(&self) -> &Function {
+        &self.function
+    }
+}
+
+/// FunctionBuilder builds a Function by allocating wire IDs and building gates.
+/// finish() must be called to obtain the function.
+/// The number of public and private inputs consumed by the function are evaluated on the fly.
+///
+/// # Example
+/// ```
+/// use std::collections::BTreeMap;
+/// use zki_sieve::producers::builder::{FunctionBuilder, GateBuilder,  BuildGate::*};
+/// use zki_sieve::producers::sink::MemorySink;
+/// use zki_sieve::structs::count::Count;
+/// use zki_sieve::structs::types::Type;
+/// use zki_sieve::structs::wirerange::WireRange;
+///
+/// let mut b = GateBuilder::new(MemorySink::default(), &[], &[Type::new_field_type(vec![7])], &[]);
+///
+///  let private_square = {
+///     let mut fb = b.new_function_builder("private_square".to_string(), vec![Count::new(0, 1)], vec![]);
+///     let private_input_wire = fb.create_gate(Private(0, None));
+///     let output_wire = fb.create_gate(Mul(0, private_input_wire, private_input_wire));
+///
+///     fb.finish(vec![WireRange::new(output_wire, output_wire)]).unwrap()
+///  };
+/// ```
+pub struct FunctionBuilder<'a> {
+    name: String,
+    output_count: Vec<Count>,
+    input_count: Vec<Count>,
+    gates: Vec<Gate>,
+
+    public_count: BTreeMap<TypeId, u64>,  // evaluated on the fly
+    private_count: BTreeMap<TypeId, u64>, // evaluated on the fly
+    types: &'a [Type],
+    known_conversions: &'a BTreeSet<Conversion>,
+    known_functions: &'a BTreeMap<String, FunctionCounts>,
     next_available_id: BTreeMap<TypeId, WireId>,
 }
 
 impl FunctionBuilder<'_> {
     /// Returns a Vec<(TypeId, WireId)> containing the inputs wires (without WireRange).
     pub fn input_wires(&self) -> Vec<(TypeId, WireId)> {
-        let mut map = BTreeMap::new();
-        for count in self.output_count.iter() {
-            map.insert(count.type_id, count.count);
-        }
-        let mut result: Vec<(TypeId, WireId)> = vec![];
-        for count in self.input_count.iter() {
-            let type_id_count = map.entry(count.type_id).or_insert(0);
-            for id in *type_id_count..(*type_id_count + count.count) {
-                result.push((count.type_id, id));
-            }
+        self.input_wire_ranges()
+            .iter()
+            .flat_map(|(type_id, range)| {
+                (range.first_id..=range.last_id).map(move |wire_id| (*type_id, wire_id))
+            })
+            .collect()
+    }
+
+    /// Returns the input wires grouped by type, each group in increasing order.
+    pub fn input_wires_by_type(&self) -> BTreeMap<TypeId, Vec<WireId>> {
+        let mut result: BTreeMap<TypeId, Vec<WireId>> = BTreeMap::new();
+        for (type_id, wire_id) in self.input_wires() {
+            result.entry(type_id).or_default().push(wire_id);
         }
         result
     }
 
+    /// Returns one (TypeId, WireRange) per entry of `output_count`.
+    pub fn output_wire_ranges(&self) -> Vec<(TypeId, WireRange)> {
+        let mut next_ids = BTreeMap::new();
+        wire_ranges_from_counts(&self.output_count, &mut next_ids)
+    }
+
+    /// Returns, for each type of the outputs, the range of all output wires of this type.
+    /// Output wires of a type are numbered contiguously from 0, even when the type appears
+    /// several times in `output_count`.
+    pub fn output_wire_ranges_by_type(&self) -> BTreeMap<TypeId, WireRange> {
+        Count::total(&self.output_count)
+            .into_iter()
+            .filter(|(_, count)| *count > 0)
+            .map(|(type_id, count)| (type_id, WireRange::new(0, count - 1)))
+            .collect()
+    }
+
+    /// Returns one (TypeId, WireRange) per entry of `input_count`.
+    /// Input wires of each type are numbered after the output wires of the same type.
+    pub fn input_wire_ranges(&self) -> Vec<(TypeId, WireRange)> {
+        let mut next_ids = BTreeMap::new();
+        wire_ranges_from_counts(&self.output_count, &mut next_ids);
+        wire_ranges_from_counts(&self.input_count, &mut next_ids)
+    }
+
     /// Updates public_count and private_count,
     /// Allocates a new wire id for the output and creates a new gate,
     /// Returns the newly allocated WireId.
@@ -618,6 +2001,17 @@ impl FunctionBuilder<'_> {
         out_id
     }
 
+    /// Creates all `gates` in order (see `create_gate`), e.g. when translating them
+    /// from another representation.
+    /// Returns one WireId per gate, `NO_OUTPUT` for gates without output.
+    pub fn add_gate_sequence(&mut self, gates: Vec<BuildGate>) -> Vec<WireId> {
+        self.gates.reserve(gates.len());
+        gates
+            .into_iter()
+            .map(|gate| self.create_gate(gate))
+            .collect()
+    }
+
     /// Allocates some new wire ids for the output,
     /// Updates public_count and private_count,
     /// Creates a new gate,
@@ -686,6 +2080,32 @@ impl FunctionBuilder<'_> {
         Ok(out_ids)
     }
 
+    /// Creates a wire holding the constant 0.
+    pub fn create_zero(&mut self, type_id: TypeId) -> WireId {
+        self.create_gate(BuildGate::Constant(type_id, vec![0]))
+    }
+
+    /// Creates a wire holding the constant 1.
+    pub fn create_one(&mut self, type_id: TypeId) -> WireId {
+        self.create_gate(BuildGate::Constant(type_id, vec![1]))
+    }
+
+    /// Creates a wire holding the constant -1 of the Field type `type_id`.
+    pub fn create_neg_one(&mut self, type_id: TypeId) -> Result<WireId> {
+        let minus_one = field_minus_one(self.types, &[], type_id)?;
+        Ok(self.create_gate(BuildGate::Constant(type_id, minus_one)))
+    }
+
+    /// Returns the number of Mul gates created so far in this function.
+    pub fn mul_count(&self) -> usize {
+        count_mul_gates(&self.gates)
+    }
+
+    /// Returns the number of gates created so far in this function.
+    pub fn total_gate_count(&self) -> usize {
+        self.gates.len()
+    }
+
     // Creates and returns the Function as well as the number of public/private inputs consumed by this Function
     pub fn finish(&mut self, out_ids: Vec<WireRange>) -> Result<FunctionWithInfos> {
         if !check_wire_ranges_with_counts(&out_ids, &self.output_count) {
@@ -1353,3 +2773,1321 @@ fn test_builder_with_files_sink() {
 
     assert_eq!(filenames.as_slice(), expected_filenames);
 }
+
+#[test]
+fn test_builder_snapshot_and_rollback() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::consumers::source::Source;
+    use crate::producers::builder::{BuildGate::*, GateBuilder, GateBuilderT};
+    use crate::producers::sink::MemorySink;
+
+    let build = |speculate: bool| -> Result<(Source<'static>, usize)> {
+        let mut b = GateBuilder::new(
+            MemorySink::default(),
+            &[],
+            &[Type::new_field_type(vec![101])],
+            &[],
+        );
+        let x = b.create_gate(Private(0, Some(vec![10])))?;
+
+        if speculate {
+            // Try an alternative implementation and rollback
+            let snapshot = b.snapshot();
+            let y = b.create_gate(Public(0, Some(vec![2])))?;
+            let z = b.create_gate(Private(0, Some(vec![3])))?;
+            b.create_gate(Mul(0, y, z))?;
+            assert_eq!(b.total_mul_count(), 1);
+            b.rollback(snapshot)?;
+            assert_eq!(b.total_mul_count(), 0);
+        }
+
+        let x_square = b.create_gate(Mul(0, x, x))?;
+        let neg_100 = b.create_gate(Constant(0, vec![1]))?;
+        let diff = b.create_gate(Add(0, x_square, neg_100))?;
+        b.create_gate(AssertZero(0, diff))?;
+        let mul_count = b.total_mul_count();
+        Ok((b.finish().into(), mul_count))
+    };
+
+    let (expected_source, expected_mul_count) = build(false)?;
+    let (source, mul_count) = build(true)?;
+    assert_eq!(
+        source.read_all_messages()?,
+        expected_source.read_all_messages()?
+    );
+    assert_eq!(mul_count, expected_mul_count);
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(evaluator.get_violations(), Vec::<String>::new());
+
+    // Functions cannot be rolled back
+    let mut b = GateBuilder::new(
+        MemorySink::default(),
+        &[],
+        &[Type::new_field_type(vec![101])],
+        &[],
+    );
+    let snapshot = b.snapshot();
+    let square = {
+        let mut fb = b.new_function_builder(
+            "square".to_string(),
+            vec![Count::new(0, 1)],
+            vec![Count::new(0, 1)],
+        );
+        let input = fb.input_wires()[0].1;
+        let output = fb.create_gate(Mul(0, input, input));
+        fb.finish(vec![WireRange::new(output, output)])?
+    };
+    b.push_function(square)?;
+    assert!(b.rollback(snapshot).is_err());
+
+    // Types, conversions, aliases and prover values are restored
+    let mut context = ProverContext::default();
+    context.push_witness(0, vec![4]);
+    context.push_witness(0, vec![5]);
+    let mut b = GateBuilder::new_with_prover_mode(MemorySink::default(), &[vec![101]], context);
+    let x = b.create_gate(Private(0, None))?;
+    b.alias_wire(x, 0, "x");
+    let snapshot = b.snapshot();
+    let y = b.create_gate(Private(0, None))?;
+    b.alias_wire(y, 0, "x");
+    b.alias_wire(y, 0, "y");
+    let bits = b.push_type(vec![2])?;
+    b.create_conversion_chain(&[(bits, 7)], 0, WireRange::new(x, x))?;
+    assert!(b.create_gate(Private(0, None)).is_err());
+    b.rollback(snapshot)?;
+
+    assert_eq!(b.resolve_alias("x"), Some((0, x)));
+    assert_eq!(b.resolve_alias("y"), None);
+    assert_eq!(b.create_gate(Private(0, None))?, y);
+    assert_eq!(b.push_type(vec![7])?, bits);
+    assert!(b.known_conversions.is_empty());
+    let messages = Source::from(b.finish()).read_all_messages()?;
+    assert_eq!(
+        messages.relations[0].types,
+        vec![Type::Field(vec![101]), Type::Field(vec![7])]
+    );
+    assert!(messages.relations[0].conversions.is_empty());
+    assert_eq!(messages.private_inputs[0].inputs, vec![vec![4], vec![5]]);
+    Ok(())
+}
+
+#[test]
+fn test_builder_mul_count() -> Result<()> {
+    use crate::producers::builder::{BuildComplexGate::*, BuildGate::*, GateBuilder, GateBuilderT};
+    use crate::producers::sink::MemorySink;
+
+    // Same circuit as simple_example_relation()
+    let mut b = GateBuilder::new(
+        MemorySink::default(),
+        &[],
+        &[Type::new_field_type(vec![101])],
+        &[],
+    );
+    let square = {
+        let mut fb = b.new_function_builder(
+            "square".to_string(),
+            vec![Count::new(0, 1)],
+            vec![Count::new(0, 1)],
+        );
+        let input = fb.input_wires()[0].1;
+        let output = fb.create_gate(Mul(0, input, input));
+        assert_eq!(fb.mul_count(), 1);
+        assert_eq!(fb.total_gate_count(), 1);
+        fb.finish(vec![WireRange::new(output, output)])?
+    };
+    b.push_function(square)?;
+
+    let public = b.create_gate(Public(0, Some(vec![5])))?;
+    let private_0 = b.create_gate(Private(0, Some(vec![3])))?;
+    let private_1 = b.create_gate(Private(0, Some(vec![4])))?;
+    let mut squares = vec![];
+    for wire in [public, private_0, private_1] {
+        let out = b.create_complex_gate(
+            Call("square".to_string(), vec![WireRange::new(wire, wire)]),
+            vec![],
+            vec![],
+        )?;
+        squares.push(out[0].first_id);
+    }
+    let sum = b.create_gate(Add(0, squares[1], squares[2]))?;
+    let neg = b.create_gate(MulConstant(0, squares[0], vec![100]))?;
+    let diff = b.create_gate(Add(0, sum, neg))?;
+    b.create_gate(AssertZero(0, diff))?;
+    assert_eq!(b.total_mul_count(), 1);
+
+    // Top-level Mul gates are counted as well
+    b.create_gate(Mul(0, private_0, private_1))?;
+    assert_eq!(b.total_mul_count(), 2);
+    Ok(())
+}
+
+#[test]
+fn test_builder_with_recursive_function() {
+    use crate::producers::sink::MemorySink;
+
+    let mut b = GateBuilder::new(
+        MemorySink::default(),
+        &[],
+        &[Type::new_field_type(vec![101])],
+        &[],
+    );
+    let recursive_function = FunctionWithInfos {
+        function: Function::new(
+            "recursive".to_string(),
+            vec![Count::new(0, 1)],
+            vec![Count::new(0, 1)],
+            FunctionBody::Gates(vec![Gate::Call(
+                "recursive".to_string(),
+                vec![WireRange::new(0, 0)],
+                vec![WireRange::new(1, 1)],
+            )]),
+        ),
+        public_count: BTreeMap::new(),
+        private_count: BTreeMap::new(),
+    };
+    let err = b.push_function(recursive_function).unwrap_err();
+    assert_eq!(err.to_string(), "Recursive cycle: recursive → recursive");
+}
+
+#[test]
+fn test_builder_conversion_chain() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::consumers::source::Source;
+    use crate::producers::builder::BuildGate::*;
+    use num_bigint::BigUint;
+
+    let mut b = GateBuilder::new(
+        MemorySink::default(),
+        &[],
+        &[
+            Type::Field(vec![7]),
+            Type::Field(vec![101]),
+            Type::Field(vec![2]),
+        ],
+        &[],
+    );
+
+    // 3*7 + 5 = 26 = 0b11010
+    let in_0 = b.create_gate(Private(0, Some(vec![3])))?;
+    let in_1 = b.create_gate(Private(0, Some(vec![5])))?;
+    let bits = b.create_conversion_chain(&[(1, 1), (2, 5)], 0, WireRange::new(in_0, in_1))?;
+    assert_eq!(bits, WireRange::new(0, 4));
+
+    let source: Source = b.finish().into();
+    let messages = source.read_all_messages()?;
+    assert_eq!(
+        messages.relations[0].conversions,
+        vec![
+            Conversion::new(Count::new(1, 1), Count::new(0, 2)),
+            Conversion::new(Count::new(2, 5), Count::new(1, 1)),
+        ]
+    );
+
+    let mut backend = PlaintextBackend::default();
+    let evaluator = Evaluator::from_messages(source.iter_messages(), &mut backend);
+    let values = (bits.first_id..=bits.last_id)
+        .map(|wire_id| evaluator.get(2, wire_id).map(|value| value.clone()))
+        .collect::<Result<Vec<_>>>()?;
+    let expected = [1u8, 1, 0, 1, 0]
+        .iter()
+        .map(|bit| BigUint::from(*bit))
+        .collect::<Vec<_>>();
+    assert_eq!(values, expected);
+    assert_eq!(evaluator.get_violations(), Vec::<String>::new());
+    Ok(())
+}
+
+#[test]
+fn test_builder_constants() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::consumers::source::Source;
+    use crate::producers::builder::BuildGate::*;
+
+    let mut b = GateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![101])], &[]);
+
+    // 0 + 1 + (p-1) = 0
+    let zero = b.create_zero(0)?;
+    let one = b.create_one(0)?;
+    let neg_one = b.create_neg_one(0)?;
+    let sum = b.create_gate(Add(0, zero, one))?;
+    let sum = b.create_gate(Add(0, sum, neg_one))?;
+    b.create_gate(AssertZero(0, sum))?;
+
+    // Same check inside a function
+    let function = {
+        let mut fb = b.new_function_builder("zero".to_string(), vec![Count::new(0, 1)], vec![]);
+        let zero = fb.create_zero(0);
+        let one = fb.create_one(0);
+        let neg_one = fb.create_neg_one(0)?;
+        let sum = fb.create_gate(Add(0, zero, one));
+        let sum = fb.create_gate(Add(0, sum, neg_one));
+        fb.finish(vec![WireRange::new(sum, sum)])?
+    };
+    b.push_function(function)?;
+    let out = b.create_complex_gate(
+        BuildComplexGate::Call("zero".to_string(), vec![]),
+        vec![],
+        vec![],
+    )?;
+    b.create_gate(AssertZero(0, out[0].first_id))?;
+
+    // -1 is only defined for Field types
+    assert!(b.create_neg_one(1).is_err());
+
+    let source: Source = b.finish().into();
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(evaluator.get_violations(), Vec::<String>::new());
+    Ok(())
+}
+
+/// Returns a builder extending `example_relation()`, whose sink holds the example messages.
+#[cfg(test)]
+fn extended_example_builder() -> Result<GateBuilder<MemorySink>> {
+    use crate::producers::examples::*;
+
+    let (public_inputs, private_inputs) = (example_public_inputs(), example_private_inputs());
+    let mut sink = MemorySink::default();
+    for inputs in public_inputs[1..].iter() {
+        sink.push_public_inputs_message(inputs)?;
+    }
+    for inputs in private_inputs[1..].iter() {
+        sink.push_private_inputs_message(inputs)?;
+    }
+    GateBuilder::from_relation(
+        sink,
+        &example_relation(),
+        &public_inputs[0],
+        &private_inputs[0],
+    )
+}
+
+#[test]
+fn test_builder_from_relation() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::consumers::source::Source;
+    use crate::producers::builder::BuildGate::*;
+    use crate::producers::examples::*;
+
+    let mut b = extended_example_builder()?;
+    assert_eq!(b.known_functions().len(), 6);
+
+    // The example uses wires 0..=8 of type 1, so new wires start at 9
+    let x = b.create_gate(Private(1, Some(vec![42])))?;
+    assert_eq!(x, 9);
+    let y = b.create_gate(AddConstant(1, x, vec![59]))?;
+    b.create_gate(AssertZero(1, y))?;
+
+    // The known functions can be called
+    let square = b.create_complex_gate(
+        BuildComplexGate::Call("square".to_string(), vec![WireRange::new(x, x)]),
+        vec![],
+        vec![],
+    )?;
+    assert_eq!(square, vec![WireRange::new(11, 11)]);
+
+    let source: Source = b.finish().into();
+    let messages = source.read_all_messages()?;
+    assert_eq!(messages.relations.len(), 2);
+    assert_eq!(messages.relations[0], example_relation());
+    assert!(messages.relations[1].types.is_empty());
+
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(evaluator.get_violations(), Vec::<String>::new());
+    Ok(())
+}
+
+#[test]
+fn test_builder_create_call() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::consumers::source::Source;
+    use crate::producers::builder::BuildGate::*;
+
+    let mut b = extended_example_builder()?;
+    let x = b.create_gate(Private(1, Some(vec![7])))?;
+    let square = b.create_call("square", vec![WireRange::new(x, x)], vec![], vec![])?;
+    assert_eq!(square.ranges.len(), 1);
+    let x_squared = square.wire(0)?;
+    assert_eq!(square.all_wires(0)?, vec![x_squared]);
+    assert!(square.range(1).is_err());
+    let y = b.create_gate(AddConstant(1, x_squared, vec![52]))?;
+    b.create_gate(AssertZero(1, y))?;
+
+    // Multi-wire output ranges
+    let first = b.create_gate(Private(1, Some(vec![3])))?;
+    let second = b.create_gate(Private(1, Some(vec![4])))?;
+    assert_eq!(second, first + 1);
+    let product = b.create_call(
+        "vector_mul_7_2",
+        vec![WireRange::new(first, second), WireRange::new(first, second)],
+        vec![],
+        vec![],
+    )?;
+    assert_eq!(product.all_wires(0)?.len(), 2);
+    assert!(product.wire(0).is_err());
+    assert!(b
+        .create_call("unknown", vec![WireRange::new(x, x)], vec![], vec![])
+        .is_err());
+
+    let source: Source = b.finish().into();
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(evaluator.get_violations(), Vec::<String>::new());
+    Ok(())
+}
+
+#[test]
+fn test_function_builder_wire_ranges() -> Result<()> {
+    let b = GateBuilder::new(
+        MemorySink::default(),
+        &[],
+        &[Type::Field(vec![7]), Type::Field(vec![101])],
+        &[],
+    );
+    let fb = b.new_function_builder(
+        "f".to_string(),
+        vec![Count::new(0, 2), Count::new(1, 1)],
+        vec![Count::new(1, 3), Count::new(0, 1), Count::new(1, 2)],
+    );
+    assert_eq!(
+        fb.output_wire_ranges(),
+        vec![(0, WireRange::new(0, 1)), (1, WireRange::new(0, 0))]
+    );
+    assert_eq!(
+        fb.input_wire_ranges(),
+        vec![
+            (1, WireRange::new(1, 3)),
+            (0, WireRange::new(2, 2)),
+            (1, WireRange::new(4, 5)),
+        ]
+    );
+
+    // Consistent with input_wires()
+    let input_wires = fb
+        .input_wire_ranges()
+        .iter()
+        .flat_map(|(type_id, range)| {
+            (range.first_id..=range.last_id).map(move |wire_id| (*type_id, wire_id))
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(input_wires, fb.input_wires());
+    Ok(())
+}
+
+#[test]
+fn test_function_builder_add_gate_sequence() -> Result<()> {
+    use crate::producers::builder::BuildGate::*;
+
+    let b = GateBuilder::new(
+        MemorySink::default(),
+        &[],
+        &[Type::Field(vec![7]), Type::Field(vec![101])],
+        &[],
+    );
+    let mut fb = b.new_function_builder(
+        "f".to_string(),
+        vec![],
+        vec![Count::new(0, 1), Count::new(1, 1)],
+    );
+    // 100 gates alternating between both types, and ending with AssertZero gates
+    let mut gates = vec![];
+    for i in 0..48 {
+        let type_id = (i % 2) as TypeId;
+        gates.push(Constant(type_id, vec![(i % 7) as u8]));
+    }
+    gates.push(AddConstant(0, 0, vec![1]));
+    gates.push(MulConstant(1, 0, vec![2]));
+    for i in 50..100 {
+        gates.push(AssertZero((i % 2) as TypeId, 0));
+    }
+    let outputs = fb.add_gate_sequence(gates);
+    assert_eq!(outputs.len(), 100);
+    assert!(outputs[50..].iter().all(|wire| *wire == NO_OUTPUT));
+
+    // Output wires of each type are contiguous, after the input wire
+    for type_id in 0..2 {
+        let wires = outputs[..50]
+            .iter()
+            .skip(type_id)
+            .step_by(2)
+            .cloned()
+            .collect::<Vec<_>>();
+        assert_eq!(wires, (1..=25).collect::<Vec<_>>());
+    }
+    assert_eq!(fb.create_gate(Constant(0, vec![1])), 26);
+    Ok(())
+}
+
+#[test]
+fn test_builder_register_function_from_gates() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::consumers::source::Source;
+    use crate::format::bristol::from_bristol;
+    use crate::producers::builder::BuildGate::*;
+    use std::collections::HashMap;
+
+    // a OR b = (a AND b) XOR (a XOR b), with inputs 0 and 1 and output 4
+    let bristol = "3 5\n2 1 1\n1 1\n\n2 1 0 1 2 AND\n2 1 0 1 3 XOR\n2 1 2 3 4 XOR\n";
+    let relation = from_bristol(bristol)?;
+    // Renumber the wires in the scope of the function: output 0, then inputs 1 and 2
+    let map = HashMap::from([
+        ((0, 0), 1),
+        ((0, 1), 2),
+        ((0, 2), 3),
+        ((0, 3), 4),
+        ((0, 4), 0),
+    ]);
+    let gates = relation
+        .directives
+        .into_iter()
+        .filter_map(|directive| match directive {
+            Directive::Gate(Gate::Private(_, _)) => None,
+            Directive::Gate(gate) => Some(gate.substitute_wires(&map)),
+            Directive::Function(_) => None,
+        })
+        .collect::<Vec<_>>();
+
+    let mut b = new_example_builder();
+    let output_count = vec![Count::new(0, 1)];
+    let input_count = vec![Count::new(0, 1), Count::new(0, 1)];
+    // The output wire must be set, and the inputs must be set before being used
+    assert!(b
+        .register_function_from_gates(
+            "or".to_string(),
+            output_count.clone(),
+            input_count.clone(),
+            gates[..2].to_vec(),
+        )
+        .is_err());
+    assert!(b
+        .register_function_from_gates(
+            "or".to_string(),
+            output_count.clone(),
+            input_count.clone(),
+            vec![Gate::Add(0, 0, 1, 5)],
+        )
+        .is_err());
+    b.register_function_from_gates("or".to_string(), output_count, input_count, gates)?;
+
+    for (left, right) in [(0u8, 0u8), (0, 1), (1, 0), (1, 1)] {
+        let left_wire = b.create_gate(Private(0, Some(vec![left])))?;
+        let right_wire = b.create_gate(Private(0, Some(vec![right])))?;
+        let or = b.create_call(
+            "or",
+            vec![
+                WireRange::new(left_wire, left_wire),
+                WireRange::new(right_wire, right_wire),
+            ],
+            vec![],
+            vec![],
+        )?;
+        let diff = b.create_gate(AddConstant(0, or.wire(0)?, vec![left | right]))?;
+        b.create_gate(AssertZero(0, diff))?;
+    }
+
+    let source: Source = b.finish().into();
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(evaluator.get_violations(), Vec::<String>::new());
+    Ok(())
+}
+
+#[test]
+fn test_builder_dry_run() -> Result<()> {
+    use crate::consumers::source::Source;
+    use crate::consumers::stats::Stats;
+    use crate::producers::builder::BuildGate::*;
+
+    fn circuit(b: &mut impl GateBuilderT) -> Result<()> {
+        let x = b.create_gate(Private(1, Some(vec![5])))?;
+        let y = b.create_gate(Public(1, Some(vec![25])))?;
+        let square = b.create_complex_gate(
+            BuildComplexGate::Call("square".to_string(), vec![WireRange::new(x, x)]),
+            vec![],
+            vec![],
+        )?;
+        let cube = b.create_gate(Mul(1, square[0].first_id, x))?;
+        let diff = b.create_gate(MulConstant(1, y, vec![100]))?;
+        let diff = b.create_gate(Add(1, square[0].first_id, diff))?;
+        b.create_gate(AssertZero(1, diff))?;
+        b.create_gate(Delete(1, cube, cube))?;
+        Ok(())
+    }
+    let b = extended_example_builder()?;
+    let (gate_count, mul_count) = (b.current_gate_count(), b.total_mul_count());
+    let (b, estimate) = b.dry_run(circuit);
+    assert_eq!(
+        estimate,
+        CircuitEstimate {
+            estimated_gate_count: 8,
+            estimated_mul_count: 1,
+            estimated_public_input_count: 1,
+            estimated_private_input_count: 1,
+            error: None,
+        }
+    );
+    // The builder is unchanged
+    assert_eq!(b.current_gate_count(), gate_count);
+    assert_eq!(
+        b.current_wire_counts(),
+        extended_example_builder()?.current_wire_counts()
+    );
+    let (mut b, failed_estimate) = b.dry_run(|dry_run_builder| {
+        dry_run_builder.create_gate(Constant(1, vec![1]))?;
+        dry_run_builder.create_gate(Constant(9, vec![1]))?;
+        Ok(())
+    });
+    assert_eq!(failed_estimate.estimated_gate_count, 1);
+    assert!(failed_estimate.error.is_some());
+
+    // The estimate matches the circuit actually built
+    circuit(&mut b)?;
+    assert_eq!(
+        b.current_gate_count() - gate_count,
+        estimate.estimated_gate_count
+    );
+    assert_eq!(
+        b.total_mul_count() - mul_count,
+        estimate.estimated_mul_count
+    );
+    let source: Source = b.finish().into();
+    let stats = Stats::from_messages(source.iter_messages());
+    let example_source: Source = extended_example_builder()?.finish().into();
+    let example_stats = Stats::from_messages(example_source.iter_messages());
+    assert_eq!(
+        stats.gate_stats.public_inputs_consumed - example_stats.gate_stats.public_inputs_consumed,
+        estimate.estimated_public_input_count
+    );
+    assert_eq!(
+        stats.gate_stats.private_inputs_consumed - example_stats.gate_stats.private_inputs_consumed,
+        estimate.estimated_private_input_count
+    );
+    Ok(())
+}
+
+#[test]
+fn test_builder_gate_callback() -> Result<()> {
+    use crate::consumers::source::Source;
+    use crate::consumers::stats::Stats;
+    use crate::producers::builder::BuildGate::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let mul_count = Rc::new(RefCell::new(0));
+    let outputs = Rc::new(RefCell::new(vec![]));
+    let mut b = GateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![101])], &[])
+        .with_gate_callback({
+            let mul_count = mul_count.clone();
+            let outputs = outputs.clone();
+            move |gate, out_id| {
+                if let Gate::Mul(_, _, _, _) = gate {
+                    *mul_count.borrow_mut() += 1;
+                }
+                outputs.borrow_mut().push(out_id);
+            }
+        });
+
+    let x = b.create_gate(Private(0, Some(vec![3])))?;
+    let mut power = x;
+    for _ in 0..5 {
+        power = b.create_gate(Mul(0, power, x))?;
+    }
+    let sum = b.create_gate(Add(0, power, x))?;
+    let square = b.create_gate(Mul(0, sum, sum))?;
+    b.create_gate(AssertZero(0, square))?;
+    assert_eq!(*mul_count.borrow(), 6);
+    assert_eq!(outputs.borrow().len(), 9);
+    assert_eq!(outputs.borrow()[..3], [x, x + 1, x + 2]);
+    assert_eq!(outputs.borrow()[8], NO_OUTPUT);
+
+    let source: Source = b.finish().into();
+    let stats = Stats::from_messages(source.iter_messages());
+    assert_eq!(stats.gate_stats.mul_gates, *mul_count.borrow());
+    Ok(())
+}
+
+#[test]
+fn test_builder_input_arrays() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::consumers::source::Source;
+
+    let mut b = GateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![101])], &[]);
+    let values = vec![vec![1], vec![2], vec![3], vec![4]];
+    let public = b.create_public_input_array(0, values.clone())?;
+    assert_eq!(public.last_id - public.first_id + 1, values.len() as u64);
+    let private = b.create_private_input_array(0, values.clone())?;
+    assert_eq!(private, WireRange::new(4, 7));
+    assert!(b.create_private_input_array(0, vec![]).is_err());
+
+    let source: Source = b.finish().into();
+    let messages = source.read_all_messages()?;
+    assert_eq!(messages.public_inputs[0].inputs, values);
+    assert_eq!(messages.private_inputs[0].inputs, values);
+
+    let mut backend = PlaintextBackend::default();
+    let evaluator = Evaluator::from_messages(source.iter_messages(), &mut backend);
+    assert_eq!(evaluator.get(0, 6)?, &BigUint::from(3u8));
+    Ok(())
+}
+
+#[test]
+fn test_builder_aliases() -> Result<()> {
+    use crate::producers::builder::BuildGate::*;
+
+    let mut b = new_example_builder();
+    let x = b.create_gate(Private(0, Some(vec![1])))?;
+    let y = b.create_gate(Private(0, Some(vec![1])))?;
+    let sum = b.create_gate(Add(0, x, y))?;
+    b.alias_wire(x, 0, "x");
+    b.alias_wire(sum, 0, "x_plus_y");
+
+    assert_eq!(b.resolve_alias("x"), Some((0, x)));
+    assert_eq!(b.resolve_alias("x_plus_y"), Some((0, sum)));
+    assert_eq!(b.resolve_alias("y"), None);
+    assert_eq!(
+        b.dump_aliases(),
+        BTreeMap::from([
+            ("x".to_string(), (0, x)),
+            ("x_plus_y".to_string(), (0, sum)),
+        ])
+    );
+    Ok(())
+}
+
+#[test]
+fn test_builder_iter_plugin() -> Result<()> {
+    use crate::consumers::source::Source;
+    use crate::producers::builder::{BuildComplexGate::*, BuildGate::*};
+
+    let mut b = GateBuilder::new(
+        MemorySink::default(),
+        &["iter".to_string()],
+        &[Type::Field(vec![101])],
+        &[],
+    );
+
+    // square(x) = x * x
+    let mut fb = b.new_function_builder(
+        "square".to_string(),
+        vec![Count::new(0, 1)],
+        vec![Count::new(0, 1)],
+    );
+    let x = fb.input_wires()[0].1;
+    let x_squared = fb.create_gate(Mul(0, x, x));
+    let square = fb.finish(vec![WireRange::new(x_squared, x_squared)])?;
+    b.push_function(square)?;
+
+    // add(acc, x) = acc + x
+    let mut fb = b.new_function_builder(
+        "add".to_string(),
+        vec![Count::new(0, 1)],
+        vec![Count::new(0, 1), Count::new(0, 1)],
+    );
+    let inputs = fb.input_wire_ranges();
+    let sum = fb.create_gate(Add(0, inputs[0].1.first_id, inputs[1].1.first_id));
+    let add = fb.finish(vec![WireRange::new(sum, sum)])?;
+    b.push_function(add)?;
+
+    b.push_iter_plugin(
+        "square_3".to_string(),
+        "square".to_string(),
+        3,
+        vec![Count::new(0, 3)],
+        vec![Count::new(0, 3)],
+    )?;
+    b.push_iter_plugin(
+        "sum_3".to_string(),
+        "add".to_string(),
+        3,
+        vec![Count::new(0, 1)],
+        vec![Count::new(0, 1), Count::new(0, 3)],
+    )?;
+    // Incompatible signatures and unknown body functions are rejected
+    assert!(b
+        .push_iter_plugin(
+            "wrong".to_string(),
+            "square".to_string(),
+            3,
+            vec![Count::new(0, 2)],
+            vec![Count::new(0, 3)],
+        )
+        .is_err());
+    assert!(b
+        .push_iter_plugin(
+            "unknown".to_string(),
+            "cube".to_string(),
+            3,
+            vec![Count::new(0, 3)],
+            vec![Count::new(0, 3)],
+        )
+        .is_err());
+
+    let values = b.create_private_input_array(0, vec![vec![1], vec![2], vec![3]])?;
+    let squares =
+        b.create_complex_gate(Call("square_3".to_string(), vec![values]), vec![], vec![])?;
+    assert_eq!(squares, vec![WireRange::new(3, 5)]);
+    let zero = b.create_zero(0)?;
+    let sum = b.create_complex_gate(
+        Call(
+            "sum_3".to_string(),
+            vec![WireRange::new(zero, zero), squares[0]],
+        ),
+        vec![],
+        vec![],
+    )?;
+    assert_eq!(sum, vec![WireRange::new(7, 7)]);
+
+    let source: Source = b.finish().into();
+    let relation = &source.read_all_messages()?.relations[0];
+    let plugin_bodies = relation
+        .directives
+        .iter()
+        .filter_map(|directive| match directive {
+            Directive::Function(function) => match &function.body {
+                FunctionBody::PluginBody(plugin_body) => {
+                    Some((plugin_body.operation.as_str(), plugin_body.params.clone()))
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        plugin_bodies,
+        vec![
+            ("map", vec!["square".to_string(), "3".to_string()]),
+            ("reduce", vec!["add".to_string(), "3".to_string()]),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_builder_max_len() -> Result<()> {
+    use crate::consumers::source::Source;
+    use crate::producers::builder::BuildGate::*;
+
+    let mut b = GateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![101])], &[]);
+    assert_eq!(b.max_len(), 100 * 1000);
+    assert!(b.set_max_len(0).is_err());
+    b.set_max_len(10)?;
+    assert_eq!(b.max_len(), 10);
+
+    let mut acc = b.create_gate(Constant(0, vec![1]))?;
+    for _ in 1..25 {
+        acc = b.create_gate(AddConstant(0, acc, vec![1]))?;
+    }
+    assert!(b.set_max_len(100).is_err());
+    assert_eq!(b.max_len(), 10);
+
+    let source: Source = b.finish().into();
+    let messages = source.read_all_messages()?;
+    assert!(messages.relations.len() >= 2);
+    let gate_count = messages
+        .relations
+        .iter()
+        .map(|relation| relation.directives.len())
+        .sum::<usize>();
+    assert_eq!(gate_count, 25);
+    Ok(())
+}
+
+#[test]
+fn test_builder_push_type() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::consumers::source::Source;
+    use crate::consumers::validator::Validator;
+    use crate::producers::builder::BuildGate::*;
+
+    let mut b = GateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![101])], &[]);
+    let x = b.create_gate(Private(0, Some(vec![10])))?;
+    let y = b.create_gate(AddConstant(0, x, vec![91]))?;
+    b.create_gate(AssertZero(0, y))?;
+    assert!(b.create_gate(Constant(1, vec![1])).is_err());
+
+    // The new type can be used once pushed
+    assert_eq!(b.push_type(vec![7])?, 1);
+    let z = b.create_gate(Private(1, Some(vec![3])))?;
+    let w = b.create_gate(AddConstant(1, z, vec![4]))?;
+    b.create_gate(AssertZero(1, w))?;
+
+    let source: Source = b.finish().into();
+    let messages = source.read_all_messages()?;
+    assert_eq!(
+        messages.relations[0].types,
+        vec![Type::Field(vec![101]), Type::Field(vec![7])]
+    );
+    assert_eq!(
+        Validator::ingest_all_from_source(&source),
+        Vec::<String>::new()
+    );
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(evaluator.get_violations(), Vec::<String>::new());
+
+    // Types can be added once the first relation message has been flushed
+    let mut b = GateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![101])], &[]);
+    b.set_max_len(2)?;
+    let x = b.create_gate(Private(0, Some(vec![10])))?;
+    let y = b.create_gate(AddConstant(0, x, vec![91]))?;
+    b.create_gate(AssertZero(0, y))?;
+    assert_eq!(b.push_type(vec![7])?, 1);
+    let z = b.create_gate(Private(1, Some(vec![3])))?;
+    let w = b.create_gate(AddConstant(1, z, vec![4]))?;
+    b.create_gate(AssertZero(1, w))?;
+
+    let source: Source = b.finish().into();
+    let messages = source.read_all_messages()?;
+    assert_eq!(messages.relations[0].types, vec![Type::Field(vec![101])]);
+    assert_eq!(messages.relations[1].types, vec![Type::Field(vec![7])]);
+    assert_eq!(
+        Validator::ingest_all_from_source(&source),
+        Vec::<String>::new()
+    );
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(evaluator.get_violations(), Vec::<String>::new());
+    Ok(())
+}
+
+#[test]
+fn test_builder_per_type_max_len() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::consumers::source::Source;
+    use crate::producers::builder::BuildGate::*;
+
+    let mut b = GateBuilder::new(
+        MemorySink::default(),
+        &[],
+        &[Type::Field(vec![101]), Type::Field(vec![7])],
+        &[],
+    );
+    b.set_max_len(4)?;
+    assert!(b.set_per_type_max_len(0, 0).is_err());
+    assert!(b.set_per_type_max_len(2, 10).is_err());
+    b.set_per_type_max_len(0, 10)?;
+
+    // Many public inputs of type 0 and a few of type 1
+    for i in 0..25u8 {
+        let x = b.create_gate(Public(0, Some(vec![i])))?;
+        let y = b.create_gate(AddConstant(0, x, vec![(101 - i) % 101]))?;
+        b.create_gate(AssertZero(0, y))?;
+    }
+    for i in 0..6u8 {
+        let x = b.create_gate(Public(1, Some(vec![i])))?;
+        let y = b.create_gate(AddConstant(1, x, vec![(7 - i) % 7]))?;
+        b.create_gate(AssertZero(1, y))?;
+    }
+
+    let source: Source = b.finish().into();
+    let messages = source.read_all_messages()?;
+    let public_input_lens = |modulo: u8| {
+        messages
+            .public_inputs
+            .iter()
+            .filter(|inputs| inputs.type_value == Type::Field(vec![modulo]))
+            .map(|inputs| inputs.inputs.len())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(public_input_lens(101), vec![10, 10, 5]);
+    assert_eq!(public_input_lens(7), vec![4, 2]);
+
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(evaluator.get_violations(), Vec::<String>::new());
+    Ok(())
+}
+
+#[test]
+fn test_builder_type_names() -> Result<()> {
+    use crate::consumers::source::Source;
+
+    let mut b = GateBuilder::new_named(
+        MemorySink::default(),
+        &[
+            ("F101".to_string(), vec![101]),
+            ("Boolean".to_string(), vec![2]),
+        ],
+    );
+    assert_eq!(b.type_name(0), Some("F101"));
+    assert_eq!(b.type_name(1), Some("Boolean"));
+    assert_eq!(b.type_name(2), None);
+
+    let x = b.create_one(0)?;
+    let err = b.create_xor(0, x, x).unwrap_err();
+    assert!(err.to_string().contains("Type 'F101'"));
+
+    let ring = Type::new_plugin_type(
+        "zkif_ring".to_string(),
+        "type".to_string(),
+        vec!["2".to_string(), "8".to_string()],
+    );
+    let mut b = GateBuilder::new(
+        MemorySink::default(),
+        &["zkif_ring".to_string()],
+        &[Type::Field(vec![101]), ring],
+        &[],
+    );
+    let err = b.create_neg_one(1).unwrap_err();
+    assert_eq!(err.to_string(), "Type id 1 is not a Field type.");
+    let mut b = b.with_type_names(vec!["F101".to_string(), "Z_256".to_string()]);
+    let err = b.create_neg_one(1).unwrap_err();
+    assert_eq!(err.to_string(), "Type 'Z_256' is not a Field type.");
+
+    // Names are not serialized
+    let source: Source = b.finish().into();
+    let messages = source.read_all_messages()?;
+    assert_eq!(messages.relations[0].types[0], Type::Field(vec![101]));
+    Ok(())
+}
+
+#[test]
+fn test_builder_constant_validation() -> Result<()> {
+    use crate::producers::builder::BuildGate::*;
+
+    let mut b = GateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![101])], &[]);
+    assert!(b.create_gate(Constant(0, vec![100])).is_ok());
+    let err = b.create_gate(Constant(0, vec![101])).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "The constant 101 is not an element of Type id 0"
+    );
+    let x = b.create_gate(Private(0, Some(vec![3])))?;
+    assert!(b.create_gate(AddConstant(0, x, vec![0, 1])).is_err());
+    assert!(b.create_gate(MulConstant(0, x, vec![255])).is_err());
+    // Trailing zeros do not change the value
+    assert!(b.create_gate(MulConstant(0, x, vec![100, 0, 0])).is_ok());
+
+    b.skip_constant_validation();
+    assert!(b.create_gate(Constant(0, vec![101])).is_ok());
+    Ok(())
+}
+
+#[test]
+fn test_builder_prover_mode() -> Result<()> {
+    use crate::producers::builder::BuildGate::*;
+    use crate::Source;
+
+    let build = |b: &mut GateBuilder<MemorySink>, values: &[Option<Value>]| -> Result<()> {
+        let x = b.create_gate(Private(0, values[0].clone()))?;
+        let y = b.create_gate(Private(1, values[1].clone()))?;
+        let z = b.create_gate(Private(0, values[2].clone()))?;
+        let xz = b.create_gate(Mul(0, x, z))?;
+        b.create_gate(AssertZero(0, xz))?;
+        b.create_gate(AssertZero(1, y))?;
+        Ok(())
+    };
+    let types = [vec![101], vec![7]];
+
+    let mut explicit = GateBuilder::new(
+        MemorySink::default(),
+        &[],
+        &[Type::Field(types[0].clone()), Type::Field(types[1].clone())],
+        &[],
+    );
+    build(
+        &mut explicit,
+        &[Some(vec![3]), Some(vec![0]), Some(vec![0])],
+    )?;
+    let explicit: Source = explicit.finish().into();
+
+    let mut context = ProverContext::default();
+    context.push_witness(0, vec![3]);
+    context.push_witness(1, vec![0]);
+    context.push_witness(0, vec![0]);
+    let mut prover = GateBuilder::new_with_prover_mode(MemorySink::default(), &types, context);
+    build(&mut prover, &[None, None, None])?;
+    let prover: Source = prover.finish().into();
+
+    assert_eq!(prover.read_all_messages()?, explicit.read_all_messages()?);
+
+    // The context runs out of values of type 1
+    let mut context = ProverContext::default();
+    context.push_witness(0, vec![3]);
+    context.push_witness(1, vec![0]);
+    let mut b = GateBuilder::new_with_prover_mode(MemorySink::default(), &types, context);
+    b.create_gate(Private(1, None))?;
+    b.create_gate(Private(0, None))?;
+    let err = b.create_gate(Private(1, None)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "The prover context has no more private input values for Type id 1"
+    );
+    // Explicit values are still accepted
+    b.create_gate(Private(1, Some(vec![2])))?;
+    Ok(())
+}
+
+#[test]
+fn test_builder_wire_values() -> Result<()> {
+    use crate::producers::builder::BuildGate::*;
+
+    let mut context = ProverContext::default();
+    context.push_witness(0, vec![7]);
+    let mut b = GateBuilder::new_with_prover_mode(MemorySink::default(), &[vec![101]], context);
+    let x = b.create_gate(Public(0, Some(vec![60])))?;
+    let y = b.create_gate(Private(0, None))?;
+    let z = b.create_gate(Private(0, Some(vec![5])))?;
+    let sum = b.create_gate(Add(0, x, y))?;
+    let product = b.create_gate(Mul(0, sum, y))?;
+    let shifted = b.create_gate(AddConstant(0, product, vec![50]))?;
+    let scaled = b.create_gate(MulConstant(0, shifted, vec![2]))?;
+    assert_eq!(b.wire_value(0, y), Some(&vec![7]));
+    assert_eq!(b.wire_value(0, sum), Some(&vec![67]));
+    assert_eq!(b.wire_value(0, product), Some(&vec![65]));
+    assert_eq!(b.wire_value(0, shifted), Some(&vec![14]));
+    assert_eq!(b.wire_value(0, scaled), Some(&vec![28]));
+
+    let snapshot = b.snapshot();
+    b.create_gate(Delete(0, z, z))?;
+    assert_eq!(b.wire_value(0, z), None);
+    b.rollback(snapshot)?;
+    assert_eq!(b.wire_value(0, z), Some(&vec![5]));
+
+    // The values of the private inputs are unknown to the verifier
+    let mut b = GateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![101])], &[]);
+    let x = b.create_gate(Public(0, Some(vec![3])))?;
+    let y = b.create_gate(Private(0, None))?;
+    let sum = b.create_gate(Add(0, x, y))?;
+    assert_eq!(b.wire_value(0, x), Some(&vec![3]));
+    assert_eq!(b.wire_value(0, sum), None);
+    Ok(())
+}
+
+#[test]
+fn test_function_builder_wires_by_type() -> Result<()> {
+    use crate::producers::builder::BuildGate::*;
+
+    let b = GateBuilder::new(
+        MemorySink::default(),
+        &[],
+        &[
+            Type::Field(vec![7]),
+            Type::Field(vec![101]),
+            Type::Field(vec![11]),
+        ],
+        &[],
+    );
+    let mut fb = b.new_function_builder(
+        "f".to_string(),
+        vec![Count::new(1, 1), Count::new(0, 2), Count::new(1, 2)],
+        vec![
+            Count::new(2, 2),
+            Count::new(1, 1),
+            Count::new(0, 1),
+            Count::new(2, 1),
+        ],
+    );
+    assert_eq!(
+        fb.output_wire_ranges_by_type(),
+        BTreeMap::from([(0, WireRange::new(0, 1)), (1, WireRange::new(0, 2))])
+    );
+    assert_eq!(
+        fb.input_wires_by_type(),
+        BTreeMap::from([(0, vec![2]), (1, vec![3]), (2, vec![0, 1, 2])])
+    );
+
+    // New wires are allocated after the inputs of their type
+    let inputs = fb.input_wires_by_type();
+    let sum_1 = fb.create_gate(Add(1, inputs[&1][0], inputs[&1][0]));
+    assert_eq!(sum_1, 4);
+    let sum_2 = fb.create_gate(Add(2, inputs[&2][0], inputs[&2][2]));
+    assert_eq!(sum_2, 3);
+    let sum_0 = fb.create_gate(Add(0, inputs[&0][0], inputs[&0][0]));
+    assert_eq!(sum_0, 3);
+    Ok(())
+}
+
+#[test]
+fn test_builder_checkpoints() -> Result<()> {
+    use crate::producers::builder::BuildGate::*;
+
+    let mut b = GateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![101])], &[]);
+    b.set_max_len(4)?;
+    let x = b.create_gate(Private(0, Some(vec![3])))?;
+    b.emit_checkpoint("inputs".to_string());
+    // x^5 with square-and-multiply: 2 squarings and 1 multiplication
+    let x5 = b.create_pow(0, x, 5)?;
+    b.emit_checkpoint("pow".to_string());
+    let minus_243 = b.create_gate(Constant(0, vec![60]))?;
+    let diff = b.create_gate(Add(0, x5, minus_243))?;
+    b.create_gate(AssertZero(0, diff))?;
+    b.emit_checkpoint("assert".to_string());
+    b.emit_checkpoint("end".to_string());
+
+    assert_eq!(
+        b.checkpoint_report(),
+        vec![
+            ("inputs".to_string(), 1, 1),
+            ("pow".to_string(), 4, 3),
+            ("assert".to_string(), 7, 3),
+            ("end".to_string(), 7, 0),
+        ]
+    );
+
+    // Checkpoints emitted after a snapshot are removed by the rollback
+    let mut b = GateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![101])], &[]);
+    let x = b.create_gate(Private(0, Some(vec![3])))?;
+    let snapshot = b.snapshot();
+    b.create_pow(0, x, 5)?;
+    b.emit_checkpoint("pow".to_string());
+    b.rollback(snapshot)?;
+    b.emit_checkpoint("inputs".to_string());
+    assert_eq!(b.checkpoint_report(), vec![("inputs".to_string(), 1, 1)]);
+    Ok(())
+}
+
+#[test]
+fn test_builder_verifier_view() -> Result<()> {
+    use crate::consumers::validator::Validator;
+    use crate::producers::builder::BuildGate::*;
+    use crate::Message;
+
+    let mut b = GateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![101])], &[]);
+    let private_square = {
+        let mut fb =
+            b.new_function_builder("private_square".to_string(), vec![Count::new(0, 1)], vec![]);
+        let private_wire = fb.create_gate(Private(0, None));
+        let output_wire = fb.create_gate(Mul(0, private_wire, private_wire));
+        fb.finish(vec![WireRange::new(output_wire, output_wire)])?
+    };
+    b.push_function(private_square)?;
+
+    // Prove that we know x, y and w such that x * y = z and w^2 = t for public z and t
+    b.create_gate(New(0, 0, 1))?;
+    let x = b.create_gate(Private(0, Some(vec![3])))?;
+    let y = b.create_gate(Private(0, Some(vec![5])))?;
+    let xy = b.create_gate(Mul(0, x, y))?;
+    let extra = b.create_gate(Private(0, Some(vec![1])))?;
+    let z = b.create_gate(Public(0, Some(vec![15])))?;
+    let minus_z = b.create_gate(MulConstant(0, z, vec![100]))?;
+    let diff = b.create_gate(Add(0, xy, minus_z))?;
+    let diff = b.create_gate(Mul(0, diff, extra))?;
+    b.create_gate(AssertZero(0, diff))?;
+    let w2 = b
+        .create_call("private_square", vec![], vec![], vec![vec![vec![4]]])?
+        .wire(0)?;
+    let t = b.create_gate(Public(0, Some(vec![16])))?;
+    let minus_t = b.create_gate(MulConstant(0, t, vec![100]))?;
+    let diff = b.create_gate(Add(0, w2, minus_t))?;
+    b.create_gate(AssertZero(0, diff))?;
+
+    let relation = b.build_verifier_view()?;
+    let private_gates = relation
+        .directives
+        .iter()
+        .flat_map(|directive| match directive {
+            Directive::Gate(gate) => vec![gate.clone()],
+            Directive::Function(function) => match &function.body {
+                FunctionBody::Gates(gates) => gates.clone(),
+                FunctionBody::PluginBody(_) => vec![],
+            },
+        })
+        .filter(|gate| matches!(gate, Gate::Private(_, _)))
+        .count();
+    assert_eq!(private_gates, 0);
+    assert_eq!(relation.directives[1], Directive::Gate(Gate::New(0, 0, 1)));
+    assert_eq!(
+        relation.directives[3],
+        Directive::Gate(Gate::New(0, extra, extra))
+    );
+
+    // The messages emitted by the builder are those of the verifier
+    let source: crate::Source = b.finish().into();
+    let messages = source.read_all_messages()?;
+    assert_eq!(messages.relations, vec![relation]);
+    assert!(messages
+        .private_inputs
+        .iter()
+        .all(|private_inputs| private_inputs.inputs.is_empty()));
+    let mut validator = Validator::new_as_verifier();
+    for inputs in messages.public_inputs {
+        validator.ingest_message(&Message::PublicInputs(inputs));
+    }
+    for relation in messages.relations {
+        validator.ingest_message(&Message::Relation(relation));
+    }
+    assert_eq!(validator.get_violations(), Vec::<String>::new());
+    Ok(())
+}
+
+#[test]
+fn test_builder_shared_registry() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::producers::builder::BuildGate::*;
+    use crate::Source;
+
+    // The first builder defines the function `square`
+    let mut library = GateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![101])], &[]);
+    let square = {
+        let mut fb = library.new_function_builder(
+            "square".to_string(),
+            vec![Count::new(0, 1)],
+            vec![Count::new(0, 1)],
+        );
+        let input = fb.input_wires()[0].1;
+        let output = fb.create_gate(Mul(0, input, input));
+        fb.finish(vec![WireRange::new(output, output)])?
+    };
+    library.push_function(square)?;
+    let registry = library.clone_registry();
+    assert!(registry.0.contains_key("square"));
+
+    // The second builder calls it
+    let mut b = GateBuilder::new_with_registry(MemorySink::default(), &[vec![101]], registry);
+    let x = b.create_gate(Private(0, Some(vec![7])))?;
+    let x2 = b.create_complex_gate(
+        BuildComplexGate::Call("square".to_string(), vec![WireRange::new(x, x)]),
+        vec![],
+        vec![],
+    )?;
+    let diff = b.create_gate(AddConstant(0, x2[0].first_id, vec![52]))?;
+    b.create_gate(AssertZero(0, diff))?;
+
+    // The function cannot be defined twice
+    let mut fb = b.new_function_builder("square".to_string(), vec![], vec![]);
+    let duplicate = fb.finish(vec![])?;
+    assert!(b.push_function(duplicate).is_err());
+
+    let library: Source = library.finish().into();
+    let b: Source = b.finish().into();
+    let source = Source::from_buffers(library.iter_buffers().chain(b.iter_buffers()).collect());
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(evaluator.get_violations(), Vec::<String>::new());
+    Ok(())
+}
+
+#[test]
+fn test_builder_current_counts() -> Result<()> {
+    use crate::producers::builder::BuildGate::*;
+
+    let mut b = GateBuilder::new(
+        MemorySink::default(),
+        &[],
+        &[Type::Field(vec![101]), Type::Field(vec![7])],
+        &[],
+    );
+    b.set_max_len(2)?;
+    assert_eq!(b.current_gate_count(), 0);
+    assert!(b.current_wire_counts().is_empty());
+
+    let mut previous_count = 0;
+    let mut x = b.create_gate(Private(0, Some(vec![3])))?;
+    for _ in 0..5 {
+        x = b.create_gate(Mul(0, x, x))?;
+        let count = b.current_gate_count();
+        assert!(count > previous_count);
+        previous_count = count;
+    }
+    b.create_gate(Constant(1, vec![2]))?;
+    // Some gates have been flushed to the sink, but they are still counted
+    assert_eq!(b.current_gate_count(), 7);
+    assert_eq!(
+        b.current_wire_counts(),
+        [(0, 6), (1, 1)].iter().cloned().collect::<BTreeMap<_, _>>()
+    );
+    Ok(())
+}
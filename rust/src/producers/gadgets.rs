@@ -0,0 +1,3030 @@
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+use crate::producers::builder::{BuildComplexGate, BuildGate::*, GateBuilder, GateBuilderT};
+use crate::structs::count::Count;
+use crate::structs::value::{remove_trailing_zeros, value_to_biguint};
+use crate::structs::value_arithmetic::{value_add, value_inv, value_mul, value_neg, value_pow};
+use crate::structs::wirerange::{WireRange, WireRangeWithType};
+use crate::{Result, Sink, TypeId, Value, WireId};
+
+/// Returns the Non-Adjacent Form of `scalar`, least significant digit first.
+/// Each digit is -1, 0 or 1, and no two consecutive digits are non-zero.
+fn naf_digits(scalar: &BigUint) -> Vec<i8> {
+    let mut k = scalar.clone();
+    let mut digits = vec![];
+    while !k.is_zero() {
+        if &k % 2u8 == BigUint::one() {
+            if &k % 4u8 == BigUint::one() {
+                digits.push(1);
+                k -= 1u8;
+            } else {
+                digits.push(-1);
+                k += 1u8;
+            }
+        } else {
+            digits.push(0);
+        }
+        k >>= 1;
+    }
+    digits
+}
+
+/// Returns an error (prefixed with `gadget_name`) if `permutation` is not a permutation
+/// of `0..permutation.len()`.
+fn check_permutation(gadget_name: &str, permutation: &[usize]) -> Result<()> {
+    let mut sorted = permutation.to_vec();
+    sorted.sort_unstable();
+    if sorted.iter().enumerate().any(|(i, index)| i != *index) {
+        return Err(format!("{}: {:?} is not a permutation.", gadget_name, permutation).into());
+    }
+    Ok(())
+}
+
+impl<S: Sink> GateBuilder<S> {
+    /// Creates the gates computing `left - right`.
+    fn create_sub(&mut self, type_id: TypeId, left: WireId, right: WireId) -> Result<WireId> {
+        let minus_one = self.minus_one(type_id)?;
+        let neg_right = self.create_gate(MulConstant(type_id, right, minus_one))?;
+        self.create_gate(Add(type_id, left, neg_right))
+    }
+
+    /// Swaps `a` and `b` if and only if the selector bit `sel` is 1.
+    /// It returns `(a + sel*(b-a), b + sel*(a-b))`.
+    /// `sel` is assumed to be boolean (0 or 1).
+    pub fn create_conditional_swap(
+        &mut self,
+        type_id: TypeId,
+        sel: WireId,
+        a: WireId,
+        b: WireId,
+    ) -> Result<(WireId, WireId)> {
+        let b_minus_a = self.create_sub(type_id, b, a)?;
+        let sel_b_minus_a = self.create_gate(Mul(type_id, sel, b_minus_a))?;
+        let out_a = self.create_gate(Add(type_id, a, sel_b_minus_a))?;
+
+        let a_minus_b = self.create_sub(type_id, a, b)?;
+        let sel_a_minus_b = self.create_gate(Mul(type_id, sel, a_minus_b))?;
+        let out_b = self.create_gate(Add(type_id, b, sel_a_minus_b))?;
+
+        Ok((out_a, out_b))
+    }
+
+    /// Returns `if_one` if the selector bit `sel` is 1 and `if_zero` if it is 0,
+    /// computed as `if_zero + sel*(if_one-if_zero)` with a single multiplication.
+    /// `sel` is assumed to be boolean (0 or 1).
+    pub fn create_mux(
+        &mut self,
+        type_id: TypeId,
+        sel: WireId,
+        if_zero: WireId,
+        if_one: WireId,
+    ) -> Result<WireId> {
+        let diff = self.create_sub(type_id, if_one, if_zero)?;
+        let sel_diff = self.create_gate(Mul(type_id, sel, diff))?;
+        self.create_gate(Add(type_id, if_zero, sel_diff))
+    }
+
+    /// Computes several linear combinations of the same `inputs`:
+    /// the output `i` is equal to `sum_j matrix[i][j] * inputs[j]`.
+    /// Each product `coefficient * input` is computed only once and reused by all outputs
+    /// sharing this coefficient for this input. Coefficients 0 and 1 do not require any gate.
+    pub fn create_sum_of_products(
+        &mut self,
+        type_id: TypeId,
+        inputs: &[WireId],
+        matrix: &[Vec<Value>],
+    ) -> Result<Vec<WireId>> {
+        let mut products: BTreeMap<(usize, Value), WireId> = BTreeMap::new();
+        let mut outputs = vec![];
+        for row in matrix.iter() {
+            if row.len() != inputs.len() {
+                return Err(format!(
+                    "create_sum_of_products: a row of the matrix contains {} coefficients instead of {}.",
+                    row.len(),
+                    inputs.len()
+                )
+                .into());
+            }
+            let mut sum: Option<WireId> = None;
+            for (j, coefficient) in row.iter().enumerate() {
+                let coefficient = remove_trailing_zeros(coefficient);
+                let term = if coefficient.is_empty() {
+                    continue;
+                } else if coefficient == [1] {
+                    inputs[j]
+                } else if let Some(product) = products.get(&(j, coefficient.clone())) {
+                    *product
+                } else {
+                    let product =
+                        self.create_gate(MulConstant(type_id, inputs[j], coefficient.clone()))?;
+                    products.insert((j, coefficient), product);
+                    product
+                };
+                sum = Some(match sum {
+                    None => term,
+                    Some(sum) => self.create_gate(Add(type_id, sum, term))?,
+                });
+            }
+            let output = match sum {
+                Some(sum) => sum,
+                None => self.create_zero(type_id)?,
+            };
+            outputs.push(output);
+        }
+        Ok(outputs)
+    }
+
+    /// Multiplies `wire` by the constant `scalar` (little-endian) using its Non-Adjacent Form.
+    /// The accumulator is doubled for each digit (by adding it to itself, so that the constant 2
+    /// is never needed, e.g. in the Boolean field) and `wire` is added (resp. subtracted) for each
+    /// digit 1 (resp. -1). Since no two consecutive NAF digits are non-zero, it requires fewer
+    /// additions than the binary method on average.
+    pub fn create_naf_mul(
+        &mut self,
+        type_id: TypeId,
+        wire: WireId,
+        scalar: &[u8],
+    ) -> Result<WireId> {
+        let digits = naf_digits(&value_to_biguint(scalar));
+        let mut neg_wire: Option<WireId> = None;
+        let mut acc: Option<WireId> = None;
+        for digit in digits.iter().rev() {
+            if let Some(prev) = acc {
+                acc = Some(self.create_gate(Add(type_id, prev, prev))?);
+            }
+            let term = match digit {
+                0 => continue,
+                1 => wire,
+                _ => match neg_wire {
+                    Some(neg_wire) => neg_wire,
+                    None => {
+                        let minus_one = self.minus_one(type_id)?;
+                        let neg = self.create_gate(MulConstant(type_id, wire, minus_one))?;
+                        neg_wire = Some(neg);
+                        neg
+                    }
+                },
+            };
+            acc = Some(match acc {
+                None => term,
+                Some(prev) => self.create_gate(Add(type_id, prev, term))?,
+            });
+        }
+        match acc {
+            Some(acc) => Ok(acc),
+            None => self.create_zero(type_id),
+        }
+    }
+
+    /// Checks that `type_id` is the Boolean field (modulo 2).
+    fn check_binary_field(&self, type_id: TypeId, operation: &str) -> Result<()> {
+        if remove_trailing_zeros(self.get_field_modulo(type_id)?) != [2] {
+            return Err(match self.type_name(type_id) {
+                Some(_) => format!(
+                    "{} not meaningful for non-binary field ({})",
+                    operation,
+                    self.describe_type(type_id)
+                ),
+                None => format!("{} not meaningful for non-binary field", operation),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Creates the gates computing `left XOR right` (i.e. `left + right` in a Boolean field).
+    pub fn create_xor(&mut self, type_id: TypeId, left: WireId, right: WireId) -> Result<WireId> {
+        self.check_binary_field(type_id, "XOR")?;
+        self.create_gate(Add(type_id, left, right))
+    }
+
+    /// Creates the gates computing `left AND right` (i.e. `left * right` in a Boolean field).
+    pub fn create_and(&mut self, type_id: TypeId, left: WireId, right: WireId) -> Result<WireId> {
+        self.check_binary_field(type_id, "AND")?;
+        self.create_gate(Mul(type_id, left, right))
+    }
+
+    /// Creates the gates computing `left OR right` (i.e. `left + right + left * right`
+    /// in a Boolean field).
+    pub fn create_or(&mut self, type_id: TypeId, left: WireId, right: WireId) -> Result<WireId> {
+        self.check_binary_field(type_id, "OR")?;
+        let xor = self.create_gate(Add(type_id, left, right))?;
+        let and = self.create_gate(Mul(type_id, left, right))?;
+        self.create_gate(Add(type_id, xor, and))
+    }
+
+    /// Creates the gates computing `NOT wire` (i.e. `wire + 1` in a Boolean field).
+    pub fn create_not(&mut self, type_id: TypeId, wire: WireId) -> Result<WireId> {
+        self.check_binary_field(type_id, "NOT")?;
+        self.create_gate(AddConstant(type_id, wire, vec![1]))
+    }
+
+    /// Returns `(sum, carry)` = `(a XOR b, a AND b)` in a Boolean field.
+    pub fn create_half_adder(
+        &mut self,
+        type_id: TypeId,
+        a: WireId,
+        b: WireId,
+    ) -> Result<(WireId, WireId)> {
+        let sum = self.create_xor(type_id, a, b)?;
+        let carry = self.create_and(type_id, a, b)?;
+        Ok((sum, carry))
+    }
+
+    /// Returns `(sum, carry_out)` of `a + b + carry_in` in a Boolean field,
+    /// made of two half adders whose carries are combined with an OR gate.
+    pub fn create_full_adder(
+        &mut self,
+        type_id: TypeId,
+        a: WireId,
+        b: WireId,
+        carry_in: WireId,
+    ) -> Result<(WireId, WireId)> {
+        let (partial_sum, partial_carry) = self.create_half_adder(type_id, a, b)?;
+        let (sum, carry) = self.create_half_adder(type_id, partial_sum, carry_in)?;
+        let carry_out = self.create_or(type_id, partial_carry, carry)?;
+        Ok((sum, carry_out))
+    }
+
+    /// Returns the `n + 1` bits (least significant bit first, the last one being the carry-out)
+    /// of the sum of the `n`-bit numbers `a_bits` and `b_bits`, in a Boolean field.
+    /// The least significant bits are added with a half adder, and the others with a chain
+    /// of full adders.
+    pub fn create_binary_adder(
+        &mut self,
+        type_id: TypeId,
+        a_bits: &[WireId],
+        b_bits: &[WireId],
+    ) -> Result<Vec<WireId>> {
+        if a_bits.len() != b_bits.len() {
+            return Err(format!(
+                "create_binary_adder: the numbers have different lengths ({} and {}).",
+                a_bits.len(),
+                b_bits.len()
+            )
+            .into());
+        }
+        if a_bits.is_empty() {
+            return Ok(vec![self.create_zero(type_id)?]);
+        }
+
+        let (sum, mut carry) = self.create_half_adder(type_id, a_bits[0], b_bits[0])?;
+        let mut sum_bits = vec![sum];
+        for (a, b) in a_bits.iter().zip(b_bits.iter()).skip(1) {
+            let (sum, carry_out) = self.create_full_adder(type_id, *a, *b, carry)?;
+            sum_bits.push(sum);
+            carry = carry_out;
+        }
+        sum_bits.push(carry);
+        Ok(sum_bits)
+    }
+
+    /// Reduces the three `n`-bit numbers `a_bits`, `b_bits` and `c_bits` (least significant
+    /// bit first) to two numbers `(partial_sum_bits, carry_bits)`, in a Boolean field,
+    /// such that `a + b + c = partial_sum + 2 * carry`.
+    /// Each position is handled by an independent full adder, so there is no carry chain.
+    pub fn create_carry_save_adder(
+        &mut self,
+        type_id: TypeId,
+        a_bits: &[WireId],
+        b_bits: &[WireId],
+        c_bits: &[WireId],
+    ) -> Result<(Vec<WireId>, Vec<WireId>)> {
+        if a_bits.len() != b_bits.len() || a_bits.len() != c_bits.len() {
+            return Err(format!(
+                "create_carry_save_adder: the numbers have different lengths ({}, {} and {}).",
+                a_bits.len(),
+                b_bits.len(),
+                c_bits.len()
+            )
+            .into());
+        }
+        let mut partial_sum_bits = Vec::with_capacity(a_bits.len());
+        let mut carry_bits = Vec::with_capacity(a_bits.len());
+        for ((a, b), c) in a_bits.iter().zip(b_bits.iter()).zip(c_bits.iter()) {
+            let (sum, carry) = self.create_full_adder(type_id, *a, *b, *c)?;
+            partial_sum_bits.push(sum);
+            carry_bits.push(carry);
+        }
+        Ok((partial_sum_bits, carry_bits))
+    }
+
+    /// Returns the `2n` bits (least significant bit first) of the product of the `n`-bit
+    /// numbers `a_bits` and `b_bits`, in a Boolean field.
+    /// The `n^2` partial products `a_j AND b_i` form `n` shifted rows, which are reduced
+    /// with carry-save adders into a sum and a carry, finally added with `create_binary_adder`.
+    /// All intermediate numbers are `2n` bits wide: bits beyond the product width are dropped.
+    pub fn create_schoolbook_multiplier(
+        &mut self,
+        type_id: TypeId,
+        a_bits: &[WireId],
+        b_bits: &[WireId],
+    ) -> Result<Vec<WireId>> {
+        if a_bits.len() != b_bits.len() {
+            return Err(format!(
+                "create_schoolbook_multiplier: the numbers have different lengths ({} and {}).",
+                a_bits.len(),
+                b_bits.len()
+            )
+            .into());
+        }
+        let n = a_bits.len();
+        if n == 0 {
+            return Ok(vec![]);
+        }
+
+        let zero = self.create_zero(type_id)?;
+        let mut rows = Vec::with_capacity(n);
+        for (i, b) in b_bits.iter().enumerate() {
+            let mut row = vec![zero; 2 * n];
+            for (j, a) in a_bits.iter().enumerate() {
+                row[i + j] = self.create_and(type_id, *a, *b)?;
+            }
+            rows.push(row);
+        }
+
+        let mut sum_bits = rows[0].clone();
+        let mut shifted_carry_bits = vec![zero; 2 * n];
+        for row in rows.iter().skip(1) {
+            let (partial_sum_bits, carry_bits) =
+                self.create_carry_save_adder(type_id, &sum_bits, &shifted_carry_bits, row)?;
+            sum_bits = partial_sum_bits;
+            shifted_carry_bits = vec![zero];
+            shifted_carry_bits.extend_from_slice(&carry_bits[..2 * n - 1]);
+        }
+        let mut product_bits = self.create_binary_adder(type_id, &sum_bits, &shifted_carry_bits)?;
+        product_bits.truncate(2 * n);
+        Ok(product_bits)
+    }
+
+    /// Asserts that `wire` is not zero, by checking that `wire * inverse - 1 = 0`
+    /// where `inverse` is a new private input.
+    /// If the value of `wire` is known (see `wire_value`), `inverse` is computed from it
+    /// (0 if the value is zero, such that the assertion fails).
+    pub fn create_assert_nonzero(&mut self, type_id: TypeId, wire: WireId) -> Result<()> {
+        let minus_one = self.minus_one(type_id)?;
+        let inverse_value = self.wire_value(type_id, wire).map(|value| {
+            let modulo = value_to_biguint(&minus_one) + BigUint::one();
+            let value = value_to_biguint(value) % &modulo;
+            // Fermat's little theorem: value^(p-2) = value^(-1) (and 0^(p-2) = 0)
+            value
+                .modpow(&(&modulo - BigUint::from(2u8)), &modulo)
+                .to_bytes_le()
+        });
+        let inverse = self.create_gate(Private(type_id, inverse_value))?;
+        let product = self.create_gate(Mul(type_id, wire, inverse))?;
+        let product_minus_one = self.create_gate(AddConstant(type_id, product, minus_one))?;
+        self.create_gate(AssertZero(type_id, product_minus_one))?;
+        Ok(())
+    }
+
+    /// Asserts that `wire` is boolean with `wire * (wire - 1) = 0`.
+    /// `minus_one` is the value '-1' in the field `type_id`.
+    fn create_assert_boolean(
+        &mut self,
+        type_id: TypeId,
+        wire: WireId,
+        minus_one: &Value,
+    ) -> Result<()> {
+        let wire_minus_one = self.create_gate(AddConstant(type_id, wire, minus_one.clone()))?;
+        let product = self.create_gate(Mul(type_id, wire, wire_minus_one))?;
+        self.create_gate(AssertZero(type_id, product))?;
+        Ok(())
+    }
+
+    /// Decomposes `wire` into `num_bits` new private wires (least significant bit first).
+    /// Each bit is constrained to be boolean, and their recomposition to be equal to `wire`.
+    /// The modulo of the field must be greater than `2^num_bits`.
+    /// If the value of `wire` is known (see `wire_value`), the bits are computed from it, and an
+    /// error is returned if it does not fit in `num_bits` bits.
+    pub fn create_bit_decomposition(
+        &mut self,
+        type_id: TypeId,
+        wire: WireId,
+        num_bits: usize,
+    ) -> Result<Vec<WireId>> {
+        let minus_one = self.minus_one(type_id)?;
+        let value = self
+            .wire_value(type_id, wire)
+            .map(|value| value_to_biguint(value));
+        if let Some(value) = &value {
+            if value.bits() > num_bits as u64 {
+                return Err(format!(
+                    "create_bit_decomposition: the value does not fit in {} bits.",
+                    num_bits
+                )
+                .into());
+            }
+        }
+
+        let mut bits = vec![];
+        for i in 0..num_bits {
+            let bit_value = value
+                .as_ref()
+                .map(|value| ((value >> i) & BigUint::one()).to_bytes_le());
+            let bit = self.create_gate(Private(type_id, bit_value))?;
+            self.create_assert_boolean(type_id, bit, &minus_one)?;
+            bits.push(bit);
+        }
+
+        let sum = self.create_bit_pack(type_id, &bits)?;
+        let diff = self.create_sub(type_id, sum, wire)?;
+        self.create_gate(AssertZero(type_id, diff))?;
+        Ok(bits)
+    }
+
+    /// Returns a wire holding 1 if `a < b`, and 0 otherwise.
+    /// `a` and `b` must be in `[0, 2^(num_bits-1))`. The result is the most significant bit of
+    /// the `num_bits`-bit decomposition of `b - a - 1 + 2^(num_bits-1)`.
+    pub fn create_less_than(
+        &mut self,
+        type_id: TypeId,
+        a: WireId,
+        b: WireId,
+        num_bits: usize,
+    ) -> Result<WireId> {
+        if num_bits == 0 {
+            return Err("create_less_than: num_bits must be positive.".into());
+        }
+        let modulo = value_to_biguint(&self.minus_one(type_id)?) + BigUint::one();
+        let offset = ((BigUint::one() << (num_bits - 1)) - BigUint::one()) % &modulo;
+
+        let diff = self.create_sub(type_id, b, a)?;
+        let shifted = self.create_gate(AddConstant(type_id, diff, offset.to_bytes_le()))?;
+        let bits = self.create_bit_decomposition(type_id, shifted, num_bits)?;
+        Ok(bits[num_bits - 1])
+    }
+
+    /// Returns `wires` sorted in increasing order, with Batcher's bitonic sorting network.
+    /// The number of wires must be a power of two `n = 2^k`, and the network is made of
+    /// `n*k*(k+1)/4` compare-and-swap steps, each one a `create_less_than` on `num_bits` bits
+    /// selecting a `create_conditional_swap`. All values must be in `[0, 2^(num_bits-1))`.
+    pub fn create_bitonic_sort(
+        &mut self,
+        type_id: TypeId,
+        wires: &[WireId],
+        num_bits: usize,
+    ) -> Result<Vec<WireId>> {
+        let n = wires.len();
+        if n > 1 && !n.is_power_of_two() {
+            return Err(format!(
+                "create_bitonic_sort: the number of wires ({}) is not a power of two.",
+                n
+            )
+            .into());
+        }
+
+        let mut wires = wires.to_vec();
+        let mut block_size = 2;
+        while block_size <= n {
+            let mut distance = block_size / 2;
+            while distance > 0 {
+                for i in 0..n {
+                    let j = i ^ distance;
+                    if j <= i {
+                        continue;
+                    }
+                    // Within increasing blocks, swap when wires[j] < wires[i]
+                    let (low, high) = if i & block_size == 0 { (j, i) } else { (i, j) };
+                    let sel = self.create_less_than(type_id, wires[low], wires[high], num_bits)?;
+                    let (first, second) =
+                        self.create_conditional_swap(type_id, sel, wires[i], wires[j])?;
+                    wires[i] = first;
+                    wires[j] = second;
+                }
+                distance /= 2;
+            }
+            block_size *= 2;
+        }
+        Ok(wires)
+    }
+
+    /// Returns a wire holding 1 if at least `threshold` of the `inputs` are 1, and 0 otherwise.
+    /// The inputs are assumed to be boolean: they are summed with `create_add_many`, and the
+    /// result is `threshold - 1 < sum` computed with `create_less_than`, on just enough bits
+    /// to hold the number of inputs and the threshold.
+    pub fn create_threshold(
+        &mut self,
+        type_id: TypeId,
+        inputs: &[WireId],
+        threshold: usize,
+    ) -> Result<WireId> {
+        if threshold == 0 {
+            return self.create_one(type_id);
+        }
+
+        let sum = self.create_add_many(type_id, inputs)?;
+        let bound_value = BigUint::from(threshold - 1).to_bytes_le();
+        let bound = self.create_gate(Constant(type_id, bound_value))?;
+        let max = inputs.len().max(threshold);
+        let num_bits = (usize::BITS - max.leading_zeros()) as usize + 1;
+        self.create_less_than(type_id, bound, sum, num_bits)
+    }
+
+    /// Lifts the bit `bit_wire` of the Boolean field `bool_type_id` into the Field
+    /// `arith_type_id` (0 is mapped to 0 and 1 to 1) with a Convert gate.
+    /// The conversion is declared if needed (see `create_conversion_chain`).
+    pub fn create_boolean_to_field(
+        &mut self,
+        bool_type_id: TypeId,
+        arith_type_id: TypeId,
+        bit_wire: WireId,
+    ) -> Result<WireId> {
+        self.check_binary_field(bool_type_id, "Boolean to field conversion")?;
+        let out = self.create_conversion_chain(
+            &[(arith_type_id, 1)],
+            bool_type_id,
+            WireRange::new(bit_wire, bit_wire),
+        )?;
+        Ok(out.first_id)
+    }
+
+    /// Converts `wire` of the Field `arith_type_id` into a bit of the Boolean field
+    /// `bool_type_id`. `wire` is first constrained to be 0 or 1.
+    pub fn create_field_to_boolean(
+        &mut self,
+        arith_type_id: TypeId,
+        bool_type_id: TypeId,
+        wire: WireId,
+    ) -> Result<WireId> {
+        self.check_binary_field(bool_type_id, "Field to boolean conversion")?;
+        let minus_one = self.minus_one(arith_type_id)?;
+        let wire_minus_one = self.create_gate(AddConstant(arith_type_id, wire, minus_one))?;
+        let product = self.create_gate(Mul(arith_type_id, wire, wire_minus_one))?;
+        self.create_gate(AssertZero(arith_type_id, product))?;
+
+        let out = self.create_conversion_chain(
+            &[(bool_type_id, 1)],
+            arith_type_id,
+            WireRange::new(wire, wire),
+        )?;
+        Ok(out.first_id)
+    }
+
+    /// Returns `-wire` if the selector bit `sel` is 1 and `wire` if it is 0.
+    /// It computes `wire * (1 - 2*sel)` as `wire + (-2) * (sel * wire)`,
+    /// which requires a single Mul gate.
+    /// `sel` is assumed to be boolean (0 or 1).
+    pub fn create_conditional_negate(
+        &mut self,
+        type_id: TypeId,
+        sel: WireId,
+        wire: WireId,
+    ) -> Result<WireId> {
+        let minus_two = value_to_biguint(self.get_field_modulo(type_id)?) - 2u8;
+        let sel_wire = self.create_gate(Mul(type_id, sel, wire))?;
+        let minus_two_sel_wire =
+            self.create_gate(MulConstant(type_id, sel_wire, minus_two.to_bytes_le()))?;
+        self.create_gate(Add(type_id, wire, minus_two_sel_wire))
+    }
+
+    /// Computes `wire^exponent` with the square-and-multiply method, from the most significant
+    /// bit of `exponent` to the least significant one.
+    /// It requires `floor(log2(exponent)) + popcount(exponent) - 1` Mul gates.
+    /// `wire^0` is the constant 1.
+    pub fn create_pow(&mut self, type_id: TypeId, wire: WireId, exponent: u64) -> Result<WireId> {
+        if exponent == 0 {
+            return self.create_one(type_id);
+        }
+        let mut acc = wire;
+        for bit in (0..63 - exponent.leading_zeros()).rev() {
+            acc = self.create_gate(Mul(type_id, acc, acc))?;
+            if (exponent >> bit) & 1 == 1 {
+                acc = self.create_gate(Mul(type_id, acc, wire))?;
+            }
+        }
+        Ok(acc)
+    }
+
+    /// Asserts that `wire` is equal to the constant `expected`.
+    pub fn create_assert_equal_to_constant(
+        &mut self,
+        type_id: TypeId,
+        wire: WireId,
+        expected: Value,
+    ) -> Result<()> {
+        let constant = self.create_gate(Constant(type_id, expected))?;
+        let diff = self.create_sub(type_id, wire, constant)?;
+        self.create_gate(AssertZero(type_id, diff))?;
+        Ok(())
+    }
+
+    /// Returns the prefix sums of `wires`: output `i` holds `wires[0] + ... + wires[i]`.
+    /// The first output is `wires[0]` itself, so `n - 1` Add gates are created for `n` inputs.
+    pub fn create_accumulate(&mut self, type_id: TypeId, wires: &[WireId]) -> Result<Vec<WireId>> {
+        let mut sums: Vec<WireId> = Vec::with_capacity(wires.len());
+        for wire in wires.iter() {
+            let sum = match sums.last() {
+                None => *wire,
+                Some(prev) => self.create_gate(Add(type_id, *prev, *wire))?,
+            };
+            sums.push(sum);
+        }
+        Ok(sums)
+    }
+
+    /// Rotates `wires` by `k` positions to the left: output `i` is a copy of
+    /// `wires[(i + k) % n]`. One Copy gate is created per wire.
+    /// A wire id does not carry its type, so `wires` are taken to be of the first type
+    /// (type id 0), e.g. the Boolean field of a hash circuit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the builder has no type.
+    pub fn create_rotation(&mut self, wires: &[WireId], k: usize) -> Vec<WireId> {
+        if wires.is_empty() {
+            return vec![];
+        }
+        let k = k % wires.len();
+        wires[k..]
+            .iter()
+            .chain(wires[..k].iter())
+            .map(|wire| {
+                self.create_gate(Copy(0, *wire))
+                    .unwrap_or_else(|err| panic!("create_rotation: {}", err))
+            })
+            .collect()
+    }
+
+    /// Same as `create_rotation`.
+    pub fn rotate_left(&mut self, wires: &[WireId], k: usize) -> Vec<WireId> {
+        self.create_rotation(wires, k)
+    }
+
+    /// Rotates `wires` by `k` positions to the right: output `i` is a copy of
+    /// `wires[(i - k) % n]` (see `create_rotation`).
+    pub fn rotate_right(&mut self, wires: &[WireId], k: usize) -> Vec<WireId> {
+        if wires.is_empty() {
+            return vec![];
+        }
+        let n = wires.len();
+        self.create_rotation(wires, n - k % n)
+    }
+
+    /// Packs `bits` (least significant bit first) into the field element `sum_i bits[i] * 2^i`.
+    /// One MulConstant gate is created per bit except the first one.
+    /// The bits are not constrained to be boolean.
+    pub fn create_bit_pack(&mut self, type_id: TypeId, bits: &[WireId]) -> Result<WireId> {
+        let mut sum: Option<WireId> = None;
+        for (i, bit) in bits.iter().enumerate() {
+            let term = if i == 0 {
+                *bit
+            } else {
+                let power = (BigUint::one() << i).to_bytes_le();
+                self.create_gate(MulConstant(type_id, *bit, power))?
+            };
+            sum = Some(match sum {
+                None => term,
+                Some(sum) => self.create_gate(Add(type_id, sum, term))?,
+            });
+        }
+        match sum {
+            Some(sum) => Ok(sum),
+            None => self.create_zero(type_id),
+        }
+    }
+
+    /// Returns the sum of `wires` (0 if `wires` is empty).
+    pub fn create_add_many(&mut self, type_id: TypeId, wires: &[WireId]) -> Result<WireId> {
+        match self.create_accumulate(type_id, wires)?.last() {
+            Some(sum) => Ok(*sum),
+            None => self.create_zero(type_id),
+        }
+    }
+
+    /// Returns the product of `wires` (1 if `wires` is empty).
+    pub fn create_mul_many(&mut self, type_id: TypeId, wires: &[WireId]) -> Result<WireId> {
+        let mut product: Option<WireId> = None;
+        for wire in wires {
+            product = Some(match product {
+                None => *wire,
+                Some(product) => self.create_gate(Mul(type_id, product, *wire))?,
+            });
+        }
+        match product {
+            Some(product) => Ok(product),
+            None => self.create_one(type_id),
+        }
+    }
+
+    /// Returns the number of bits set to 1 in `bits`.
+    /// Each bit is constrained to be boolean.
+    pub fn create_hamming_weight(&mut self, type_id: TypeId, bits: &[WireId]) -> Result<WireId> {
+        let minus_one = self.minus_one(type_id)?;
+        for bit in bits.iter() {
+            self.create_assert_boolean(type_id, *bit, &minus_one)?;
+        }
+        self.create_add_many(type_id, bits)
+    }
+
+    /// Asserts that `wire` lies in `[0, 2^num_bits)` by decomposing it into `num_bits` bits
+    /// (see `create_bit_decomposition`).
+    pub fn create_range_check(
+        &mut self,
+        type_id: TypeId,
+        wire: WireId,
+        num_bits: usize,
+    ) -> Result<()> {
+        self.create_bit_decomposition(type_id, wire, num_bits)?;
+        Ok(())
+    }
+
+    /// Creates a private input of value `value` (None for the verifier), and asserts that it
+    /// lies in `[0, 2^num_bits)` (see `create_range_check`).
+    pub fn create_range_constrained_input(
+        &mut self,
+        type_id: TypeId,
+        num_bits: usize,
+        value: Option<Value>,
+    ) -> Result<WireId> {
+        let wire = self.create_gate(Private(type_id, value))?;
+        self.create_range_check(type_id, wire, num_bits)?;
+        Ok(wire)
+    }
+
+    /// Asserts that each of `wires` is boolean (0 or 1) with `w^2 - w = 0`.
+    /// This emits 4 gates per wire: Mul for `w^2`, MulConstant and Add for the subtraction
+    /// (see `create_sub`), and AssertZero.
+    pub fn create_assert_bits(&mut self, type_id: TypeId, wires: &[WireId]) -> Result<()> {
+        for wire in wires {
+            let square = self.create_gate(Mul(type_id, *wire, *wire))?;
+            let diff = self.create_sub(type_id, square, *wire)?;
+            self.create_gate(AssertZero(type_id, diff))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the wire holding `prod_i (wires[i] + shift)`.
+    fn create_grand_product(
+        &mut self,
+        type_id: TypeId,
+        wires: &[WireId],
+        shift: WireId,
+    ) -> Result<WireId> {
+        let shifted = wires
+            .iter()
+            .map(|wire| self.create_gate(Add(type_id, *wire, shift)))
+            .collect::<Result<Vec<_>>>()?;
+        self.create_mul_many(type_id, &shifted)
+    }
+
+    /// Asserts that `shuffled` is a permutation of `original` with a grand product argument:
+    /// `prod_i (original[i] - challenge) = prod_i (shuffled[i] - challenge)`,
+    /// where `challenge` is a new public input.
+    /// `permutation_private` is the permutation known by the prover, such that
+    /// `shuffled[i] = original[permutation_private[i]]`. It is not used by the circuit,
+    /// but it is checked to be a permutation of the right length.
+    /// If the values of `original` and `shuffled` are known (see `wire_value`), they are checked
+    /// against `permutation_private`, and the value of `challenge` is derived from them with
+    /// SHA-256 (Fiat-Shamir), such that it is chosen after them.
+    pub fn create_shuffle_proof(
+        &mut self,
+        type_id: TypeId,
+        original: &[WireId],
+        shuffled: &[WireId],
+        permutation_private: &[usize],
+    ) -> Result<()> {
+        if original.len() != shuffled.len() || original.len() != permutation_private.len() {
+            return Err(format!(
+                "create_shuffle_proof: the vectors have different lengths ({}, {} and {}).",
+                original.len(),
+                shuffled.len(),
+                permutation_private.len()
+            )
+            .into());
+        }
+        check_permutation("create_shuffle_proof", permutation_private)?;
+
+        let values = original
+            .iter()
+            .chain(shuffled.iter())
+            .map(|wire| {
+                self.wire_value(type_id, *wire)
+                    .map(|value| value_to_biguint(value))
+            })
+            .collect::<Option<Vec<_>>>();
+        let challenge_value = match values {
+            None => None,
+            Some(values) => {
+                let (original_values, shuffled_values) = values.split_at(original.len());
+                for (i, index) in permutation_private.iter().enumerate() {
+                    if shuffled_values[i] != original_values[*index] {
+                        return Err(format!(
+                            "create_shuffle_proof: shuffled[{}] is not original[{}].",
+                            i, index
+                        )
+                        .into());
+                    }
+                }
+                let mut hasher = Sha256::new();
+                for value in values.iter() {
+                    let bytes = value.to_bytes_le();
+                    hasher.update((bytes.len() as u64).to_le_bytes());
+                    hasher.update(bytes);
+                }
+                let modulo = value_to_biguint(self.get_field_modulo(type_id)?);
+                Some((BigUint::from_bytes_le(&hasher.finalize()) % modulo).to_bytes_le())
+            }
+        };
+        let challenge = self.create_gate(Public(type_id, challenge_value))?;
+
+        let minus_one = self.minus_one(type_id)?;
+        let neg_challenge = self.create_gate(MulConstant(type_id, challenge, minus_one))?;
+        let original_product = self.create_grand_product(type_id, original, neg_challenge)?;
+        let shuffled_product = self.create_grand_product(type_id, shuffled, neg_challenge)?;
+        let diff = self.create_sub(type_id, original_product, shuffled_product)?;
+        self.create_gate(AssertZero(type_id, diff))?;
+        Ok(())
+    }
+
+    /// Returns a wire holding `sum_i left[i] * right[i]`.
+    pub fn create_inner_product(
+        &mut self,
+        type_id: TypeId,
+        left: &[WireId],
+        right: &[WireId],
+    ) -> Result<WireId> {
+        if left.len() != right.len() {
+            return Err(format!(
+                "create_inner_product: the vectors have different lengths ({} and {}).",
+                left.len(),
+                right.len()
+            )
+            .into());
+        }
+        let products = left
+            .iter()
+            .zip(right.iter())
+            .map(|(l, r)| self.create_gate(Mul(type_id, *l, *r)))
+            .collect::<Result<Vec<_>>>()?;
+        self.create_add_many(type_id, &products)
+    }
+
+    /// Returns a wire holding `sum_i coefficients[i] * wires[i]` (see `create_sum_of_products`).
+    pub fn create_linear_combination(
+        &mut self,
+        type_id: TypeId,
+        wires: &[WireId],
+        coefficients: &[Value],
+    ) -> Result<WireId> {
+        let outputs = self.create_sum_of_products(type_id, wires, &[coefficients.to_vec()])?;
+        Ok(outputs[0])
+    }
+
+    /// Asserts that `<a_vec, b_vec> = expected_product` by folding the vectors in half
+    /// at each round, as in the inner product argument of Bulletproofs.
+    /// With the challenge `x`, a round computes the cross terms `L = <a_lo, b_hi>` and
+    /// `R = <a_hi, b_lo>`, and replaces
+    /// - `a` with `x * a_lo + x^-1 * a_hi`,
+    /// - `b` with `x^-1 * b_lo + x * b_hi`,
+    /// - `expected_product` with `expected_product + x^2 * L + x^-2 * R`,
+    /// which preserves the relation. When the length is odd, the last term is subtracted from
+    /// `expected_product` instead. The final length-1 vectors are checked with a Mul gate.
+    /// Since the cross terms are computed in the circuit, the challenge is the constant `x = 2`.
+    pub fn create_inner_product_argument(
+        &mut self,
+        type_id: TypeId,
+        a_vec: &[WireId],
+        b_vec: &[WireId],
+        expected_product: WireId,
+    ) -> Result<()> {
+        if a_vec.len() != b_vec.len() {
+            return Err(format!(
+                "create_inner_product_argument: the vectors have different lengths ({} and {}).",
+                a_vec.len(),
+                b_vec.len()
+            )
+            .into());
+        }
+        let minus_one = self.minus_one(type_id)?;
+        let modulo = (value_to_biguint(&minus_one) + BigUint::one()).to_bytes_le();
+        let x = vec![2];
+        let x_inv = value_inv(&x, &modulo)
+            .map_err(|_| "create_inner_product_argument: the field is too small.")?;
+        let x_square = value_mul(&x, &x, &modulo);
+        let x_inv_square = value_mul(&x_inv, &x_inv, &modulo);
+
+        let mut a_vec = a_vec.to_vec();
+        let mut b_vec = b_vec.to_vec();
+        let mut expected = expected_product;
+        while a_vec.len() > 1 {
+            if a_vec.len() % 2 == 1 {
+                let (a_last, b_last) = (a_vec.pop().unwrap(), b_vec.pop().unwrap());
+                let last = self.create_gate(Mul(type_id, a_last, b_last))?;
+                expected = self.create_sub(type_id, expected, last)?;
+            }
+            let half = a_vec.len() / 2;
+            let (a_lo, a_hi) = a_vec.split_at(half);
+            let (b_lo, b_hi) = b_vec.split_at(half);
+            let l = self.create_inner_product(type_id, a_lo, b_hi)?;
+            let r = self.create_inner_product(type_id, a_hi, b_lo)?;
+
+            let mut next_a = vec![];
+            let mut next_b = vec![];
+            for i in 0..half {
+                next_a.push(self.create_linear_combination(
+                    type_id,
+                    &[a_lo[i], a_hi[i]],
+                    &[x.clone(), x_inv.clone()],
+                )?);
+                next_b.push(self.create_linear_combination(
+                    type_id,
+                    &[b_lo[i], b_hi[i]],
+                    &[x_inv.clone(), x.clone()],
+                )?);
+            }
+            expected = self.create_linear_combination(
+                type_id,
+                &[expected, l, r],
+                &[vec![1], x_square.clone(), x_inv_square.clone()],
+            )?;
+            a_vec = next_a;
+            b_vec = next_b;
+        }
+
+        let product = self.create_inner_product(type_id, &a_vec, &b_vec)?;
+        let diff = self.create_sub(type_id, product, expected)?;
+        self.create_gate(AssertZero(type_id, diff))?;
+        Ok(())
+    }
+
+    /// Returns the wires `wires[permutation[i]]` (in the order of `i`), each copied with a
+    /// Copy gate.
+    /// It returns an error if `permutation` is not a permutation of `0..wires.len()`.
+    pub fn create_shuffle(
+        &mut self,
+        type_id: TypeId,
+        wires: &[WireId],
+        permutation: &[usize],
+    ) -> Result<Vec<WireId>> {
+        if permutation.len() != wires.len() {
+            return Err(format!(
+                "create_shuffle: the permutation has {} elements instead of {}.",
+                permutation.len(),
+                wires.len()
+            )
+            .into());
+        }
+        check_permutation("create_shuffle", permutation)?;
+        permutation
+            .iter()
+            .map(|index| self.create_gate(Copy(type_id, wires[*index])))
+            .collect()
+    }
+
+    /// Computes the number theoretic transform of `input_wires`:
+    /// the output `k` is `sum_j input_wires[j] * nth_root_of_unity^(j*k)`.
+    /// The length `n` of `input_wires` must be a power of two, and `nth_root_of_unity` a
+    /// primitive `n`-th root of unity in the field `type_id`.
+    /// The circuit is made of Cooley-Tukey butterflies: each one multiplies its odd input by
+    /// a twiddle factor and its opposite (two MulConstant gates) and adds them to its even input
+    /// (two Add gates), for a total of `n * log2(n)` MulConstant gates.
+    pub fn create_ntt(
+        &mut self,
+        type_id: TypeId,
+        input_wires: &[WireId],
+        nth_root_of_unity: Value,
+    ) -> Result<Vec<WireId>> {
+        let n = input_wires.len();
+        if !n.is_power_of_two() {
+            return Err(format!(
+                "create_ntt: the number of inputs ({}) is not a power of two.",
+                n
+            )
+            .into());
+        }
+        let minus_one = self.minus_one(type_id)?;
+        let modulo = (value_to_biguint(&minus_one) + BigUint::one()).to_bytes_le();
+        let one = value_to_biguint(&[1]);
+        let is_one = |exp: usize| {
+            value_to_biguint(&value_pow(&nth_root_of_unity, exp as u64, &modulo)) == one
+        };
+        if !is_one(n) || (n > 1 && is_one(n / 2)) {
+            return Err(format!(
+                "create_ntt: {} is not a primitive {}-th root of unity.",
+                value_to_biguint(&nth_root_of_unity),
+                n
+            )
+            .into());
+        }
+        self.create_ntt_butterflies(type_id, input_wires, &nth_root_of_unity, &modulo)
+    }
+
+    /// Recursive step of `create_ntt`: transforms the even and odd inputs with the root
+    /// `root^2`, then combines them with butterflies.
+    fn create_ntt_butterflies(
+        &mut self,
+        type_id: TypeId,
+        wires: &[WireId],
+        root: &Value,
+        modulo: &Value,
+    ) -> Result<Vec<WireId>> {
+        let half = wires.len() / 2;
+        if half == 0 {
+            return Ok(wires.to_vec());
+        }
+        let square = value_mul(root, root, modulo);
+        let even_wires = wires.iter().step_by(2).cloned().collect::<Vec<_>>();
+        let odd_wires = wires.iter().skip(1).step_by(2).cloned().collect::<Vec<_>>();
+        let even = self.create_ntt_butterflies(type_id, &even_wires, &square, modulo)?;
+        let odd = self.create_ntt_butterflies(type_id, &odd_wires, &square, modulo)?;
+
+        let mut low = vec![];
+        let mut high = vec![];
+        let mut twiddle = vec![1];
+        for k in 0..half {
+            let t = self.create_gate(MulConstant(type_id, odd[k], twiddle.clone()))?;
+            let minus_t =
+                self.create_gate(MulConstant(type_id, odd[k], value_neg(&twiddle, modulo)))?;
+            low.push(self.create_gate(Add(type_id, even[k], t))?);
+            high.push(self.create_gate(Add(type_id, even[k], minus_t))?);
+            twiddle = value_mul(&twiddle, root, modulo);
+        }
+        low.extend(high);
+        Ok(low)
+    }
+
+    /// Returns a wire of the Field `target_type` holding the value of `wire` (of the Field
+    /// `source_type`) reduced modulo the target modulo.
+    /// `wire` is decomposed into `source_bits` private bits (see `create_bit_decomposition`),
+    /// each bit is converted to `target_type` with a Convert gate, and the bits are
+    /// recomposed with the coefficients `2^i mod target_modulo`.
+    /// `2^source_bits` must be smaller than the source modulo, so that the decomposition is
+    /// unique, and the target modulo must fit in `target_bits` bits.
+    pub fn create_modular_reduction(
+        &mut self,
+        source_type: TypeId,
+        target_type: TypeId,
+        wire: WireId,
+        source_bits: usize,
+        target_bits: usize,
+    ) -> Result<WireId> {
+        let source_modulo = value_to_biguint(&self.minus_one(source_type)?) + BigUint::one();
+        if (BigUint::one() << source_bits) >= source_modulo {
+            return Err(format!(
+                "create_modular_reduction: 2^{} is not smaller than the source modulo {}.",
+                source_bits, source_modulo
+            )
+            .into());
+        }
+        let target_modulo = value_to_biguint(&self.minus_one(target_type)?) + BigUint::one();
+        if target_modulo.bits() > target_bits as u64 {
+            return Err(format!(
+                "create_modular_reduction: the target modulo {} does not fit in {} bits.",
+                target_modulo, target_bits
+            )
+            .into());
+        }
+
+        let bits = self.create_bit_decomposition(source_type, wire, source_bits)?;
+        let mut converted_bits = vec![];
+        let mut coefficients = vec![];
+        for (i, bit) in bits.iter().enumerate() {
+            let converted = self.create_conversion_chain(
+                &[(target_type, 1)],
+                source_type,
+                WireRange::new(*bit, *bit),
+            )?;
+            converted_bits.push(converted.first_id);
+            coefficients.push(((BigUint::one() << i) % &target_modulo).to_bytes_le());
+        }
+        self.create_linear_combination(target_type, &converted_bits, &coefficients)
+    }
+
+    /// Returns the wires `[F_0, F_1, ..., F_{n-1}]` of the Fibonacci sequence starting
+    /// from the constants 0 and 1, using `n - 2` Add gates (plus the Constant gates).
+    pub fn create_fibonacci_sequence(&mut self, type_id: TypeId, n: usize) -> Result<Vec<WireId>> {
+        if n == 0 {
+            return Ok(vec![]);
+        }
+        let first = self.create_gate(Constant(type_id, vec![0]))?;
+        if n == 1 {
+            return Ok(vec![first]);
+        }
+        let second = self.create_gate(Constant(type_id, vec![1]))?;
+        self.create_fibonacci_from_inputs(type_id, first, second, n)
+    }
+
+    /// Returns the wires of the first `n` terms of the Fibonacci-like sequence starting
+    /// from the wires `first` and `second` (e.g. private inputs), using `n - 2` Add gates.
+    pub fn create_fibonacci_from_inputs(
+        &mut self,
+        type_id: TypeId,
+        first: WireId,
+        second: WireId,
+        n: usize,
+    ) -> Result<Vec<WireId>> {
+        let mut sequence = vec![first, second];
+        sequence.truncate(n);
+        while sequence.len() < n {
+            let len = sequence.len();
+            let next = self.create_gate(Add(type_id, sequence[len - 2], sequence[len - 1]))?;
+            sequence.push(next);
+        }
+        Ok(sequence)
+    }
+
+    /// Same as `create_fibonacci_sequence`, but each term is computed by calling the function
+    /// `add_step` (a single Add gate) instead of inlining the Add gate, e.g. to compare
+    /// the cost of Call gates with inlined gates.
+    /// `add_step` is defined on the first use, and must otherwise have the signature
+    /// `(type_id: 1, type_id: 1) -> type_id: 1`.
+    pub fn create_fibonacci_via_function(
+        &mut self,
+        type_id: TypeId,
+        n: usize,
+    ) -> Result<Vec<WireId>> {
+        if n == 0 {
+            return Ok(vec![]);
+        }
+        let first = self.create_gate(Constant(type_id, vec![0]))?;
+        if n == 1 {
+            return Ok(vec![first]);
+        }
+        let second = self.create_gate(Constant(type_id, vec![1]))?;
+
+        let name = "add_step";
+        let single = Count::new(type_id, 1);
+        match self.known_functions().get(name) {
+            Some(counts) => {
+                if counts.input_count != [single.clone(), single.clone()]
+                    || counts.output_count != [single]
+                {
+                    return Err(format!(
+                        "create_fibonacci_via_function: {} does not add two wires of type {}.",
+                        name, type_id
+                    )
+                    .into());
+                }
+            }
+            None => {
+                let mut fb = self.new_function_builder(
+                    name.to_string(),
+                    vec![single.clone()],
+                    vec![single.clone(), single],
+                );
+                let inputs = fb.input_wires();
+                let output = fb.create_gate(Add(type_id, inputs[0].1, inputs[1].1));
+                let function = fb.finish(vec![WireRange::new(output, output)])?;
+                self.push_function(function)?;
+            }
+        }
+
+        let mut sequence = vec![first, second];
+        while sequence.len() < n {
+            let len = sequence.len();
+            let (left, right) = (sequence[len - 2], sequence[len - 1]);
+            let outputs = self.create_complex_gate(
+                BuildComplexGate::Call(
+                    name.to_string(),
+                    vec![WireRange::new(left, left), WireRange::new(right, right)],
+                ),
+                vec![],
+                vec![],
+            )?;
+            sequence.push(outputs[0].first_id);
+        }
+        Ok(sequence)
+    }
+
+    /// Reconstructs, in the Field `output_type`, the number `x < m_1 * ... * m_n` such that
+    /// `x = residue_wires[i] mod moduli[i]`, where each residue wire belongs to the Field type
+    /// whose modulo is `moduli[i]`.
+    /// It follows Garner's algorithm: the mixed-radix digits
+    /// `v_i = (r_i - (v_1 + v_2 m_1 + ... + v_{i-1} m_1...m_{i-2})) / (m_1...m_{i-1}) mod m_i`
+    /// are computed in the Field `m_i` from the previous digits converted with Convert gates,
+    /// and `x = v_1 + v_2 m_1 + ... + v_n m_1...m_{n-1}` is computed in the output Field.
+    /// The moduli must be in increasing order, such that converting a digit to a larger Field
+    /// never overflows, and their product must not exceed the output modulo.
+    pub fn create_crt_reconstruct(
+        &mut self,
+        moduli: &[Value],
+        residue_wires: &[WireId],
+        output_type: TypeId,
+    ) -> Result<WireId> {
+        if moduli.is_empty() || moduli.len() != residue_wires.len() {
+            return Err(format!(
+                "create_crt_reconstruct: {} moduli and {} residue wires were given.",
+                moduli.len(),
+                residue_wires.len()
+            )
+            .into());
+        }
+        let mut type_ids = vec![];
+        for (i, modulo) in moduli.iter().enumerate() {
+            let type_id = self.field_type_id(modulo).ok_or_else(|| {
+                format!(
+                    "create_crt_reconstruct: no Field type has the modulo {}.",
+                    value_to_biguint(modulo)
+                )
+            })?;
+            if i > 0 && value_to_biguint(modulo) <= value_to_biguint(&moduli[i - 1]) {
+                return Err(
+                    "create_crt_reconstruct: the moduli are not in increasing order.".into(),
+                );
+            }
+            type_ids.push(type_id);
+        }
+        let product = moduli.iter().fold(BigUint::one(), |product, modulo| {
+            product * value_to_biguint(modulo)
+        });
+        let output_modulo = value_to_biguint(&self.minus_one(output_type)?) + BigUint::one();
+        if product > output_modulo {
+            return Err(format!(
+                "create_crt_reconstruct: the product of the moduli {} exceeds the output modulo {}.",
+                product, output_modulo
+            )
+            .into());
+        }
+
+        // digits[i] = v_i, as a wire of type type_ids[i]
+        let mut digits = vec![residue_wires[0]];
+        for i in 1..moduli.len() {
+            let modulo = &moduli[i];
+            // prefix_products[j] = m_1...m_{j-1} mod m_i
+            let mut prefix_products = vec![vec![1]];
+            for previous in moduli[..i].iter() {
+                let next = value_mul(prefix_products.last().unwrap(), previous, modulo);
+                prefix_products.push(next);
+            }
+            let inverse = value_inv(&prefix_products[i], modulo)?;
+
+            let mut wires = vec![residue_wires[i]];
+            let mut coefficients = vec![inverse.clone()];
+            for j in 0..i {
+                let converted = self.create_conversion_chain(
+                    &[(type_ids[i], 1)],
+                    type_ids[j],
+                    WireRange::new(digits[j], digits[j]),
+                )?;
+                wires.push(converted.first_id);
+                coefficients.push(value_neg(
+                    &value_mul(&prefix_products[j], &inverse, modulo),
+                    modulo,
+                ));
+            }
+            digits.push(self.create_linear_combination(type_ids[i], &wires, &coefficients)?);
+        }
+
+        let mut wires = vec![];
+        let mut coefficients = vec![];
+        let mut prefix_product = BigUint::one();
+        for (i, digit) in digits.iter().enumerate() {
+            let converted = self.create_conversion_chain(
+                &[(output_type, 1)],
+                type_ids[i],
+                WireRange::new(*digit, *digit),
+            )?;
+            wires.push(converted.first_id);
+            coefficients.push(prefix_product.to_bytes_le());
+            prefix_product *= value_to_biguint(&moduli[i]);
+        }
+        self.create_linear_combination(output_type, &wires, &coefficients)
+    }
+
+    /// Returns the element of `array` at position `index`.
+    /// `index` is decomposed into `index_bits` private bits (see `create_bit_decomposition`),
+    /// and the element is selected by a binary tree of `create_mux`: the bit `i` selects between
+    /// the pairs of candidates remaining at level `i` (a candidate without a pair is kept as is),
+    /// for a total of `array.len() - 1` muxes.
+    /// `array.len()` must not exceed `2^index_bits`; the result is unspecified when `index` is
+    /// not smaller than `array.len()`.
+    pub fn create_select_from_array(
+        &mut self,
+        type_id: TypeId,
+        array: &[WireId],
+        index: WireId,
+        index_bits: usize,
+    ) -> Result<WireId> {
+        if array.is_empty() {
+            return Err("create_select_from_array: the array is empty.".into());
+        }
+        if index_bits < usize::BITS as usize && array.len() > (1 << index_bits) {
+            return Err(format!(
+                "create_select_from_array: {} elements cannot be indexed with {} bits.",
+                array.len(),
+                index_bits
+            )
+            .into());
+        }
+        let bits = self.create_bit_decomposition(type_id, index, index_bits)?;
+
+        let mut candidates = array.to_vec();
+        for bit in bits.iter() {
+            if candidates.len() == 1 {
+                break;
+            }
+            let mut next = vec![];
+            for pair in candidates.chunks(2) {
+                match pair {
+                    [if_zero, if_one] => {
+                        next.push(self.create_mux(type_id, *bit, *if_zero, *if_one)?)
+                    }
+                    _ => next.push(pair[0]),
+                }
+            }
+            candidates = next;
+        }
+        Ok(candidates[0])
+    }
+
+    /// Asserts that `a` and `b` hold the same multiset of values with a grand product argument:
+    /// `prod_i (a[i] + gamma) = prod_i (b[i] + gamma)`.
+    /// `gamma` should be a public wire whose value is chosen randomly after the values of
+    /// `a` and `b`, otherwise a cheating prover may find values satisfying the equality.
+    pub fn create_permutation_check(
+        &mut self,
+        type_id: TypeId,
+        a: &[WireId],
+        b: &[WireId],
+        gamma: WireId,
+    ) -> Result<()> {
+        if a.len() != b.len() {
+            return Err(format!(
+                "create_permutation_check: the vectors have different lengths ({} and {}).",
+                a.len(),
+                b.len()
+            )
+            .into());
+        }
+        let a_product = self.create_grand_product(type_id, a, gamma)?;
+        let b_product = self.create_grand_product(type_id, b, gamma)?;
+        let diff = self.create_sub(type_id, a_product, b_product)?;
+        self.create_gate(AssertZero(type_id, diff))?;
+        Ok(())
+    }
+
+    /// Asserts that `wires` hold a permutation of the constant values `public_set`, with the
+    /// grand product argument of `create_permutation_check`:
+    /// `prod_i (wires[i] + gamma) = prod_i (public_set[i] + gamma)`.
+    /// Since `gamma` is a wire, the factors `public_set[i] + gamma` are AddConstant gates
+    /// rather than constants. `gamma` should be chosen as in `create_permutation_check`.
+    pub fn create_assert_permutation_of_set(
+        &mut self,
+        type_id: TypeId,
+        wires: &[WireId],
+        public_set: &[Value],
+        gamma: WireId,
+    ) -> Result<()> {
+        if wires.len() != public_set.len() {
+            return Err(format!(
+                "create_assert_permutation_of_set: {} wires and {} set values were given.",
+                wires.len(),
+                public_set.len()
+            )
+            .into());
+        }
+        let wires_product = self.create_grand_product(type_id, wires, gamma)?;
+        let set_factors = public_set
+            .iter()
+            .map(|value| self.create_gate(AddConstant(type_id, gamma, value.clone())))
+            .collect::<Result<Vec<_>>>()?;
+        let set_product = self.create_mul_many(type_id, &set_factors)?;
+        let diff = self.create_sub(type_id, wires_product, set_product)?;
+        self.create_gate(AssertZero(type_id, diff))?;
+        Ok(())
+    }
+
+    /// Returns the root of a Merkle tree computed from `leaf` and its authentication `path`,
+    /// a list of `(sibling, direction_bit)` from the leaf level up to the root.
+    /// At each level, the current node is hashed with its sibling by calling the function
+    /// `hash_function`, which must be a known function of signature
+    /// `(type_id: 1, type_id: 1) -> type_id: 1`. The current node is the left input if
+    /// `direction_bit` is 0 and the right input if it is 1 (see `create_conditional_swap`).
+    /// Each direction bit is constrained to be boolean.
+    pub fn create_merkle_path_verification(
+        &mut self,
+        type_id: TypeId,
+        leaf: WireId,
+        path: &[(WireId, WireId)],
+        hash_function: &str,
+    ) -> Result<WireId> {
+        let counts = self.known_functions().get(hash_function).ok_or_else(|| {
+            format!(
+                "create_merkle_path_verification: the function {} is not defined.",
+                hash_function
+            )
+        })?;
+        let single = Count::new(type_id, 1);
+        if counts.input_count != [single.clone(), single.clone()] || counts.output_count != [single]
+        {
+            return Err(format!(
+                "create_merkle_path_verification: {} does not hash two wires of type {} into one.",
+                hash_function, type_id
+            )
+            .into());
+        }
+
+        let minus_one = self.minus_one(type_id)?;
+        let mut node = leaf;
+        for (sibling, direction_bit) in path {
+            self.create_assert_boolean(type_id, *direction_bit, &minus_one)?;
+            let (left, right) =
+                self.create_conditional_swap(type_id, *direction_bit, node, *sibling)?;
+            let outputs = self.create_complex_gate(
+                BuildComplexGate::Call(
+                    hash_function.to_string(),
+                    vec![WireRange::new(left, left), WireRange::new(right, right)],
+                ),
+                vec![],
+                vec![],
+            )?;
+            node = outputs[0].first_id;
+        }
+        Ok(node)
+    }
+
+    /// Returns the sum of the points `(p1_x, p1_y)` and `(p2_x, p2_y)` of a short Weierstrass
+    /// curve `y^2 = x^3 + b` (i.e. with `a = 0`, as secp256k1 or BN254), in affine coordinates.
+    /// The slope is the new private input `lambda`, constrained by the unified formula
+    /// `lambda * (y1 + y2) = x1^2 + x1*x2 + x2^2`, which holds both for the addition
+    /// of distinct points and for doubling. The sum is then
+    /// `x3 = lambda^2 - x1 - x2` and `y3 = lambda * (x1 - x3) - y1`.
+    /// The inputs must not be the point at infinity, and `y1 + y2` must not be zero: in particular,
+    /// adding a point to its opposite makes the assertion fail.
+    /// If the values of the inputs are known (see `wire_value`), `lambda` is computed from them,
+    /// and an error is returned if `y1 + y2` is zero.
+    pub fn create_ec_add(
+        &mut self,
+        type_id: TypeId,
+        p1_x: WireId,
+        p1_y: WireId,
+        p2_x: WireId,
+        p2_y: WireId,
+    ) -> Result<(WireId, WireId)> {
+        let modulo = self.get_field_modulo(type_id)?.clone();
+        let point_values = [p1_x, p1_y, p2_x, p2_y]
+            .iter()
+            .map(|wire| self.wire_value(type_id, *wire).cloned())
+            .collect::<Option<Vec<_>>>();
+        let lambda_value = match point_values {
+            None => None,
+            Some(values) => {
+                let [x1, y1, x2, y2] = [&values[0], &values[1], &values[2], &values[3]];
+                let x_sum = value_add(x1, x2, &modulo);
+                let numerator = value_add(
+                    &value_mul(&x_sum, &x_sum, &modulo),
+                    &value_neg(&value_mul(x1, x2, &modulo), &modulo),
+                    &modulo,
+                );
+                let inverse = value_inv(&value_add(y1, y2, &modulo), &modulo)
+                    .map_err(|err| format!("create_ec_add: y1 + y2 is not invertible ({})", err))?;
+                Some(value_mul(&numerator, &inverse, &modulo))
+            }
+        };
+
+        // x1^2 + x1*x2 + x2^2 = (x1 + x2)^2 - x1*x2
+        let x_sum = self.create_gate(Add(type_id, p1_x, p2_x))?;
+        let x_sum_square = self.create_gate(Mul(type_id, x_sum, x_sum))?;
+        let x_product = self.create_gate(Mul(type_id, p1_x, p2_x))?;
+        let numerator = self.create_sub(type_id, x_sum_square, x_product)?;
+        let y_sum = self.create_gate(Add(type_id, p1_y, p2_y))?;
+        let lambda = self.create_gate(Private(type_id, lambda_value))?;
+        let lambda_y_sum = self.create_gate(Mul(type_id, lambda, y_sum))?;
+        let diff = self.create_sub(type_id, lambda_y_sum, numerator)?;
+        self.create_gate(AssertZero(type_id, diff))?;
+
+        let lambda_square = self.create_gate(Mul(type_id, lambda, lambda))?;
+        let x3 = self.create_sub(type_id, lambda_square, x_sum)?;
+        let x1_minus_x3 = self.create_sub(type_id, p1_x, x3)?;
+        let lambda_x1_minus_x3 = self.create_gate(Mul(type_id, lambda, x1_minus_x3))?;
+        let y3 = self.create_sub(type_id, lambda_x1_minus_x3, p1_y)?;
+        Ok((x3, y3))
+    }
+
+    /// Evaluates the polynomial `sum_i coefficients[i] * x^i` with Horner's rule,
+    /// using one Mul and one Add gate per coefficient except the last one.
+    pub fn create_horner_eval(
+        &mut self,
+        type_id: TypeId,
+        coefficients: &[WireId],
+        x: WireId,
+    ) -> Result<WireId> {
+        let mut coefficients = coefficients.iter().rev();
+        let mut result = match coefficients.next() {
+            Some(leading) => *leading,
+            None => return self.create_zero(type_id),
+        };
+        for coefficient in coefficients {
+            let product = self.create_gate(Mul(type_id, result, x))?;
+            result = self.create_gate(Add(type_id, product, *coefficient))?;
+        }
+        Ok(result)
+    }
+
+    /// Hashes `inputs` into an element of the Field `type_id` by calling the function
+    /// `hash_function_name` (gate-based or plugin), which must take `inputs.len()` contiguous wires
+    /// of type `type_id` and return `output_bits` wires of type `type_id` holding bits
+    /// (least significant bit first). The inputs are first copied into contiguous wires.
+    /// The output bits are constrained to be boolean and packed into `sum_i bit_i * 2^i`,
+    /// reduced modulo the field modulo when `2^output_bits` exceeds it.
+    pub fn create_hash_to_field(
+        &mut self,
+        type_id: TypeId,
+        inputs: &[WireId],
+        output_bits: usize,
+        hash_function_name: &str,
+    ) -> Result<WireId> {
+        if inputs.is_empty() {
+            return Err("create_hash_to_field: there is no input to hash.".into());
+        }
+        let counts = self
+            .known_functions()
+            .get(hash_function_name)
+            .ok_or_else(|| {
+                format!(
+                    "create_hash_to_field: the function {} is not defined.",
+                    hash_function_name
+                )
+            })?;
+        if counts.input_count != [Count::new(type_id, inputs.len() as u64)]
+            || counts.output_count != [Count::new(type_id, output_bits as u64)]
+        {
+            return Err(format!(
+                "create_hash_to_field: {} does not map {} wires of type {} to {} bits.",
+                hash_function_name,
+                inputs.len(),
+                type_id,
+                output_bits
+            )
+            .into());
+        }
+
+        // Wires of the same type are allocated consecutively, so the copies are contiguous
+        let copies = inputs
+            .iter()
+            .map(|input| self.create_gate(Copy(type_id, *input)))
+            .collect::<Result<Vec<_>>>()?;
+        let in_ids = WireRange::new(copies[0], copies[copies.len() - 1]);
+        let outputs = self.create_complex_gate(
+            BuildComplexGate::Call(hash_function_name.to_string(), vec![in_ids]),
+            vec![],
+            vec![],
+        )?;
+        let bits = outputs
+            .iter()
+            .flat_map(|range| range.first_id..=range.last_id)
+            .collect::<Vec<_>>();
+        self.create_assert_bits(type_id, &bits)?;
+
+        let modulo = value_to_biguint(&self.minus_one(type_id)?) + BigUint::one();
+        let powers = (0..bits.len())
+            .map(|i| ((BigUint::one() << i) % &modulo).to_bytes_le())
+            .collect::<Vec<_>>();
+        self.create_linear_combination(type_id, &bits, &powers)
+    }
+
+    /// Returns a wire holding the dot product of the wire ranges `a` and `b`
+    /// (see `create_inner_product`).
+    /// Returns an error if a range is not of type `type_id`, is empty, or if the ranges have
+    /// different lengths.
+    pub fn create_vector_dot_product(
+        &mut self,
+        type_id: TypeId,
+        a: &WireRangeWithType,
+        b: &WireRangeWithType,
+    ) -> Result<WireId> {
+        for range in [a, b] {
+            if range.type_id != type_id {
+                return Err(format!(
+                    "create_vector_dot_product: the range {}..={} has type {} instead of {}.",
+                    range.first_id, range.last_id, range.type_id, type_id
+                )
+                .into());
+            }
+            if range.last_id < range.first_id {
+                return Err(format!(
+                    "create_vector_dot_product: the range {}..={} is empty.",
+                    range.first_id, range.last_id
+                )
+                .into());
+            }
+        }
+        let a = (a.first_id..=a.last_id).collect::<Vec<_>>();
+        let b = (b.first_id..=b.last_id).collect::<Vec<_>>();
+        if a.len() != b.len() {
+            return Err(format!(
+                "create_vector_dot_product: the ranges have different lengths ({} and {}).",
+                a.len(),
+                b.len()
+            )
+            .into());
+        }
+        self.create_inner_product(type_id, &a, &b)
+    }
+
+    /// Returns `a` if `sel0` is 1, `b` if `sel1` is 1, and `c` if both are 0, computed as
+    /// `sel0*a + sel1*b + (1 - sel0 - sel1)*c = c + sel0*(a - c) + sel1*(b - c)`.
+    /// The selectors are constrained to be boolean, and exclusive with a 1-bit range check
+    /// of `sel0 + sel1` (see `create_range_check`), i.e. `sel0 + sel1 <= 1`.
+    pub fn create_ternary_op(
+        &mut self,
+        type_id: TypeId,
+        sel0: WireId,
+        sel1: WireId,
+        a: WireId,
+        b: WireId,
+        c: WireId,
+    ) -> Result<WireId> {
+        self.create_assert_bits(type_id, &[sel0, sel1])?;
+        let sel_sum = self.create_gate(Add(type_id, sel0, sel1))?;
+        self.create_range_check(type_id, sel_sum, 1)?;
+
+        let a_minus_c = self.create_sub(type_id, a, c)?;
+        let sel0_term = self.create_gate(Mul(type_id, sel0, a_minus_c))?;
+        let b_minus_c = self.create_sub(type_id, b, c)?;
+        let sel1_term = self.create_gate(Mul(type_id, sel1, b_minus_c))?;
+        let sum = self.create_gate(Add(type_id, c, sel0_term))?;
+        self.create_gate(Add(type_id, sum, sel1_term))
+    }
+
+    /// Reads the element of `memory` at position `index` without revealing `index`
+    /// (see `create_select_from_array`, whose constraints are the same).
+    pub fn create_oblivious_read(
+        &mut self,
+        type_id: TypeId,
+        memory: &[WireId],
+        index: WireId,
+        index_bits: usize,
+    ) -> Result<WireId> {
+        self.create_select_from_array(type_id, memory, index, index_bits)
+    }
+
+    /// Returns a copy of `memory` where the element at position `index` is replaced by `value`,
+    /// without revealing `index`.
+    /// `index` is decomposed into `index_bits` private bits, which are expanded into the
+    /// indicator bits `index == j` of all positions (one Mul gate per indicator and per level),
+    /// and each position is updated with `create_mux`. Nothing is written if `index` is not
+    /// smaller than `memory.len()`.
+    pub fn create_oblivious_write(
+        &mut self,
+        type_id: TypeId,
+        memory: &[WireId],
+        index: WireId,
+        value: WireId,
+        index_bits: usize,
+    ) -> Result<Vec<WireId>> {
+        if index_bits < usize::BITS as usize && memory.len() > (1 << index_bits) {
+            return Err(format!(
+                "create_oblivious_write: {} elements cannot be indexed with {} bits.",
+                memory.len(),
+                index_bits
+            )
+            .into());
+        }
+        let bits = self.create_bit_decomposition(type_id, index, index_bits)?;
+
+        // Expanding the bits from the most significant one, indicators[j] = (index_prefix == j)
+        let mut indicators = vec![self.create_one(type_id)?];
+        for bit in bits.iter().rev() {
+            let mut next = vec![];
+            for indicator in indicators.iter() {
+                let if_one = self.create_gate(Mul(type_id, *indicator, *bit))?;
+                let if_zero = self.create_sub(type_id, *indicator, if_one)?;
+                next.push(if_zero);
+                next.push(if_one);
+            }
+            indicators = next;
+        }
+
+        memory
+            .iter()
+            .zip(indicators.iter())
+            .map(|(element, indicator)| self.create_mux(type_id, *indicator, *element, value))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+fn new_test_builder() -> GateBuilder<crate::producers::sink::MemorySink> {
+    use crate::producers::sink::MemorySink;
+    use crate::structs::types::Type;
+
+    GateBuilder::new(
+        MemorySink::default(),
+        &[],
+        &[Type::new_field_type(vec![101])],
+        &[],
+    )
+}
+
+/// Creates one private input per value in `values` (of type 0), and returns their wires.
+#[cfg(test)]
+fn create_private_inputs<S: Sink>(b: &mut GateBuilder<S>, values: &[u8]) -> Result<Vec<WireId>> {
+    if values.is_empty() {
+        return Ok(vec![]);
+    }
+    let wires =
+        b.create_private_input_array(0, values.iter().map(|value| vec![*value]).collect())?;
+    Ok((wires.first_id..=wires.last_id).collect())
+}
+
+#[cfg(test)]
+fn evaluate_wires(
+    b: GateBuilder<crate::producers::sink::MemorySink>,
+    wires: &[WireId],
+) -> Result<Vec<BigUint>> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::Source;
+
+    let source: Source = b.finish().into();
+    let mut backend = PlaintextBackend::default();
+    let mut evaluator = Evaluator::default();
+    for message in source.iter_messages() {
+        evaluator.ingest_message(&message?, &mut backend);
+    }
+    let values = wires
+        .iter()
+        .map(|wire| evaluator.get(0, *wire).map(|value| value.clone()))
+        .collect::<Result<Vec<_>>>()?;
+    assert_eq!(evaluator.get_violations(), Vec::<String>::new());
+    Ok(values)
+}
+
+#[test]
+fn test_create_conditional_swap() -> Result<()> {
+    use crate::consumers::stats::Stats;
+    use crate::Source;
+
+    for sel_value in [0u8, 1] {
+        let mut b = new_test_builder();
+        let sel = b.create_gate(Private(0, Some(vec![sel_value])))?;
+        let a = b.create_gate(Private(0, Some(vec![3])))?;
+        let c = b.create_gate(Private(0, Some(vec![8])))?;
+        let (out_a, out_c) = b.create_conditional_swap(0, sel, a, c)?;
+
+        let values = evaluate_wires(b, &[out_a, out_c])?;
+        if sel_value == 0 {
+            assert_eq!(values, vec![BigUint::from(3u8), BigUint::from(8u8)]);
+        } else {
+            assert_eq!(values, vec![BigUint::from(8u8), BigUint::from(3u8)]);
+        }
+    }
+
+    // Exactly two multiplications
+    let mut b = new_test_builder();
+    let sel = b.create_gate(Private(0, Some(vec![1])))?;
+    let a = b.create_gate(Private(0, Some(vec![3])))?;
+    let c = b.create_gate(Private(0, Some(vec![8])))?;
+    b.create_conditional_swap(0, sel, a, c)?;
+    let source: Source = b.finish().into();
+    let stats = Stats::from_messages(source.iter_messages());
+    assert_eq!(stats.gate_stats.mul_gates, 2);
+    Ok(())
+}
+
+#[test]
+fn test_create_sum_of_products() -> Result<()> {
+    use crate::consumers::stats::Stats;
+    use crate::Source;
+
+    // MDS matrix used in some hash functions (e.g. Poseidon with t=3)
+    let matrix = vec![
+        vec![vec![2], vec![1], vec![1]],
+        vec![vec![1], vec![2], vec![1]],
+        vec![vec![1], vec![1], vec![2]],
+    ];
+    let input_values = [5u8, 7, 11];
+
+    let mut b = new_test_builder();
+    let inputs = create_private_inputs(&mut b, &input_values)?;
+    let outputs = b.create_sum_of_products(0, &inputs, &matrix)?;
+    let values = evaluate_wires(b, &outputs)?;
+    assert_eq!(
+        values,
+        vec![
+            BigUint::from(28u8),
+            BigUint::from(30u8),
+            BigUint::from(34u8)
+        ]
+    );
+
+    // A naive implementation would use 9 MulConstant gates
+    let mut b = new_test_builder();
+    let inputs = create_private_inputs(&mut b, &input_values)?;
+    b.create_sum_of_products(0, &inputs, &matrix)?;
+    let source: Source = b.finish().into();
+    let stats = Stats::from_messages(source.iter_messages());
+    assert_eq!(stats.gate_stats.mul_constant_gates, 3);
+    assert_eq!(stats.gate_stats.add_gates, 6);
+
+    // Rows must have one coefficient per input
+    let mut b = new_test_builder();
+    assert!(b
+        .create_sum_of_products(0, &inputs, &[vec![vec![1]]])
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_create_naf_mul() -> Result<()> {
+    use crate::consumers::stats::Stats;
+    use crate::producers::sink::MemorySink;
+    use crate::structs::types::Type;
+    use crate::Source;
+
+    // 7 = 0b111 has three non-zero binary digits, but only two NAF digits (8 - 1).
+    assert_eq!(naf_digits(&BigUint::from(7u8)), vec![-1, 0, 0, 1]);
+    assert_eq!(naf_digits(&BigUint::from(0u8)), Vec::<i8>::new());
+
+    for scalar in [0u8, 1, 7, 12, 100] {
+        let mut b = new_test_builder();
+        let x = b.create_gate(Private(0, Some(vec![3])))?;
+        let out = b.create_naf_mul(0, x, &[scalar])?;
+        let values = evaluate_wires(b, &[out])?;
+        assert_eq!(values, vec![BigUint::from(3 * scalar as u32 % 101)]);
+    }
+
+    // Besides the 3 doublings, the binary method would use two additions for 7,
+    // NAF uses a single one.
+    let mut b = new_test_builder();
+    let x = b.create_gate(Private(0, Some(vec![3])))?;
+    b.create_naf_mul(0, x, &[7])?;
+    let source: Source = b.finish().into();
+    let stats = Stats::from_messages(source.iter_messages());
+    assert_eq!(stats.gate_stats.add_gates, 3 + 1);
+
+    // The constant 2 is not an element of the Boolean field, but doubling does not need it.
+    let mut b = GateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![2])], &[]);
+    let x = b.create_gate(Private(0, Some(vec![1])))?;
+    let out = b.create_naf_mul(0, x, &[7])?;
+    assert_eq!(evaluate_wires(b, &[out])?, vec![BigUint::from(1u8)]);
+    Ok(())
+}
+
+#[test]
+fn test_boolean_operations() -> Result<()> {
+    use crate::producers::sink::MemorySink;
+    use crate::structs::types::Type;
+
+    for (left_value, right_value) in [(0u8, 0u8), (0, 1), (1, 0), (1, 1)] {
+        let mut b = GateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![2])], &[]);
+        let left = b.create_gate(Private(0, Some(vec![left_value])))?;
+        let right = b.create_gate(Private(0, Some(vec![right_value])))?;
+        let xor = b.create_xor(0, left, right)?;
+        let and = b.create_and(0, left, right)?;
+        let or = b.create_or(0, left, right)?;
+        let not = b.create_not(0, left)?;
+
+        let values = evaluate_wires(b, &[xor, and, or, not])?;
+        let expected = [
+            left_value ^ right_value,
+            left_value & right_value,
+            left_value | right_value,
+            1 - left_value,
+        ]
+        .iter()
+        .map(|value| BigUint::from(*value))
+        .collect::<Vec<_>>();
+        assert_eq!(values, expected);
+    }
+
+    // Boolean operations are rejected in other fields
+    let mut b = new_test_builder();
+    let x = b.create_gate(Private(0, Some(vec![1])))?;
+    let err = b.create_xor(0, x, x).unwrap_err();
+    assert_eq!(err.to_string(), "XOR not meaningful for non-binary field");
+    assert!(b.create_and(0, x, x).is_err());
+    assert!(b.create_or(0, x, x).is_err());
+    assert!(b.create_not(0, x).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_create_assert_nonzero() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::producers::builder::ProverContext;
+    use crate::producers::sink::MemorySink;
+    use crate::Source;
+
+    let mut b = new_test_builder();
+    let x = b.create_gate(Private(0, Some(vec![42])))?;
+    b.create_assert_nonzero(0, x)?;
+    evaluate_wires(b, &[])?;
+
+    // In prover mode, the inverse of a wire computed from the witnesses is computed as well
+    let mut context = ProverContext::default();
+    context.push_witness(0, vec![6]);
+    let mut b = GateBuilder::new_with_prover_mode(MemorySink::default(), &[vec![101]], context);
+    let x = b.create_gate(Private(0, None))?;
+    let x_plus_one = b.create_gate(AddConstant(0, x, vec![1]))?;
+    b.create_assert_nonzero(0, x_plus_one)?;
+    evaluate_wires(b, &[])?;
+
+    // A zero value cannot pass the check
+    let mut b = new_test_builder();
+    let x = b.create_gate(Private(0, Some(vec![0])))?;
+    b.create_assert_nonzero(0, x)?;
+    let source: Source = b.finish().into();
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(evaluator.get_violations().len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_create_less_than() -> Result<()> {
+    for (a_value, b_value, expected) in [(3u8, 7u8, 1u8), (7, 3, 0), (5, 5, 0)] {
+        let mut b = GateBuilder::new(
+            crate::producers::sink::MemorySink::default(),
+            &[],
+            &[crate::structs::types::Type::Field(vec![0x01, 0x01])], // 257
+            &[],
+        );
+        let a = b.create_gate(Private(0, Some(vec![a_value])))?;
+        let c = b.create_gate(Private(0, Some(vec![b_value])))?;
+        let less_than = b.create_less_than(0, a, c, 8)?;
+        let values = evaluate_wires(b, &[less_than])?;
+        assert_eq!(values, vec![BigUint::from(expected)]);
+    }
+
+    // The decomposition must fit in num_bits bits
+    let mut b = new_test_builder();
+    let x = b.create_gate(Private(0, Some(vec![42])))?;
+    assert!(b.create_bit_decomposition(0, x, 4).is_err());
+    let bits = b.create_bit_decomposition(0, x, 6)?;
+    let values = evaluate_wires(b, &bits)?;
+    let expected = [0u8, 1, 0, 1, 0, 1]
+        .iter()
+        .map(|bit| BigUint::from(*bit))
+        .collect::<Vec<_>>();
+    assert_eq!(values, expected);
+    Ok(())
+}
+
+#[test]
+fn test_create_bitonic_sort() -> Result<()> {
+    let inputs: [[u8; 4]; 4] = [[17, 3, 29, 8], [5, 5, 0, 31], [1, 2, 3, 4], [30, 20, 10, 0]];
+    for input in inputs.iter() {
+        let mut b = new_test_builder();
+        let wires = create_private_inputs(&mut b, input)?;
+        let sorted = b.create_bitonic_sort(0, &wires, 6)?;
+        let sorted_values = evaluate_wires(b, &sorted)?;
+
+        let mut expected = input.to_vec();
+        expected.sort_unstable();
+        let expected = expected
+            .iter()
+            .map(|value| BigUint::from(*value))
+            .collect::<Vec<_>>();
+        assert_eq!(sorted_values, expected);
+    }
+
+    let mut b = new_test_builder();
+    let wires = create_private_inputs(&mut b, &[1; 3])?;
+    assert!(b.create_bitonic_sort(0, &wires, 6).is_err());
+    assert_eq!(b.create_bitonic_sort(0, &wires[..1], 6)?, wires[..1]);
+    Ok(())
+}
+
+#[test]
+fn test_create_threshold() -> Result<()> {
+    let inputs = [1u8, 0, 1, 1, 0];
+    for (threshold, expected) in [(3, 1u8), (4, 0), (0, 1), (1, 1), (5, 0), (7, 0)] {
+        let mut b = new_test_builder();
+        let wires = create_private_inputs(&mut b, &inputs)?;
+        let output = b.create_threshold(0, &wires, threshold)?;
+        let values = evaluate_wires(b, &[output])?;
+        assert_eq!(values, vec![BigUint::from(expected)]);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_boolean_field_conversions() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::producers::sink::MemorySink;
+    use crate::structs::types::Type;
+    use crate::Source;
+
+    let new_builder = || {
+        GateBuilder::new(
+            MemorySink::default(),
+            &[],
+            &[Type::Field(vec![2]), Type::Field(vec![101])],
+            &[],
+        )
+    };
+
+    for bit_value in [0u8, 1] {
+        let mut b = new_builder();
+        let bit = b.create_gate(Private(0, Some(vec![bit_value])))?;
+        let lifted = b.create_boolean_to_field(0, 1, bit)?;
+        let back = b.create_field_to_boolean(1, 0, lifted)?;
+
+        let source: Source = b.finish().into();
+        let mut backend = PlaintextBackend::default();
+        let evaluator = Evaluator::from_messages(source.iter_messages(), &mut backend);
+        assert_eq!(evaluator.get(1, lifted)?, &BigUint::from(bit_value));
+        assert_eq!(evaluator.get(0, back)?, &BigUint::from(bit_value));
+        assert_eq!(evaluator.get_violations(), Vec::<String>::new());
+    }
+
+    // Non-boolean values are rejected
+    let mut b = new_builder();
+    let x = b.create_gate(Private(1, Some(vec![2])))?;
+    b.create_field_to_boolean(1, 0, x)?;
+    let source: Source = b.finish().into();
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(evaluator.get_violations().len(), 1);
+
+    // The Boolean type must be a binary field
+    let mut b = new_builder();
+    let x = b.create_gate(Private(1, Some(vec![1])))?;
+    assert!(b.create_boolean_to_field(1, 1, x).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_create_conditional_negate() -> Result<()> {
+    use crate::consumers::stats::Stats;
+    use crate::Source;
+
+    for (sel, expected) in [(0u8, 3u8), (1, 98)] {
+        let mut b = new_test_builder();
+        let sel = b.create_gate(Private(0, Some(vec![sel])))?;
+        let x = b.create_gate(Private(0, Some(vec![3])))?;
+        let out = b.create_conditional_negate(0, sel, x)?;
+        let values = evaluate_wires(b, &[out])?;
+        assert_eq!(values, vec![BigUint::from(expected)]);
+    }
+
+    let mut b = new_test_builder();
+    let sel = b.create_gate(Private(0, Some(vec![1])))?;
+    let x = b.create_gate(Private(0, Some(vec![3])))?;
+    b.create_conditional_negate(0, sel, x)?;
+    let source: Source = b.finish().into();
+    let stats = Stats::from_messages(source.iter_messages());
+    assert_eq!(stats.gate_stats.mul_gates, 1);
+    assert_eq!(stats.gate_stats.mul_constant_gates, 1);
+    assert_eq!(stats.gate_stats.add_gates, 1);
+    Ok(())
+}
+
+#[test]
+fn test_create_pow() -> Result<()> {
+    use crate::consumers::stats::Stats;
+    use crate::Source;
+    use num_traits::Pow;
+
+    for exponent in [0u64, 1, 2, 5, 13, 100] {
+        let mut b = new_test_builder();
+        let x = b.create_gate(Private(0, Some(vec![3])))?;
+        let out = b.create_pow(0, x, exponent)?;
+        let values = evaluate_wires(b, &[out])?;
+        let expected = Pow::pow(BigUint::from(3u8), exponent) % BigUint::from(101u8);
+        assert_eq!(values, vec![expected]);
+    }
+
+    // x^5 = (x^2)^2 * x requires 3 multiplications.
+    let mut b = new_test_builder();
+    let x = b.create_gate(Private(0, Some(vec![3])))?;
+    b.create_pow(0, x, 5)?;
+    let source: Source = b.finish().into();
+    let stats = Stats::from_messages(source.iter_messages());
+    assert_eq!(stats.gate_stats.mul_gates, 3);
+    Ok(())
+}
+
+#[test]
+fn test_create_assert_equal_to_constant() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::Source;
+
+    let mut b = new_test_builder();
+    let x = b.create_gate(Private(0, Some(vec![3])))?;
+    let x_squared = b.create_gate(Mul(0, x, x))?;
+    b.create_assert_equal_to_constant(0, x_squared, vec![9])?;
+    evaluate_wires(b, &[])?;
+
+    let mut b = new_test_builder();
+    let x = b.create_gate(Private(0, Some(vec![3])))?;
+    let x_squared = b.create_gate(Mul(0, x, x))?;
+    b.create_assert_equal_to_constant(0, x_squared, vec![10])?;
+    let source: Source = b.finish().into();
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(evaluator.get_violations().len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_create_accumulate() -> Result<()> {
+    use crate::consumers::stats::Stats;
+    use crate::Source;
+
+    let mut b = new_test_builder();
+    let wires = create_private_inputs(&mut b, &[3u8, 4, 5, 99])?;
+    let sums = b.create_accumulate(0, &wires)?;
+    assert_eq!(sums[0], wires[0]);
+    let values = evaluate_wires(b, &sums)?;
+    let expected = [3u8, 7, 12, 10]
+        .iter()
+        .map(|value| BigUint::from(*value))
+        .collect::<Vec<_>>();
+    assert_eq!(values, expected);
+
+    let mut b = new_test_builder();
+    assert_eq!(b.create_accumulate(0, &[])?, Vec::<WireId>::new());
+    let wires = b.create_private_input_array(0, vec![vec![3], vec![4], vec![5]])?;
+    b.create_accumulate(0, &(wires.first_id..=wires.last_id).collect::<Vec<_>>())?;
+    let source: Source = b.finish().into();
+    let stats = Stats::from_messages(source.iter_messages());
+    assert_eq!(stats.gate_stats.add_gates, 2);
+    Ok(())
+}
+
+#[test]
+fn test_create_rotation() -> Result<()> {
+    use crate::consumers::stats::Stats;
+    use crate::Source;
+
+    let expected = |values: &[u8]| {
+        values
+            .iter()
+            .map(|value| BigUint::from(*value))
+            .collect::<Vec<_>>()
+    };
+
+    let mut b = new_test_builder();
+    let wires = b.create_private_input_array(0, vec![vec![1], vec![2], vec![3], vec![4]])?;
+    let wires = (wires.first_id..=wires.last_id).collect::<Vec<_>>();
+    let left = b.rotate_left(&wires, 1);
+    let right = b.rotate_right(&wires, 1);
+    let full = b.create_rotation(&wires, 4);
+    let values = evaluate_wires(b, &[left, right, full].concat())?;
+    assert_eq!(values[0..4], expected(&[2, 3, 4, 1])[..]);
+    assert_eq!(values[4..8], expected(&[4, 1, 2, 3])[..]);
+    assert_eq!(values[8..12], expected(&[1, 2, 3, 4])[..]);
+
+    let mut b = new_test_builder();
+    let wires = b.create_private_input_array(0, vec![vec![1], vec![2], vec![3], vec![4]])?;
+    b.create_rotation(&(wires.first_id..=wires.last_id).collect::<Vec<_>>(), 1);
+    let source: Source = b.finish().into();
+    let stats = Stats::from_messages(source.iter_messages());
+    assert_eq!(stats.gate_stats.copy_gates, 4);
+    Ok(())
+}
+
+#[test]
+fn test_create_bit_pack() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::consumers::stats::Stats;
+    use crate::Source;
+
+    let mut b = new_test_builder();
+    let x = b.create_gate(Private(0, Some(vec![42])))?;
+    let bits = b.create_bit_decomposition(0, x, 6)?;
+    let packed = b.create_bit_pack(0, &bits)?;
+    let diff = b.create_sub(0, packed, x)?;
+    b.create_gate(AssertZero(0, diff))?;
+    let values = evaluate_wires(b, &[packed])?;
+    assert_eq!(values, vec![BigUint::from(42u8)]);
+
+    let mut b = new_test_builder();
+    let bits = b.create_private_input_array(0, vec![vec![1], vec![0], vec![1], vec![1]])?;
+    let packed = b.create_bit_pack(0, &(bits.first_id..=bits.last_id).collect::<Vec<_>>())?;
+    let source: Source = b.finish().into();
+    let stats = Stats::from_messages(source.iter_messages());
+    assert_eq!(stats.gate_stats.mul_constant_gates, 3);
+    assert_eq!(stats.gate_stats.add_gates, 3);
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(*evaluator.get(0, packed)?, BigUint::from(13u8));
+    Ok(())
+}
+
+#[test]
+fn test_create_hamming_weight() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::Source;
+
+    let mut b = new_test_builder();
+    let bits = b.create_private_input_array(
+        0,
+        [0u8, 1, 0, 0, 1, 1, 0, 0]
+            .iter()
+            .map(|bit| vec![*bit])
+            .collect(),
+    )?;
+    let weight = b.create_hamming_weight(0, &(bits.first_id..=bits.last_id).collect::<Vec<_>>())?;
+    let values = evaluate_wires(b, &[weight])?;
+    assert_eq!(values, vec![BigUint::from(3u8)]);
+
+    // Non-boolean inputs are rejected
+    let mut b = new_test_builder();
+    let bits = b.create_private_input_array(0, vec![vec![1], vec![2]])?;
+    b.create_hamming_weight(0, &[bits.first_id, bits.last_id])?;
+    let source: Source = b.finish().into();
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(evaluator.get_violations().len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_create_range_constrained_input() -> Result<()> {
+    for value in 0u8..16 {
+        let mut b = new_test_builder();
+        let x = b.create_range_constrained_input(0, 4, Some(vec![value]))?;
+        let values = evaluate_wires(b, &[x])?;
+        assert_eq!(values, vec![BigUint::from(value)]);
+    }
+
+    for value in [16u8, 17, 100] {
+        let mut b = new_test_builder();
+        assert!(b
+            .create_range_constrained_input(0, 4, Some(vec![value]))
+            .is_err());
+    }
+
+    // The verifier does not know the value
+    let mut b = new_test_builder();
+    b.create_range_constrained_input(0, 4, None)?;
+    Ok(())
+}
+
+#[test]
+fn test_create_assert_bits() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::consumers::stats::Stats;
+    use crate::Source;
+
+    let mut b = new_test_builder();
+    let zero = b.create_gate(Private(0, Some(vec![0])))?;
+    let one = b.create_gate(Private(0, Some(vec![1])))?;
+    let gate_count = b.current_gate_count();
+    b.create_assert_bits(0, &[zero, one, one])?;
+    assert_eq!(b.current_gate_count() - gate_count, 4 * 3);
+    let source: Source = b.finish().into();
+    let stats = Stats::from_messages(source.iter_messages());
+    assert_eq!(stats.gate_stats.mul_gates, 3);
+    assert_eq!(stats.gate_stats.mul_constant_gates, 3);
+    assert_eq!(stats.gate_stats.add_gates, 3);
+    assert_eq!(stats.gate_stats.assert_zero_gates, 3);
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(evaluator.get_violations(), Vec::<String>::new());
+
+    let mut b = new_test_builder();
+    let one = b.create_gate(Private(0, Some(vec![1])))?;
+    let two = b.create_gate(Private(0, Some(vec![2])))?;
+    b.create_assert_bits(0, &[one, two])?;
+    let source: Source = b.finish().into();
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(evaluator.get_violations().len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_create_shuffle_proof() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::Source;
+
+    let build = |original: &[u8], shuffled: &[u8], permutation: &[usize]| -> Result<Vec<String>> {
+        let mut b = new_test_builder();
+        let original = create_private_inputs(&mut b, original)?;
+        let shuffled = create_private_inputs(&mut b, shuffled)?;
+        b.create_shuffle_proof(0, &original, &shuffled, permutation)?;
+        let source: Source = b.finish().into();
+        let evaluator =
+            Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+        Ok(evaluator.get_violations())
+    };
+
+    // Swap of the two first elements
+    assert_eq!(
+        build(&[3, 5, 7, 9], &[5, 3, 7, 9], &[1, 0, 2, 3])?,
+        Vec::<String>::new()
+    );
+    // Not a permutation of the original values
+    assert!(build(&[3, 5, 7, 9], &[3, 3, 7, 9], &[0, 1, 2, 3]).is_err());
+    // Invalid permutations
+    assert!(build(&[3, 5, 7, 9], &[5, 3, 7, 9], &[1, 1, 2, 3]).is_err());
+    assert!(build(&[3, 5, 7, 9], &[5, 3, 7, 9], &[1, 0, 2]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_create_inner_product_argument() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::Source;
+
+    let violations = |a: &[u8], b: &[u8], expected: u8| -> Result<Vec<String>> {
+        let mut builder = new_test_builder();
+        let a = create_private_inputs(&mut builder, a)?;
+        let b = create_private_inputs(&mut builder, b)?;
+        let expected = builder.create_gate(Public(0, Some(vec![expected])))?;
+        builder.create_inner_product_argument(0, &a, &b, expected)?;
+        let source: Source = builder.finish().into();
+        let evaluator =
+            Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+        Ok(evaluator.get_violations())
+    };
+
+    // 1*5 + 2*6 + 3*7 + 4*8 = 70
+    assert_eq!(
+        violations(&[1, 2, 3, 4], &[5, 6, 7, 8], 70)?,
+        Vec::<String>::new()
+    );
+    assert_eq!(violations(&[1, 2, 3, 4], &[5, 6, 7, 8], 71)?.len(), 1);
+    // Odd lengths: 1*5 + 2*6 + 3*7 = 38
+    assert_eq!(
+        violations(&[1, 2, 3], &[5, 6, 7], 38)?,
+        Vec::<String>::new()
+    );
+    assert_eq!(violations(&[3], &[7], 21)?, Vec::<String>::new());
+    assert_eq!(violations(&[], &[], 0)?, Vec::<String>::new());
+    assert!(violations(&[1, 2], &[5], 5).is_err());
+
+    let mut b = new_test_builder();
+    let x = b.create_gate(Private(0, Some(vec![3])))?;
+    let y = b.create_gate(Private(0, Some(vec![4])))?;
+    let ip = b.create_inner_product(0, &[x, y], &[y, x])?;
+    let lc = b.create_linear_combination(0, &[x, y], &[vec![2], vec![100]])?;
+    let values = evaluate_wires(b, &[ip, lc])?;
+    assert_eq!(values, vec![BigUint::from(24u8), BigUint::from(99u8)]);
+    Ok(())
+}
+
+#[test]
+fn test_create_shuffle() -> Result<()> {
+    let mut b = new_test_builder();
+    let wires = create_private_inputs(&mut b, &[3u8, 5, 7, 9])?;
+    let reversed = b.create_shuffle(0, &wires, &[3, 2, 1, 0])?;
+    let rotated = b.create_shuffle(0, &wires, &[1, 2, 3, 0])?;
+    assert!(b.create_shuffle(0, &wires, &[0, 1, 1, 3]).is_err());
+    assert!(b.create_shuffle(0, &wires, &[0, 1, 2, 4]).is_err());
+    assert!(b.create_shuffle(0, &wires, &[0, 1, 2]).is_err());
+
+    let values = evaluate_wires(b, &[reversed, rotated].concat())?;
+    let expected = [9u8, 7, 5, 3, 5, 7, 9, 3]
+        .iter()
+        .map(|value| BigUint::from(*value))
+        .collect::<Vec<_>>();
+    assert_eq!(values, expected);
+    Ok(())
+}
+
+#[test]
+fn test_create_ntt() -> Result<()> {
+    use crate::consumers::stats::Stats;
+    use crate::Source;
+
+    // 10 is a primitive 4th root of unity mod 101 (10^2 = 100 = -1)
+    let inputs = [3u32, 1, 4, 1];
+    let reference = (0..4u32)
+        .map(|k| {
+            inputs
+                .iter()
+                .enumerate()
+                .map(|(j, x)| {
+                    BigUint::from(*x)
+                        * BigUint::from(10u8)
+                            .modpow(&BigUint::from(j as u32 * k), &BigUint::from(101u8))
+                })
+                .sum::<BigUint>()
+                % 101u8
+        })
+        .collect::<Vec<_>>();
+
+    let mut b = new_test_builder();
+    let wires = create_private_inputs(&mut b, &inputs.map(|value| value as u8))?;
+    let outputs = b.create_ntt(0, &wires, vec![10])?;
+    assert_eq!(evaluate_wires(b, &outputs)?, reference);
+
+    let mut b = new_test_builder();
+    let wires = create_private_inputs(&mut b, &[1; 4])?;
+    b.create_ntt(0, &wires, vec![10])?;
+    // Not a power of two
+    assert!(b.create_ntt(0, &wires[..3], vec![10]).is_err());
+    // 100 is a square root of unity, not a primitive 4th root
+    assert!(b.create_ntt(0, &wires, vec![100]).is_err());
+    assert!(b.create_ntt(0, &wires, vec![3]).is_err());
+    let source: Source = b.finish().into();
+    let stats = Stats::from_messages(source.iter_messages());
+    assert_eq!(stats.gate_stats.mul_constant_gates, 4 * 2);
+    assert_eq!(stats.gate_stats.add_gates, 4 * 2);
+    Ok(())
+}
+
+#[test]
+fn test_create_modular_reduction() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::producers::sink::MemorySink;
+    use crate::structs::types::Type;
+    use crate::Source;
+
+    // From the 16-bit prime field 65521 to the 8-bit prime field 251
+    let new_builder = || {
+        GateBuilder::new(
+            MemorySink::default(),
+            &[],
+            &[
+                Type::Field(BigUint::from(65521u32).to_bytes_le()),
+                Type::Field(vec![251]),
+            ],
+            &[],
+        )
+    };
+    for value in [0u32, 250, 251, 30000, 32767] {
+        let mut b = new_builder();
+        let value = BigUint::from(value).to_bytes_le();
+        let x = b.create_gate(Private(0, Some(value.clone())))?;
+        let reduced = b.create_modular_reduction(0, 1, x, 15, 8)?;
+
+        let source: Source = b.finish().into();
+        let evaluator =
+            Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+        assert_eq!(
+            *evaluator.get(1, reduced)?,
+            value_to_biguint(&value) % 251u8
+        );
+        assert_eq!(evaluator.get_violations(), Vec::<String>::new());
+    }
+
+    let mut b = new_builder();
+    let x = b.create_gate(Private(0, Some(vec![1])))?;
+    // 2^16 is greater than the source modulo
+    assert!(b.create_modular_reduction(0, 1, x, 16, 8).is_err());
+    // 251 does not fit in 7 bits
+    assert!(b.create_modular_reduction(0, 1, x, 15, 7).is_err());
+    // 32768 does not fit in 15 bits
+    let x = b.create_gate(Private(0, Some(BigUint::from(32768u32).to_bytes_le())))?;
+    assert!(b.create_modular_reduction(0, 1, x, 15, 8).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_create_fibonacci_sequence() -> Result<()> {
+    use crate::consumers::stats::Stats;
+    use crate::Source;
+
+    let mut b = new_test_builder();
+    let sequence = b.create_fibonacci_sequence(0, 10)?;
+    assert_eq!(sequence.len(), 10);
+    assert_eq!(b.create_fibonacci_sequence(0, 1)?.len(), 1);
+    assert!(b.create_fibonacci_sequence(0, 0)?.is_empty());
+    let source: Source = b.finish().into();
+    let stats = Stats::from_messages(source.iter_messages());
+    assert_eq!(stats.gate_stats.add_gates, 8);
+
+    let mut b = new_test_builder();
+    let sequence = b.create_fibonacci_sequence(0, 10)?;
+    let values = evaluate_wires(b, &sequence)?;
+    let expected = [0u8, 1, 1, 2, 3, 5, 8, 13, 21, 34]
+        .iter()
+        .map(|value| BigUint::from(*value))
+        .collect::<Vec<_>>();
+    assert_eq!(values, expected);
+
+    // Starting from private inputs, with a reduction modulo 101
+    let mut b = new_test_builder();
+    let first = b.create_gate(Private(0, Some(vec![50])))?;
+    let second = b.create_gate(Private(0, Some(vec![60])))?;
+    let sequence = b.create_fibonacci_from_inputs(0, first, second, 4)?;
+    let values = evaluate_wires(b, &sequence)?;
+    let expected = [50u8, 60, 9, 69]
+        .iter()
+        .map(|value| BigUint::from(*value))
+        .collect::<Vec<_>>();
+    assert_eq!(values, expected);
+    Ok(())
+}
+
+#[test]
+fn test_create_fibonacci_via_function() -> Result<()> {
+    use crate::consumers::stats::Stats;
+    use crate::Source;
+
+    let mut b = new_test_builder();
+    let sequence = b.create_fibonacci_via_function(0, 10)?;
+    assert_eq!(sequence.len(), 10);
+    assert_eq!(b.create_fibonacci_via_function(0, 1)?.len(), 1);
+    assert!(b.create_fibonacci_via_function(0, 0)?.is_empty());
+    // add_step is defined once and reused
+    assert_eq!(b.create_fibonacci_via_function(0, 3)?.len(), 3);
+    let source: Source = b.finish().into();
+    let stats = Stats::from_messages(source.iter_messages());
+    assert_eq!(stats.gate_stats.functions_defined, 1);
+    assert_eq!(stats.gate_stats.functions_called, 9);
+
+    let mut b = new_test_builder();
+    let via_function = b.create_fibonacci_via_function(0, 10)?;
+    let inlined = b.create_fibonacci_sequence(0, 10)?;
+    let values = evaluate_wires(b, &[via_function, inlined].concat())?;
+    let expected = [0u8, 1, 1, 2, 3, 5, 8, 13, 21, 34]
+        .iter()
+        .map(|value| BigUint::from(*value))
+        .collect::<Vec<_>>();
+    assert_eq!(values, [expected.clone(), expected].concat());
+
+    // An existing add_step with another signature is rejected
+    let mut b = new_test_builder();
+    let add_step = {
+        let mut fb = b.new_function_builder(
+            "add_step".to_string(),
+            vec![Count::new(0, 1)],
+            vec![Count::new(0, 1)],
+        );
+        let inputs = fb.input_wires();
+        let output = fb.create_gate(Copy(0, inputs[0].1));
+        fb.finish(vec![WireRange::new(output, output)])?
+    };
+    b.push_function(add_step)?;
+    assert!(b.create_fibonacci_via_function(0, 3).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_create_crt_reconstruct() -> Result<()> {
+    use crate::producers::sink::MemorySink;
+    use crate::structs::types::Type;
+
+    let new_builder = || {
+        GateBuilder::new(
+            MemorySink::default(),
+            &[],
+            &[
+                Type::Field(vec![101]),
+                Type::Field(vec![5]),
+                Type::Field(vec![7]),
+            ],
+            &[],
+        )
+    };
+
+    // 23 = 3 mod 5 = 2 mod 7
+    let mut b = new_builder();
+    let r_5 = b.create_gate(Private(1, Some(vec![3])))?;
+    let r_7 = b.create_gate(Private(2, Some(vec![2])))?;
+    let x = b.create_crt_reconstruct(&[vec![5], vec![7]], &[r_5, r_7], 0)?;
+    assert_eq!(evaluate_wires(b, &[x])?, vec![BigUint::from(23u8)]);
+
+    let mut b = new_builder();
+    let r_5 = b.create_gate(Private(1, Some(vec![3])))?;
+    let r_7 = b.create_gate(Private(2, Some(vec![2])))?;
+    // The moduli must be increasing
+    assert!(b
+        .create_crt_reconstruct(&[vec![7], vec![5]], &[r_7, r_5], 0)
+        .is_err());
+    // Each modulo must be the modulo of a type
+    assert!(b
+        .create_crt_reconstruct(&[vec![5], vec![11]], &[r_5, r_7], 0)
+        .is_err());
+    // 5 * 7 does not fit in the Field 7
+    assert!(b
+        .create_crt_reconstruct(&[vec![5], vec![7]], &[r_5, r_7], 2)
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_create_select_from_array() -> Result<()> {
+    use crate::consumers::stats::Stats;
+    use crate::Source;
+
+    let array_values = [11u8, 22, 33, 44];
+    let build = |len: usize, index: u8| -> Result<_> {
+        let mut b = new_test_builder();
+        let array = create_private_inputs(&mut b, &array_values[..len])?;
+        let index_wire = b.create_gate(Private(0, Some(vec![index])))?;
+        assert!(b
+            .create_select_from_array(0, &array, index_wire, 1)
+            .is_err());
+        let selected = b.create_select_from_array(0, &array, index_wire, 2)?;
+        Ok((b, selected))
+    };
+
+    for len in [3, 4] {
+        for index in 0..len {
+            let (b, selected) = build(len, index as u8)?;
+            let values = evaluate_wires(b, &[selected])?;
+            assert_eq!(values, vec![BigUint::from(array_values[index])]);
+        }
+    }
+
+    // An array of 3 elements needs 2 muxes, i.e. 2 multiplications besides
+    // the booleanity checks of the 2 index bits
+    let (b, _) = build(3, 2)?;
+    let source: Source = b.finish().into();
+    let stats = Stats::from_messages(source.iter_messages());
+    assert_eq!(stats.gate_stats.mul_gates, 4);
+    Ok(())
+}
+
+#[test]
+fn test_create_permutation_check() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::Source;
+
+    let build = |a: &[u8], c: &[u8]| -> Result<Vec<String>> {
+        let mut b = new_test_builder();
+        let a = create_private_inputs(&mut b, a)?;
+        let c = create_private_inputs(&mut b, c)?;
+        let gamma = b.create_gate(Public(0, Some(vec![42])))?;
+        b.create_permutation_check(0, &a, &c, gamma)?;
+        let source: Source = b.finish().into();
+        let evaluator =
+            Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+        Ok(evaluator.get_violations())
+    };
+
+    assert_eq!(build(&[3, 5, 7, 9], &[9, 3, 5, 7])?, Vec::<String>::new());
+    assert_eq!(build(&[3, 5, 7, 9], &[9, 3, 5, 8])?.len(), 1);
+    assert!(build(&[3, 5, 7, 9], &[9, 3, 5]).is_err());
+
+    let mut b = new_test_builder();
+    let wires = create_private_inputs(&mut b, &[2u8, 3, 4])?;
+    let product = b.create_mul_many(0, &wires)?;
+    let one = b.create_mul_many(0, &[])?;
+    let values = evaluate_wires(b, &[product, one])?;
+    assert_eq!(values, vec![BigUint::from(24u8), BigUint::from(1u8)]);
+    Ok(())
+}
+
+#[test]
+fn test_create_assert_permutation_of_set() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::Source;
+
+    let build = |values: &[u8]| -> Result<Vec<String>> {
+        let mut b = new_test_builder();
+        let wires = create_private_inputs(&mut b, values)?;
+        let gamma = b.create_gate(Public(0, Some(vec![42])))?;
+        let set = [vec![0], vec![1], vec![2], vec![3]];
+        b.create_assert_permutation_of_set(0, &wires, &set, gamma)?;
+        let source: Source = b.finish().into();
+        let evaluator =
+            Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+        Ok(evaluator.get_violations())
+    };
+
+    assert_eq!(build(&[2, 0, 3, 1])?, Vec::<String>::new());
+    assert_eq!(build(&[0, 1, 2, 3])?, Vec::<String>::new());
+    assert_eq!(build(&[2, 0, 3, 3])?.len(), 1);
+    assert!(build(&[2, 0, 3]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_create_merkle_path_verification() -> Result<()> {
+    // A simple (insecure) hash over the field 101: h(left, right) = left^2 + 3*right
+    let hash = |left: u32, right: u32| (left * left + 3 * right) % 101;
+    let mut b = new_test_builder();
+    let hash_function = {
+        let mut fb = b.new_function_builder(
+            "hash".to_string(),
+            vec![Count::new(0, 1)],
+            vec![Count::new(0, 1), Count::new(0, 1)],
+        );
+        let inputs = fb.input_wires();
+        let square = fb.create_gate(Mul(0, inputs[0].1, inputs[0].1));
+        let triple = fb.create_gate(MulConstant(0, inputs[1].1, vec![3]));
+        let output = fb.create_gate(Add(0, square, triple));
+        fb.finish(vec![WireRange::new(output, output)])?
+    };
+    b.push_function(hash_function)?;
+
+    // Depth-3 path: the leaf is a right child at level 1 only
+    let leaf_value = 5u32;
+    let path_values = [(7u32, 0u32), (11, 1), (13, 0)];
+    let mut expected_root = leaf_value;
+    for (sibling, direction) in path_values.iter() {
+        expected_root = if *direction == 0 {
+            hash(expected_root, *sibling)
+        } else {
+            hash(*sibling, expected_root)
+        };
+    }
+
+    let leaf = b.create_gate(Private(0, Some(vec![leaf_value as u8])))?;
+    let path = path_values
+        .iter()
+        .map(|(sibling, direction)| -> Result<(WireId, WireId)> {
+            let sibling = b.create_gate(Private(0, Some(vec![*sibling as u8])))?;
+            let direction = b.create_gate(Private(0, Some(vec![*direction as u8])))?;
+            Ok((sibling, direction))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    assert!(b
+        .create_merkle_path_verification(0, leaf, &path, "unknown")
+        .is_err());
+    let root = b.create_merkle_path_verification(0, leaf, &path, "hash")?;
+
+    let values = evaluate_wires(b, &[root])?;
+    assert_eq!(values, vec![BigUint::from(expected_root)]);
+    Ok(())
+}
+
+#[test]
+fn test_create_ec_add() -> Result<()> {
+    // Points of the curve y^2 = x^3 + 3 over the field 101
+    let build = |p1: [u8; 2], p2: [u8; 2]| -> Result<(GateBuilder<_>, (WireId, WireId))> {
+        let mut b = new_test_builder();
+        let wires = create_private_inputs(&mut b, &[p1[0], p1[1], p2[0], p2[1]])?;
+        let sum = b.create_ec_add(0, wires[0], wires[1], wires[2], wires[3])?;
+        Ok((b, sum))
+    };
+
+    // Doubling: 2 * (1, 2) = (68, 74)
+    let (b, (x, y)) = build([1, 2], [1, 2])?;
+    let values = evaluate_wires(b, &[x, y])?;
+    assert_eq!(values, vec![BigUint::from(68u8), BigUint::from(74u8)]);
+
+    // Addition of distinct points: (1, 2) + (3, 63) = (93, 20)
+    let (b, (x, y)) = build([1, 2], [3, 63])?;
+    let values = evaluate_wires(b, &[x, y])?;
+    assert_eq!(values, vec![BigUint::from(93u8), BigUint::from(20u8)]);
+
+    // (1, 2) + (1, -2) is the point at infinity: there is no slope
+    assert!(build([1, 2], [1, 99]).is_err());
+    Ok(())
+}
+
+#[test]
+fn test_create_hash_to_field() -> Result<()> {
+    // Horner evaluation of 3 + 2x + x^2 at x = 5
+    let mut b = new_test_builder();
+    let coefficients = create_private_inputs(&mut b, &[3u8, 2, 1])?;
+    let x = b.create_gate(Private(0, Some(vec![5])))?;
+    let result = b.create_horner_eval(0, &coefficients, x)?;
+    assert_eq!(evaluate_wires(b, &[result])?, vec![BigUint::from(38u8)]);
+
+    // A toy hash of 4 bits into 4 bits: out_i = in_i XOR in_{i+1} = a + c - 2ac
+    let mut b = new_test_builder();
+    let hash_function = {
+        let mut fb = b.new_function_builder(
+            "xor_hash".to_string(),
+            vec![Count::new(0, 4)],
+            vec![Count::new(0, 4)],
+        );
+        let inputs = fb.input_wires();
+        let outputs = (0..4)
+            .map(|i| {
+                let a = inputs[i].1;
+                let c = inputs[(i + 1) % 4].1;
+                let ac = fb.create_gate(Mul(0, a, c));
+                let minus_2ac = fb.create_gate(MulConstant(0, ac, vec![99]));
+                let a_plus_c = fb.create_gate(Add(0, a, c));
+                fb.create_gate(Add(0, a_plus_c, minus_2ac))
+            })
+            .collect::<Vec<_>>();
+        let copies = outputs
+            .iter()
+            .map(|output| fb.create_gate(Copy(0, *output)))
+            .collect::<Vec<_>>();
+        fb.finish(vec![WireRange::new(copies[0], copies[3])])?
+    };
+    b.push_function(hash_function)?;
+
+    let inputs = create_private_inputs(&mut b, &[1u8, 0, 1, 1])?;
+    assert!(b.create_hash_to_field(0, &inputs, 3, "xor_hash").is_err());
+    assert!(b
+        .create_hash_to_field(0, &inputs[..3], 4, "xor_hash")
+        .is_err());
+    assert!(b.create_hash_to_field(0, &inputs, 4, "unknown").is_err());
+    // [1^0, 0^1, 1^1, 1^1] = [1, 1, 0, 0], packed into 3
+    let hash = b.create_hash_to_field(0, &inputs, 4, "xor_hash")?;
+    assert_eq!(evaluate_wires(b, &[hash])?, vec![BigUint::from(3u8)]);
+    Ok(())
+}
+
+#[test]
+fn test_create_vector_dot_product() -> Result<()> {
+    let mut b = new_test_builder();
+    // Wires 0..=2 hold [1, 2, 3] and wires 3..=6 hold [4, 5, 6, 7]
+    for value in 1..=7u8 {
+        b.create_gate(Private(0, Some(vec![value])))?;
+    }
+    let left = WireRangeWithType::new(0, 0, 2);
+    let right = WireRangeWithType::new(0, 3, 5);
+    let longer = WireRangeWithType::new(0, 3, 6);
+    let other_type = WireRangeWithType::new(1, 3, 5);
+
+    assert!(b.create_vector_dot_product(0, &left, &longer).is_err());
+    assert!(b.create_vector_dot_product(0, &left, &other_type).is_err());
+    assert!(b
+        .create_vector_dot_product(0, &left, &WireRangeWithType::new(0, 5, 3))
+        .is_err());
+    // 1*4 + 2*5 + 3*6 = 32
+    let dot = b.create_vector_dot_product(0, &left, &right)?;
+    assert_eq!(evaluate_wires(b, &[dot])?, vec![BigUint::from(32u8)]);
+    Ok(())
+}
+
+#[test]
+fn test_create_ternary_op() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::Source;
+
+    let build = |sel0: u8, sel1: u8| -> Result<(GateBuilder<_>, WireId)> {
+        let mut b = new_test_builder();
+        let wires = create_private_inputs(&mut b, &[sel0, sel1, 10, 20, 30])?;
+        let result = b.create_ternary_op(0, wires[0], wires[1], wires[2], wires[3], wires[4])?;
+        Ok((b, result))
+    };
+
+    for (sel0, sel1, expected) in [(1, 0, 10u8), (0, 1, 20), (0, 0, 30)] {
+        let (b, result) = build(sel0, sel1)?;
+        assert_eq!(evaluate_wires(b, &[result])?, vec![BigUint::from(expected)]);
+    }
+
+    // Both selectors cannot be set: their sum does not fit in 1 bit
+    assert!(build(1, 1).is_err());
+    assert!(build(2, 0).is_err());
+
+    // The selectors must be boolean, even if their sum is 0 (-1 + 1)
+    let (b, _) = build(100, 1)?;
+    let source: Source = b.finish().into();
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(evaluator.get_violations().len(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_create_oblivious_read_write() -> Result<()> {
+    let mut b = new_test_builder();
+    let memory = create_private_inputs(&mut b, &[10u8, 20, 30, 40, 50])?;
+    let index = b.create_gate(Private(0, Some(vec![3])))?;
+    assert!(b
+        .create_oblivious_write(0, &memory, index, index, 2)
+        .is_err());
+
+    // memory[3] += 5
+    let read = b.create_oblivious_read(0, &memory, index, 3)?;
+    let incremented = b.create_gate(AddConstant(0, read, vec![5]))?;
+    let memory = b.create_oblivious_write(0, &memory, index, incremented, 3)?;
+    let read_again = b.create_oblivious_read(0, &memory, index, 3)?;
+
+    let values = evaluate_wires(b, &[memory, vec![read, read_again]].concat())?;
+    let expected = [10u8, 20, 30, 45, 50, 40, 45]
+        .iter()
+        .map(|value| BigUint::from(*value))
+        .collect::<Vec<_>>();
+    assert_eq!(values, expected);
+    Ok(())
+}
+
+#[test]
+fn test_create_full_adder() -> Result<()> {
+    use crate::producers::sink::MemorySink;
+    use crate::structs::types::Type;
+
+    for (left, right) in [(181u16, 110u16), (0, 0), (255, 1), (100, 27)] {
+        let mut b = GateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![2])], &[]);
+        let bits = |b: &mut GateBuilder<MemorySink>, value: u16| {
+            create_private_inputs(
+                b,
+                &(0..8).map(|i| ((value >> i) & 1) as u8).collect::<Vec<_>>(),
+            )
+        };
+        let left_bits = bits(&mut b, left)?;
+        let right_bits = bits(&mut b, right)?;
+
+        // Ripple-carry addition of 8 full adders
+        let mut carry = b.create_zero(0)?;
+        let mut sum_bits = vec![];
+        for (left_bit, right_bit) in left_bits.iter().zip(right_bits.iter()) {
+            let (sum, carry_out) = b.create_full_adder(0, *left_bit, *right_bit, carry)?;
+            sum_bits.push(sum);
+            carry = carry_out;
+        }
+        sum_bits.push(carry);
+
+        let values = evaluate_wires(b, &sum_bits)?;
+        let expected = (0..9)
+            .map(|i| BigUint::from(((left + right) >> i) & 1))
+            .collect::<Vec<_>>();
+        assert_eq!(values, expected);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_create_binary_adder() -> Result<()> {
+    use crate::consumers::stats::Stats;
+    use crate::producers::sink::MemorySink;
+    use crate::structs::types::Type;
+    use crate::Source;
+
+    let new_builder = || GateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![2])], &[]);
+    let bits = |b: &mut GateBuilder<MemorySink>, value: u16| {
+        create_private_inputs(
+            b,
+            &(0..8).map(|i| ((value >> i) & 1) as u8).collect::<Vec<_>>(),
+        )
+    };
+
+    for (left, right) in [(200u16, 100u16), (37, 5), (255, 255), (0, 0)] {
+        let mut b = new_builder();
+        let left_bits = bits(&mut b, left)?;
+        let right_bits = bits(&mut b, right)?;
+        let sum_bits = b.create_binary_adder(0, &left_bits, &right_bits)?;
+        assert_eq!(sum_bits.len(), 9);
+
+        let values = evaluate_wires(b, &sum_bits)?;
+        let expected = (0..9)
+            .map(|i| BigUint::from(((left + right) >> i) & 1))
+            .collect::<Vec<_>>();
+        assert_eq!(values, expected);
+    }
+
+    // 1 half adder (2 gates) and 7 full adders (7 gates each)
+    let mut b = new_builder();
+    let left_bits = bits(&mut b, 1)?;
+    let right_bits = bits(&mut b, 2)?;
+    assert!(b
+        .create_binary_adder(0, &left_bits, &right_bits[1..])
+        .is_err());
+    b.create_binary_adder(0, &left_bits, &right_bits)?;
+    let source: Source = b.finish().into();
+    let stats = Stats::from_messages(source.iter_messages());
+    assert_eq!(
+        stats.gate_stats.add_gates + stats.gate_stats.mul_gates,
+        2 + 7 * 7
+    );
+    Ok(())
+}
+
+#[test]
+fn test_create_carry_save_adder() -> Result<()> {
+    use crate::producers::sink::MemorySink;
+    use crate::structs::types::Type;
+
+    let bits = |b: &mut GateBuilder<MemorySink>, value: u16| {
+        create_private_inputs(
+            b,
+            &(0..8).map(|i| ((value >> i) & 1) as u8).collect::<Vec<_>>(),
+        )
+    };
+    let to_number = |bits: &[BigUint]| {
+        bits.iter()
+            .enumerate()
+            .map(|(i, bit)| if bit.is_zero() { 0u16 } else { 1 << i })
+            .sum::<u16>()
+    };
+
+    for (a, c, d) in [
+        (200u16, 100u16, 255u16),
+        (37, 5, 90),
+        (255, 255, 255),
+        (0, 0, 0),
+    ] {
+        let mut b = GateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![2])], &[]);
+        let a_bits = bits(&mut b, a)?;
+        let c_bits = bits(&mut b, c)?;
+        let d_bits = bits(&mut b, d)?;
+        assert!(b
+            .create_carry_save_adder(0, &a_bits, &c_bits, &d_bits[1..])
+            .is_err());
+        let (sum_bits, carry_bits) = b.create_carry_save_adder(0, &a_bits, &c_bits, &d_bits)?;
+        assert_eq!((sum_bits.len(), carry_bits.len()), (8, 8));
+
+        let values = evaluate_wires(b, &[sum_bits, carry_bits].concat())?;
+        let (sum, carry) = values.split_at(8);
+        assert_eq!(to_number(sum) + 2 * to_number(carry), a + c + d);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_create_schoolbook_multiplier() -> Result<()> {
+    use crate::producers::sink::MemorySink;
+    use crate::structs::types::Type;
+
+    let bits = |b: &mut GateBuilder<MemorySink>, value: u16| {
+        create_private_inputs(
+            b,
+            &(0..4).map(|i| ((value >> i) & 1) as u8).collect::<Vec<_>>(),
+        )
+    };
+
+    for (left, right) in [(3u16, 7u16), (15, 15), (0, 9), (13, 11), (1, 1)] {
+        let mut b = GateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![2])], &[]);
+        let left_bits = bits(&mut b, left)?;
+        let right_bits = bits(&mut b, right)?;
+        let product_bits = b.create_schoolbook_multiplier(0, &left_bits, &right_bits)?;
+        assert_eq!(product_bits.len(), 8);
+
+        let values = evaluate_wires(b, &product_bits)?;
+        let expected = (0..8)
+            .map(|i| BigUint::from(((left * right) >> i) & 1))
+            .collect::<Vec<_>>();
+        assert_eq!(values, expected);
+    }
+
+    let mut b = GateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![2])], &[]);
+    let left_bits = bits(&mut b, 3)?;
+    assert!(b
+        .create_schoolbook_multiplier(0, &left_bits, &left_bits[1..])
+        .is_err());
+    assert!(b.create_schoolbook_multiplier(0, &[], &[])?.is_empty());
+    Ok(())
+}
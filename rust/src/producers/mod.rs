@@ -10,6 +10,18 @@ pub mod sink;
 pub mod build_gates;
 /// r1cs to ir converter
 pub mod from_r1cs;
+/// flat ir to in-memory r1cs exporter
+pub mod to_r1cs;
 
 /// gates builder and interface
 pub mod builder;
+/// gadgets built on top of the GateBuilder.
+/// Gadgets creating private inputs from the values of some wires (e.g. a bit decomposition)
+/// take these values as an `Option`: `Some` for the prover, and `None` for the verifier.
+pub mod gadgets;
+/// scopes which automatically delete their temporary wires
+pub mod scope;
+/// a variant of the GateBuilder interface working with typed wires
+pub mod typed_builder;
+/// a GateBuilder which validates each gate before emitting it
+pub mod validating_builder;
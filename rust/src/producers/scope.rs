@@ -0,0 +1,199 @@
+use std::collections::BTreeSet;
+
+use crate::producers::builder::{BuildComplexGate, BuildGate, GateBuilder, GateBuilderT};
+use crate::structs::count::Count;
+use crate::structs::function::FunctionCounts;
+use crate::structs::value::Value;
+use crate::structs::wirerange::{add_types_to_wire_ranges, WireRange};
+use crate::{Result, Sink, TypeId, WireId};
+
+/// ScopeGuard wraps a GateBuilder and records all wires allocated through it.
+/// It is created by `GateBuilder::with_scope`, which emits Delete gates
+/// for all recorded wires which are not outputs of the scope.
+pub struct ScopeGuard<'a, S: Sink> {
+    builder: &'a mut GateBuilder<S>,
+    allocated: BTreeSet<(TypeId, WireId)>,
+}
+
+impl<S: Sink> GateBuilderT for ScopeGuard<'_, S> {
+    fn create_gate(&mut self, gate: BuildGate) -> Result<WireId> {
+        let type_id = gate.get_type_id();
+        let has_output = gate.has_output();
+        let out_id = self.builder.create_gate(gate)?;
+        if has_output {
+            self.allocated.insert((type_id, out_id));
+        }
+        Ok(out_id)
+    }
+
+    fn create_complex_gate(
+        &mut self,
+        gate: BuildComplexGate,
+        public_inputs: Vec<Vec<Value>>,
+        private_inputs: Vec<Vec<Value>>,
+    ) -> Result<Vec<WireRange>> {
+        let output_count = match gate {
+            BuildComplexGate::Call(ref name, _) => {
+                FunctionCounts::get_function_counts(self.builder.known_functions(), name)?
+                    .output_count
+            }
+            BuildComplexGate::Convert(out_type_id, out_wire_count, _, _, _) => {
+                vec![Count::new(out_type_id, out_wire_count)]
+            }
+        };
+        let out_ids = self
+            .builder
+            .create_complex_gate(gate, public_inputs, private_inputs)?;
+        for range in add_types_to_wire_ranges(&out_ids, &output_count)? {
+            for wire_id in range.first_id..=range.last_id {
+                self.allocated.insert((range.type_id, wire_id));
+            }
+        }
+        Ok(out_ids)
+    }
+}
+
+impl<S: Sink> GateBuilder<S> {
+    /// with_scope runs `f` with a ScopeGuard wrapping this builder.
+    /// `f` returns the output wires of the scope, with their types.
+    /// Once `f` returns, a Delete gate is emitted for each range of contiguous wires
+    /// allocated inside the scope and not belonging to the outputs.
+    pub fn with_scope<F>(&mut self, f: F) -> Result<Vec<(TypeId, WireId)>>
+    where
+        F: FnOnce(&mut ScopeGuard<S>) -> Result<Vec<(TypeId, WireId)>>,
+    {
+        let mut guard = ScopeGuard {
+            builder: self,
+            allocated: BTreeSet::new(),
+        };
+        let outputs = f(&mut guard)?;
+        let output_set = outputs.iter().cloned().collect::<BTreeSet<_>>();
+
+        // Compute ranges of contiguous wires to delete (allocated is sorted by (type_id, wire_id))
+        let mut to_delete: Vec<(TypeId, WireId, WireId)> = vec![];
+        for (type_id, wire_id) in guard.allocated.iter() {
+            if output_set.contains(&(*type_id, *wire_id)) {
+                continue;
+            }
+            match to_delete.last_mut() {
+                Some((last_type_id, _, last_id))
+                    if *last_type_id == *type_id && *last_id + 1 == *wire_id =>
+                {
+                    *last_id = *wire_id;
+                }
+                _ => to_delete.push((*type_id, *wire_id, *wire_id)),
+            }
+        }
+
+        for (type_id, first_id, last_id) in to_delete {
+            guard
+                .builder
+                .create_gate(BuildGate::Delete(type_id, first_id, last_id))?;
+        }
+        Ok(outputs)
+    }
+}
+
+#[test]
+fn test_with_scope() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::consumers::source::Source;
+    use crate::producers::builder::BuildGate::*;
+    use crate::producers::sink::MemorySink;
+    use crate::structs::types::Type;
+    use crate::Gate;
+
+    let mut b = GateBuilder::new(
+        MemorySink::default(),
+        &[],
+        &[Type::new_field_type(vec![101])],
+        &[],
+    );
+
+    // The scope allocates four wires (0, 1, 2, 3) and returns two of them (1 and 3).
+    let outputs = b.with_scope(|scope| {
+        let x = scope.create_gate(Private(0, Some(vec![3])))?;
+        let y = scope.create_gate(Private(0, Some(vec![4])))?;
+        let xy = scope.create_gate(Mul(0, x, y))?;
+        let out = scope.create_gate(Add(0, xy, y))?;
+        Ok(vec![(0, y), (0, out)])
+    })?;
+    assert_eq!(outputs, vec![(0, 1), (0, 3)]);
+
+    let neg_16 = b.create_gate(Constant(0, vec![85]))?;
+    let diff = b.create_gate(Add(0, outputs[1].1, neg_16))?;
+    b.create_gate(AssertZero(0, diff))?;
+
+    let source: Source = b.finish().into();
+    let messages = source.read_all_messages()?;
+    let deletes = messages.relations[0]
+        .directives
+        .iter()
+        .filter(|directive| {
+            matches!(
+                directive,
+                crate::structs::directives::Directive::Gate(Gate::Delete(_, _, _))
+            )
+        })
+        .count();
+    assert_eq!(deletes, 2);
+
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(evaluator.get_violations(), Vec::<String>::new());
+    Ok(())
+}
+
+#[test]
+fn test_with_scope_two_types() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::consumers::source::Source;
+    use crate::producers::builder::BuildGate::*;
+    use crate::producers::sink::MemorySink;
+    use crate::structs::directives::Directive;
+    use crate::structs::types::Type;
+    use crate::Gate;
+
+    let mut b = GateBuilder::new(
+        MemorySink::default(),
+        &[],
+        &[
+            Type::new_field_type(vec![101]),
+            Type::new_field_type(vec![7]),
+        ],
+        &[],
+    );
+
+    // Both types use the wire ids 0 and 1. Only the wire 1 of type 0 is an output.
+    let outputs = b.with_scope(|scope| {
+        let x0 = scope.create_gate(Private(0, Some(vec![3])))?;
+        let x1 = scope.create_gate(Private(1, Some(vec![3])))?;
+        let y0 = scope.create_gate(Mul(0, x0, x0))?;
+        let y1 = scope.create_gate(Mul(1, x1, x1))?;
+        assert_eq!((x0, y0), (x1, y1));
+        Ok(vec![(0, y0)])
+    })?;
+    assert_eq!(outputs, vec![(0, 1)]);
+
+    let diff = b.create_gate(AddConstant(0, outputs[0].1, vec![92]))?;
+    b.create_gate(AssertZero(0, diff))?;
+
+    let source: Source = b.finish().into();
+    let messages = source.read_all_messages()?;
+    let deletes = messages.relations[0]
+        .directives
+        .iter()
+        .filter_map(|directive| match directive {
+            Directive::Gate(Gate::Delete(type_id, first_id, last_id)) => {
+                Some((*type_id, *first_id, *last_id))
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(deletes, vec![(0, 0, 0), (1, 0, 1)]);
+
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(evaluator.get_violations(), Vec::<String>::new());
+    Ok(())
+}
@@ -1,6 +1,10 @@
+use crate::structs::conversion::Conversion;
+use crate::structs::directives::Directive;
 use crate::structs::types::Type;
+use crate::structs::IR_VERSION;
 use crate::{consumers::source::has_sieve_extension, Source};
-use crate::{PrivateInputs, PublicInputs, Relation, Result, FILE_EXTENSION};
+use crate::{Gate, PrivateInputs, PublicInputs, Relation, Result, FILE_EXTENSION};
+use std::cmp::max;
 use std::collections::BTreeMap;
 use std::fs::{create_dir_all, read_dir, remove_file, File};
 use std::io::Write;
@@ -48,8 +52,8 @@ impl Sink for MemorySink {
     }
 }
 
-impl From<MemorySink> for Source {
-    fn from(mem: MemorySink) -> Source {
+impl From<MemorySink> for Source<'static> {
+    fn from(mem: MemorySink) -> Source<'static> {
         Source::from_buffers(vec![
             mem.public_inputs_buffer,
             mem.private_inputs_buffer,
@@ -165,12 +169,89 @@ impl Sink for FilesSink {
     }
 }
 
-impl From<FilesSink> for Source {
-    fn from(files_sink: FilesSink) -> Source {
+impl From<FilesSink> for Source<'static> {
+    fn from(files_sink: FilesSink) -> Source<'static> {
         Source::from_directory(&files_sink.workspace).unwrap()
     }
 }
 
+/// StreamingRelationWriter writes a relation incrementally into `writer`, as a sequence of
+/// Relation messages containing at most `directives_per_message` directives each.
+/// Only the directives of the current message are held in memory,
+/// so gates may be produced by an iterator without ever collecting them.
+/// Plugins, types and conversions are written in the first message only.
+/// finish() must be called.
+pub struct StreamingRelationWriter<W: Write> {
+    writer: W,
+    relation: Relation,
+    directives_per_message: usize,
+    flushed_messages: usize,
+}
+
+impl<W: Write> StreamingRelationWriter<W> {
+    pub fn new(
+        writer: W,
+        plugins: &[String],
+        types: &[Type],
+        conversions: &[Conversion],
+        directives_per_message: usize,
+    ) -> Self {
+        StreamingRelationWriter {
+            writer,
+            relation: Relation {
+                version: IR_VERSION.to_string(),
+                plugins: plugins.to_vec(),
+                types: types.to_vec(),
+                conversions: conversions.to_vec(),
+                directives: Vec::with_capacity(directives_per_message),
+            },
+            directives_per_message: max(directives_per_message, 1),
+            flushed_messages: 0,
+        }
+    }
+
+    pub fn push_directive(&mut self, directive: Directive) -> Result<()> {
+        self.relation.directives.push(directive);
+        if self.relation.directives.len() >= self.directives_per_message {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    pub fn push_gate(&mut self, gate: Gate) -> Result<()> {
+        self.push_directive(Directive::Gate(gate))
+    }
+
+    pub fn push_gates(&mut self, gates: impl IntoIterator<Item = Gate>) -> Result<()> {
+        gates.into_iter().try_for_each(|gate| self.push_gate(gate))
+    }
+
+    /// Returns the number of Relation messages written so far.
+    pub fn flushed_messages(&self) -> usize {
+        self.flushed_messages
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.relation.write_into(&mut self.writer)?;
+        self.flushed_messages += 1;
+        self.relation.plugins.clear();
+        self.relation.types.clear();
+        self.relation.conversions.clear();
+        self.relation.directives.clear();
+        Ok(())
+    }
+
+    /// Writes the remaining directives and returns the underlying writer.
+    /// A message is always written if none was, so that the types are declared.
+    pub fn finish(mut self) -> Result<W> {
+        if !self.relation.directives.is_empty() || self.flushed_messages == 0 {
+            self.flush()?;
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
 pub fn clean_workspace(workspace: impl AsRef<Path>) -> Result<()> {
     let workspace = workspace.as_ref();
 
@@ -295,3 +376,54 @@ fn test_sink() {
     clean_workspace(&workspace).unwrap();
     assert!(get_file_sizes().0.is_empty());
 }
+
+#[test]
+fn test_streaming_relation_writer() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    let types = [Type::Field(vec![101])];
+    let mut writer = StreamingRelationWriter::new(vec![], &[], &types, &[], 4);
+
+    // Prove that 1 + 2 + ... + 10 = 55 (mod 101), the gates are generated on the fly.
+    writer.push_gates((0..10).map(|i| Gate::Constant(0, i, vec![i as u8 + 1])))?;
+    writer.push_gates((10..19).map(|i| {
+        let left = if i == 10 { 0 } else { i - 1 };
+        Gate::Add(0, i, left, i - 9)
+    }))?;
+    writer.push_gate(Gate::AddConstant(0, 19, 18, vec![46]))?;
+    writer.push_gate(Gate::AssertZero(0, 19))?;
+    writer.push_gate(Gate::Delete(0, 0, 19))?;
+    assert_eq!(writer.flushed_messages(), 5);
+
+    let buffer = writer.finish()?;
+    let source = Source::from_buffers(vec![buffer]);
+    let messages = source.read_all_messages()?;
+    assert_eq!(messages.relations.len(), 6);
+    assert_eq!(messages.relations[0].types, types.to_vec());
+    assert!(messages.relations[1..]
+        .iter()
+        .all(|relation| relation.types.is_empty()));
+    let gates = messages
+        .relations
+        .iter()
+        .map(|relation| relation.directives.len())
+        .sum::<usize>();
+    assert_eq!(gates, 22);
+    assert_eq!(
+        messages.relations[5].directives,
+        vec![
+            Directive::Gate(Gate::AssertZero(0, 19)),
+            Directive::Gate(Gate::Delete(0, 0, 19))
+        ]
+    );
+
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(evaluator.get_violations(), Vec::<String>::new());
+
+    // An empty relation still declares its types
+    let buffer = StreamingRelationWriter::new(vec![], &[], &types, &[], 4).finish()?;
+    let messages = Source::from_buffers(vec![buffer]).read_all_messages()?;
+    assert_eq!(messages.relations.len(), 1);
+    assert_eq!(messages.relations[0].types, types.to_vec());
+    Ok(())
+}
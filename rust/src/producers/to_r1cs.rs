@@ -0,0 +1,318 @@
+use num_bigint::BigUint;
+use num_traits::Zero;
+use std::collections::BTreeMap;
+
+use crate::structs::directives::Directive;
+use crate::structs::types::Type;
+use crate::Gate::*;
+use crate::{PrivateInputs, PublicInputs, Relation, Result, Value, WireId};
+
+/// A linear combination is a list of (variable, coefficient).
+/// The variable 0 always holds the constant 1.
+pub type R1CSLinearCombination = Vec<(u64, Value)>;
+
+/// R1CSConstraint represents the constraint `<a, w> * <b, w> = <c, w>`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct R1CSConstraint {
+    pub a: R1CSLinearCombination,
+    pub b: R1CSLinearCombination,
+    pub c: R1CSLinearCombination,
+}
+
+/// R1CSInstance is the output of `to_r1cs`.
+/// `assignment[i]` is the value of the R1CS variable `i` (with `assignment[0] = 1`).
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct R1CSInstance {
+    pub constraints: Vec<R1CSConstraint>,
+    pub public_wire_count: usize,
+    pub private_wire_count: usize,
+    pub modulus: Value,
+    pub assignment: Vec<Value>,
+}
+
+impl R1CSInstance {
+    /// Check that `assignment` satisfies all constraints modulo `modulus`.
+    pub fn is_satisfied(&self) -> bool {
+        let modulus = BigUint::from_bytes_le(&self.modulus);
+        let assignment = self
+            .assignment
+            .iter()
+            .map(|val| BigUint::from_bytes_le(val))
+            .collect::<Vec<_>>();
+        let eval = |lc: &R1CSLinearCombination| -> Option<BigUint> {
+            let mut sum = BigUint::zero();
+            for (var, coeff) in lc.iter() {
+                let val = assignment.get(*var as usize)?;
+                sum += val * BigUint::from_bytes_le(coeff);
+            }
+            Some(sum % &modulus)
+        };
+        self.constraints.iter().all(|constraint| {
+            match (
+                eval(&constraint.a),
+                eval(&constraint.b),
+                eval(&constraint.c),
+            ) {
+                (Some(a), Some(b), Some(c)) => (a * b) % &modulus == c,
+                _ => false,
+            }
+        })
+    }
+}
+
+/// R1CSExporter keeps track of the mapping between IR wires and R1CS variables.
+struct R1CSExporter {
+    instance: R1CSInstance,
+    wire_to_var: BTreeMap<WireId, u64>,
+    modulus: BigUint,
+}
+
+impl R1CSExporter {
+    fn new(modulus: Value) -> Result<Self> {
+        let modulus_int = BigUint::from_bytes_le(&modulus);
+        if modulus_int.is_zero() {
+            return Err("to_r1cs: the modulus of the Field type is zero.".into());
+        }
+        Ok(R1CSExporter {
+            modulus: modulus_int,
+            instance: R1CSInstance {
+                modulus,
+                assignment: vec![vec![1]],
+                ..R1CSInstance::default()
+            },
+            wire_to_var: BTreeMap::new(),
+        })
+    }
+
+    /// Return the R1CS variable associated to the IR wire `wire`.
+    fn get_var(&self, wire: &WireId) -> Result<u64> {
+        self.wire_to_var
+            .get(wire)
+            .cloned()
+            .ok_or_else(|| format!("Wire {} is not defined.", wire).into())
+    }
+
+    fn get_val(&self, var: u64) -> BigUint {
+        BigUint::from_bytes_le(&self.instance.assignment[var as usize])
+    }
+
+    /// Allocate a new R1CS variable for the IR wire `wire` and assign it to `val`.
+    fn alloc(&mut self, wire: WireId, val: BigUint) -> u64 {
+        let var = self.instance.assignment.len() as u64;
+        self.instance
+            .assignment
+            .push((val % &self.modulus).to_bytes_le());
+        self.wire_to_var.insert(wire, var);
+        var
+    }
+
+    fn push_constraint(&mut self, constraint: R1CSConstraint) {
+        self.instance.constraints.push(constraint);
+    }
+}
+
+fn one_lc() -> R1CSLinearCombination {
+    vec![(0, vec![1])]
+}
+
+/// Convert a flat SIEVE IR relation (no function calls, no conversions) to an R1CS instance.
+/// The relation must contain exactly one type, which must be a Field.
+/// Each IR wire is mapped to a fresh R1CS variable, and each gate produces one constraint.
+pub fn to_r1cs(
+    relation: &Relation,
+    public_inputs: &PublicInputs,
+    private_inputs: &PrivateInputs,
+) -> Result<R1CSInstance> {
+    if relation.types.len() != 1 {
+        return Err("to_r1cs only supports relations with exactly one type.".into());
+    }
+    let modulus = match &relation.types[0] {
+        Type::Field(modulo) => modulo.clone(),
+        Type::PluginType(_, _, _) => {
+            return Err("to_r1cs only supports Field types.".into());
+        }
+    };
+    if public_inputs.type_value != relation.types[0]
+        || private_inputs.type_value != relation.types[0]
+    {
+        return Err("to_r1cs: inputs type does not match the relation type.".into());
+    }
+
+    let mut public_inputs = public_inputs.inputs.iter();
+    let mut private_inputs = private_inputs.inputs.iter();
+    let mut exporter = R1CSExporter::new(modulus)?;
+
+    for directive in relation.directives.iter() {
+        let gate = match directive {
+            Directive::Gate(gate) => gate,
+            Directive::Function(_) => {
+                return Err("to_r1cs only supports flat circuits (no function).".into());
+            }
+        };
+        match gate {
+            Constant(_, out, value) => {
+                let out_var = exporter.alloc(*out, BigUint::from_bytes_le(value));
+                exporter.push_constraint(R1CSConstraint {
+                    a: vec![(0, value.clone())],
+                    b: one_lc(),
+                    c: vec![(out_var, vec![1])],
+                });
+            }
+
+            AssertZero(_, inp) => {
+                let in_var = exporter.get_var(inp)?;
+                exporter.push_constraint(R1CSConstraint {
+                    a: vec![(in_var, vec![1])],
+                    b: one_lc(),
+                    c: vec![],
+                });
+            }
+
+            Copy(_, out, inp) => {
+                let in_var = exporter.get_var(inp)?;
+                let val = exporter.get_val(in_var);
+                let out_var = exporter.alloc(*out, val);
+                exporter.push_constraint(R1CSConstraint {
+                    a: vec![(in_var, vec![1])],
+                    b: one_lc(),
+                    c: vec![(out_var, vec![1])],
+                });
+            }
+
+            Add(_, out, left, right) => {
+                let left_var = exporter.get_var(left)?;
+                let right_var = exporter.get_var(right)?;
+                let val = exporter.get_val(left_var) + exporter.get_val(right_var);
+                let out_var = exporter.alloc(*out, val);
+                exporter.push_constraint(R1CSConstraint {
+                    a: vec![(left_var, vec![1]), (right_var, vec![1])],
+                    b: one_lc(),
+                    c: vec![(out_var, vec![1])],
+                });
+            }
+
+            Mul(_, out, left, right) => {
+                let left_var = exporter.get_var(left)?;
+                let right_var = exporter.get_var(right)?;
+                let val = exporter.get_val(left_var) * exporter.get_val(right_var);
+                let out_var = exporter.alloc(*out, val);
+                exporter.push_constraint(R1CSConstraint {
+                    a: vec![(left_var, vec![1])],
+                    b: vec![(right_var, vec![1])],
+                    c: vec![(out_var, vec![1])],
+                });
+            }
+
+            AddConstant(_, out, inp, constant) => {
+                let in_var = exporter.get_var(inp)?;
+                let val = exporter.get_val(in_var) + BigUint::from_bytes_le(constant);
+                let out_var = exporter.alloc(*out, val);
+                exporter.push_constraint(R1CSConstraint {
+                    a: vec![(in_var, vec![1]), (0, constant.clone())],
+                    b: one_lc(),
+                    c: vec![(out_var, vec![1])],
+                });
+            }
+
+            MulConstant(_, out, inp, constant) => {
+                let in_var = exporter.get_var(inp)?;
+                let val = exporter.get_val(in_var) * BigUint::from_bytes_le(constant);
+                let out_var = exporter.alloc(*out, val);
+                exporter.push_constraint(R1CSConstraint {
+                    a: vec![(in_var, constant.clone())],
+                    b: one_lc(),
+                    c: vec![(out_var, vec![1])],
+                });
+            }
+
+            Public(_, out) => {
+                let val = public_inputs
+                    .next()
+                    .ok_or("Not enough public inputs to consume.")?;
+                exporter.alloc(*out, BigUint::from_bytes_le(val));
+                exporter.instance.public_wire_count += 1;
+            }
+
+            Private(_, out) => {
+                let val = private_inputs
+                    .next()
+                    .ok_or("Not enough private inputs to consume.")?;
+                exporter.alloc(*out, BigUint::from_bytes_le(val));
+                exporter.instance.private_wire_count += 1;
+            }
+
+            // Memory management gates have no impact on the R1CS instance.
+            New(_, _, _) | Delete(_, _, _) => {}
+
+            Convert(_, _, _, _, _, _) => {
+                return Err("to_r1cs does not support Convert gates.".into());
+            }
+
+            Call(name, _, _) => {
+                return Err(format!(
+                    "to_r1cs only supports flat circuits (call to function {}).",
+                    name
+                )
+                .into());
+            }
+        }
+    }
+
+    Ok(exporter.instance)
+}
+
+#[test]
+fn test_to_r1cs_round_trip() -> Result<()> {
+    use crate::producers::builder::{BuildGate::*, GateBuilder, GateBuilderT};
+    use crate::producers::sink::MemorySink;
+    use crate::Source;
+
+    let field = Type::Field(vec![101]);
+    let mut b = GateBuilder::new(MemorySink::default(), &[], &[field], &[]);
+
+    // Prove that we know x, y such that x * y + 3 = pub_0 and x + y = pub_1.
+    let x = b.create_gate(Private(0, Some(vec![5])))?;
+    let y = b.create_gate(Private(0, Some(vec![7])))?;
+    let pub_0 = b.create_gate(Public(0, Some(vec![38])))?;
+    let pub_1 = b.create_gate(Public(0, Some(vec![12])))?;
+    let xy = b.create_gate(Mul(0, x, y))?;
+    let xy_3 = b.create_gate(AddConstant(0, xy, vec![3]))?;
+    let minus_pub_0 = b.create_gate(MulConstant(0, pub_0, vec![100]))?;
+    let diff_0 = b.create_gate(Add(0, xy_3, minus_pub_0))?;
+    b.create_gate(AssertZero(0, diff_0))?;
+    let x_plus_y = b.create_gate(Add(0, x, y))?;
+    let minus_pub_1 = b.create_gate(MulConstant(0, pub_1, vec![100]))?;
+    let diff_1 = b.create_gate(Add(0, x_plus_y, minus_pub_1))?;
+    b.create_gate(AssertZero(0, diff_1))?;
+
+    let source: Source = b.finish().into();
+    let messages = source.read_all_messages()?;
+    let instance = to_r1cs(
+        &messages.relations[0],
+        &messages.public_inputs[0],
+        &messages.private_inputs[0],
+    )?;
+
+    assert_eq!(instance.public_wire_count, 2);
+    assert_eq!(instance.private_wire_count, 2);
+    // One constraint per gate, except for Public/Private gates.
+    assert_eq!(instance.constraints.len(), 9);
+    assert!(instance.is_satisfied());
+
+    // A wrong assignment must not satisfy the constraints.
+    let mut wrong_instance = instance;
+    wrong_instance.assignment[1] = vec![6];
+    assert!(!wrong_instance.is_satisfied());
+
+    // A zero modulus is rejected instead of panicking on the first reduction.
+    let zero = Type::Field(vec![0]);
+    let mut relation = messages.relations[0].clone();
+    relation.types = vec![zero.clone()];
+    let mut public_inputs = messages.public_inputs[0].clone();
+    public_inputs.type_value = zero.clone();
+    let mut private_inputs = messages.private_inputs[0].clone();
+    private_inputs.type_value = zero;
+    assert!(to_r1cs(&relation, &public_inputs, &private_inputs).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,159 @@
+use crate::producers::builder::{BuildComplexGate, BuildGate, GateBuilderT};
+use crate::structs::value::Value;
+use crate::{Result, TypeId, WireId};
+
+/// TypedWireId is a WireId together with the TypeId of the wire.
+/// Gates created through a `TypedGateBuilderT` take their type from their input wires,
+/// so a wire cannot be used with another type by mistake.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct TypedWireId {
+    pub type_id: TypeId,
+    pub wire_id: WireId,
+}
+
+impl TypedWireId {
+    pub fn new(type_id: TypeId, wire_id: WireId) -> Self {
+        TypedWireId { type_id, wire_id }
+    }
+}
+
+/// Returns the common type of `left` and `right`, or an error if they have different types.
+fn common_type_id(left: TypedWireId, right: TypedWireId, gate_name: &str) -> Result<TypeId> {
+    if left.type_id != right.type_id {
+        return Err(format!(
+            "{}: wire {} of type {} and wire {} of type {} have different types",
+            gate_name, left.wire_id, left.type_id, right.wire_id, right.type_id
+        )
+        .into());
+    }
+    Ok(left.type_id)
+}
+
+/// TypedGateBuilderT is a variant of GateBuilderT working with TypedWireId.
+/// It is implemented for all GateBuilderT.
+pub trait TypedGateBuilderT: GateBuilderT {
+    /// Creates a Constant gate of type `type_id`.
+    fn typed_constant(&mut self, type_id: TypeId, value: Value) -> Result<TypedWireId> {
+        let wire_id = self.create_gate(BuildGate::Constant(type_id, value))?;
+        Ok(TypedWireId::new(type_id, wire_id))
+    }
+
+    fn typed_assert_zero(&mut self, wire: TypedWireId) -> Result<()> {
+        self.create_gate(BuildGate::AssertZero(wire.type_id, wire.wire_id))?;
+        Ok(())
+    }
+
+    fn typed_copy(&mut self, wire: TypedWireId) -> Result<TypedWireId> {
+        let wire_id = self.create_gate(BuildGate::Copy(wire.type_id, wire.wire_id))?;
+        Ok(TypedWireId::new(wire.type_id, wire_id))
+    }
+
+    /// Returns an error if `left` and `right` have different types.
+    fn typed_add(&mut self, left: TypedWireId, right: TypedWireId) -> Result<TypedWireId> {
+        let type_id = common_type_id(left, right, "Add")?;
+        let wire_id = self.create_gate(BuildGate::Add(type_id, left.wire_id, right.wire_id))?;
+        Ok(TypedWireId::new(type_id, wire_id))
+    }
+
+    /// Returns an error if `left` and `right` have different types.
+    fn typed_mul(&mut self, left: TypedWireId, right: TypedWireId) -> Result<TypedWireId> {
+        let type_id = common_type_id(left, right, "Mul")?;
+        let wire_id = self.create_gate(BuildGate::Mul(type_id, left.wire_id, right.wire_id))?;
+        Ok(TypedWireId::new(type_id, wire_id))
+    }
+
+    fn typed_add_constant(&mut self, wire: TypedWireId, value: Value) -> Result<TypedWireId> {
+        let wire_id =
+            self.create_gate(BuildGate::AddConstant(wire.type_id, wire.wire_id, value))?;
+        Ok(TypedWireId::new(wire.type_id, wire_id))
+    }
+
+    fn typed_mul_constant(&mut self, wire: TypedWireId, value: Value) -> Result<TypedWireId> {
+        let wire_id =
+            self.create_gate(BuildGate::MulConstant(wire.type_id, wire.wire_id, value))?;
+        Ok(TypedWireId::new(wire.type_id, wire_id))
+    }
+
+    fn typed_public(&mut self, type_id: TypeId, value: Option<Value>) -> Result<TypedWireId> {
+        let wire_id = self.create_gate(BuildGate::Public(type_id, value))?;
+        Ok(TypedWireId::new(type_id, wire_id))
+    }
+
+    fn typed_private(&mut self, type_id: TypeId, value: Option<Value>) -> Result<TypedWireId> {
+        let wire_id = self.create_gate(BuildGate::Private(type_id, value))?;
+        Ok(TypedWireId::new(type_id, wire_id))
+    }
+
+    /// Converts the contiguous wires `first..=last` into `out_wire_count` wires of type
+    /// `out_type_id`.
+    /// Returns an error if `first` and `last` have different types.
+    fn typed_convert(
+        &mut self,
+        out_type_id: TypeId,
+        out_wire_count: u64,
+        first: TypedWireId,
+        last: TypedWireId,
+    ) -> Result<Vec<TypedWireId>> {
+        let in_type_id = common_type_id(first, last, "Convert")?;
+        let out_ranges = self.create_complex_gate(
+            BuildComplexGate::Convert(
+                out_type_id,
+                out_wire_count,
+                in_type_id,
+                first.wire_id,
+                last.wire_id,
+            ),
+            vec![],
+            vec![],
+        )?;
+        Ok(out_ranges
+            .iter()
+            .flat_map(|range| range.first_id..=range.last_id)
+            .map(|wire_id| TypedWireId::new(out_type_id, wire_id))
+            .collect())
+    }
+}
+
+impl<B: GateBuilderT> TypedGateBuilderT for B {}
+
+#[test]
+fn test_typed_gate_builder() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::consumers::source::Source;
+    use crate::producers::builder::GateBuilder;
+    use crate::producers::sink::MemorySink;
+    use crate::structs::conversion::Conversion;
+    use crate::structs::count::Count;
+    use crate::structs::types::Type;
+    use num_bigint::BigUint;
+
+    let mut b = GateBuilder::new(
+        MemorySink::default(),
+        &[],
+        &[Type::Field(vec![101]), Type::Field(vec![7])],
+        &[Conversion::new(Count::new(1, 1), Count::new(0, 1))],
+    );
+
+    // Prove that we know x such that (x * x + 1) mod 101 mod 7 = 3
+    let x = b.typed_private(0, Some(vec![3]))?;
+    let xx = b.typed_mul(x, x)?;
+    let xx_1 = b.typed_add_constant(xx, vec![1])?;
+    let converted = b.typed_convert(1, 1, xx_1, xx_1)?;
+    assert_eq!(converted.len(), 1);
+    assert_eq!(converted[0].type_id, 1);
+    let minus_3 = b.typed_constant(1, vec![4])?;
+    let diff = b.typed_add(converted[0], minus_3)?;
+    b.typed_assert_zero(diff)?;
+
+    // Wires of different types cannot be combined
+    assert!(b.typed_add(x, minus_3).is_err());
+    assert!(b.typed_mul(converted[0], xx).is_err());
+    assert!(b.typed_convert(0, 1, x, converted[0]).is_err());
+
+    let source: Source = b.finish().into();
+    let evaluator =
+        Evaluator::from_messages(source.iter_messages(), &mut PlaintextBackend::default());
+    assert_eq!(evaluator.get_violations(), Vec::<String>::new());
+    assert_eq!(*evaluator.get(1, diff.wire_id)?, BigUint::from(0u8));
+    Ok(())
+}
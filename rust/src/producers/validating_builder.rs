@@ -0,0 +1,243 @@
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+
+use crate::consumers::validator::Validator;
+use crate::producers::build_gates::NO_OUTPUT;
+use crate::producers::builder::{
+    BuildComplexGate, BuildGate, FunctionWithInfos, GateBuilder, GateBuilderT,
+};
+use crate::structs::conversion::Conversion;
+use crate::structs::count::Count;
+use crate::structs::directives::Directive;
+use crate::structs::function::FunctionCounts;
+use crate::structs::types::Type;
+use crate::structs::value::Value;
+use crate::structs::wirerange::WireRange;
+use crate::structs::IR_VERSION;
+use crate::{Gate, PrivateInputs, PublicInputs, Relation, Result, Sink, TypeId, WireId};
+
+/// ValidatingGateBuilder wraps a GateBuilder and checks each gate with a Validator
+/// before passing it to the inner builder. A gate raising some violations is not emitted,
+/// and the violations are returned as an error. The builder can then be used as if the
+/// rejected gate had never been created.
+/// The validator runs in prover mode, so the values of all public and private inputs
+/// must be provided.
+pub struct ValidatingGateBuilder<S: Sink> {
+    builder: GateBuilder<S>,
+    validator: Validator,
+    types: Vec<Type>,
+    // violations raised by the rejected gates
+    rejected_violations: Vec<String>,
+}
+
+impl<S: Sink> ValidatingGateBuilder<S> {
+    /// new creates a new builder (see `GateBuilder::new`).
+    pub fn new(sink: S, plugins: &[String], types: &[Type], conversions: &[Conversion]) -> Self {
+        let mut validator = Validator::new_as_prover();
+        validator.ingest_relation(&Relation {
+            version: IR_VERSION.to_string(),
+            plugins: plugins.to_vec(),
+            types: types.to_vec(),
+            conversions: conversions.to_vec(),
+            directives: vec![],
+        });
+        ValidatingGateBuilder {
+            builder: GateBuilder::new(sink, plugins, types, conversions),
+            validator,
+            types: types.to_vec(),
+            rejected_violations: vec![],
+        }
+    }
+
+    /// Returns the violations detected so far.
+    pub fn violations(&self) -> Vec<String> {
+        let mut violations = self.validator.current_violations().to_vec();
+        violations.extend_from_slice(&self.rejected_violations);
+        violations
+    }
+
+    /// Validates and pushes a function (see `GateBuilder::push_function`).
+    pub fn push_function(&mut self, function_with_infos: FunctionWithInfos) -> Result<()> {
+        let directive = Directive::Function(function_with_infos.function().clone());
+        self.check(|validator| {
+            validator.ingest_relation(&Relation {
+                version: IR_VERSION.to_string(),
+                plugins: vec![],
+                types: vec![],
+                conversions: vec![],
+                directives: vec![directive],
+            })
+        })?;
+        self.builder.push_function(function_with_infos)
+    }
+
+    pub fn finish(self) -> S {
+        self.builder.finish()
+    }
+
+    /// Runs `f` on a copy of the validator, and returns the new violations as an error.
+    /// The state of the validator is only updated if no violation was raised.
+    fn check(&mut self, f: impl FnOnce(&mut Validator)) -> Result<()> {
+        let mut validator = self.validator.clone();
+        let violations_count = validator.how_many_violations();
+        f(&mut validator);
+        let violations = &validator.current_violations()[violations_count..];
+        if violations.is_empty() {
+            self.validator = validator;
+            Ok(())
+        } else {
+            self.rejected_violations.extend_from_slice(violations);
+            Err(violations.join("\n").into())
+        }
+    }
+
+    fn type_value(&self, type_id: TypeId) -> Result<Type> {
+        self.types
+            .get(usize::try_from(type_id)?)
+            .cloned()
+            .ok_or_else(|| format!("Type id {} is not defined.", type_id).into())
+    }
+
+    fn public_message(&self, type_id: TypeId, values: Vec<Value>) -> Result<PublicInputs> {
+        Ok(PublicInputs {
+            version: IR_VERSION.to_string(),
+            type_value: self.type_value(type_id)?,
+            inputs: values,
+        })
+    }
+
+    fn private_message(&self, type_id: TypeId, values: Vec<Value>) -> Result<PrivateInputs> {
+        Ok(PrivateInputs {
+            version: IR_VERSION.to_string(),
+            type_value: self.type_value(type_id)?,
+            inputs: values,
+        })
+    }
+
+    /// Checks `gate` (consuming the values of `public_messages` and `private_messages`).
+    fn check_gate(
+        &mut self,
+        gate: &Gate,
+        public_messages: Vec<PublicInputs>,
+        private_messages: Vec<PrivateInputs>,
+    ) -> Result<()> {
+        self.check(|validator| {
+            public_messages
+                .iter()
+                .for_each(|msg| validator.ingest_public_inputs(msg));
+            private_messages
+                .iter()
+                .for_each(|msg| validator.ingest_private_inputs(msg));
+            validator.ingest_gate(gate);
+        })
+    }
+
+    /// Returns the output wires the inner builder will allocate for `gate`.
+    fn predict_complex_outputs(&self, gate: &BuildComplexGate) -> Result<Vec<WireRange>> {
+        let output_count = match gate {
+            BuildComplexGate::Call(name, _) => {
+                FunctionCounts::get_function_counts(self.builder.known_functions(), name)?
+                    .output_count
+            }
+            BuildComplexGate::Convert(out_type_id, out_wire_count, _, _, _) => {
+                vec![Count::new(*out_type_id, *out_wire_count)]
+            }
+        };
+        let mut next_ids: BTreeMap<TypeId, WireId> = self.builder.next_available_ids().clone();
+        Ok(output_count
+            .iter()
+            .map(|count| {
+                let first_id = next_ids.entry(count.type_id).or_insert(0);
+                let range = WireRange::new(*first_id, *first_id + count.count - 1);
+                *first_id += count.count;
+                range
+            })
+            .collect())
+    }
+}
+
+impl<S: Sink> GateBuilderT for ValidatingGateBuilder<S> {
+    fn create_gate(&mut self, gate: BuildGate) -> Result<WireId> {
+        let type_id = gate.get_type_id();
+        let (public_messages, private_messages) = match &gate {
+            BuildGate::Public(_, Some(value)) => (
+                vec![self.public_message(type_id, vec![value.clone()])?],
+                vec![],
+            ),
+            BuildGate::Private(_, Some(value)) => (
+                vec![],
+                vec![self.private_message(type_id, vec![value.clone()])?],
+            ),
+            _ => (vec![], vec![]),
+        };
+        let out_id = if gate.has_output() {
+            self.builder
+                .next_available_ids()
+                .get(&type_id)
+                .cloned()
+                .unwrap_or(0)
+        } else {
+            NO_OUTPUT
+        };
+        let checked_gate = gate.clone().with_output(out_id);
+        self.check_gate(&checked_gate, public_messages, private_messages)?;
+        self.builder.create_gate(gate)
+    }
+
+    fn create_complex_gate(
+        &mut self,
+        gate: BuildComplexGate,
+        public_inputs: Vec<Vec<Value>>,
+        private_inputs: Vec<Vec<Value>>,
+    ) -> Result<Vec<WireRange>> {
+        let mut public_messages = vec![];
+        for (type_id, values) in public_inputs.iter().enumerate() {
+            if !values.is_empty() {
+                public_messages.push(self.public_message(u8::try_from(type_id)?, values.clone())?);
+            }
+        }
+        let mut private_messages = vec![];
+        for (type_id, values) in private_inputs.iter().enumerate() {
+            if !values.is_empty() {
+                private_messages
+                    .push(self.private_message(u8::try_from(type_id)?, values.clone())?);
+            }
+        }
+        let out_ids = self.predict_complex_outputs(&gate)?;
+        let checked_gate = gate.clone().with_output(out_ids);
+        self.check_gate(&checked_gate, public_messages, private_messages)?;
+        self.builder
+            .create_complex_gate(gate, public_inputs, private_inputs)
+    }
+}
+
+#[test]
+fn test_validating_builder() -> Result<()> {
+    use crate::producers::builder::BuildGate::*;
+    use crate::producers::sink::MemorySink;
+
+    let mut b =
+        ValidatingGateBuilder::new(MemorySink::default(), &[], &[Type::Field(vec![101])], &[]);
+    let x = b.create_gate(Private(0, Some(vec![3])))?;
+    let y = b.create_gate(Public(0, Some(vec![4])))?;
+    let sum = b.create_gate(Add(0, x, y))?;
+    let square = b.create_gate(Mul(0, sum, sum))?;
+    b.create_gate(AddConstant(0, square, vec![52]))?;
+    assert_eq!(b.violations(), Vec::<String>::new());
+
+    // The input wire 42 is not defined
+    let err = b.create_gate(Add(0, x, 42)).unwrap_err();
+    assert!(err.to_string().contains("42"));
+    assert_eq!(b.violations().len(), 1);
+
+    // The builder is still usable after a rejected gate
+    let z = b.create_gate(Private(0, Some(vec![5])))?;
+    b.create_gate(Mul(0, x, z))?;
+    assert_eq!(b.violations().len(), 1);
+
+    // The invalid gate has not been emitted
+    let source: crate::Source = b.finish().into();
+    let messages = source.read_all_messages()?;
+    assert_eq!(messages.relations[0].directives.len(), 7);
+    Ok(())
+}
@@ -34,6 +34,27 @@ impl Conversion {
         }
     }
 
+    /// Composes this conversion (from type A to type B) with `next` (from type B to type C)
+    /// into a conversion from type A to type C.
+    /// The output of `self` must match the input of `next` (same type and same number of wires).
+    pub fn compose(self, next: Conversion) -> Result<Conversion> {
+        if self.output_count.type_id != next.input_count.type_id {
+            return Err(format!(
+                "Cannot compose conversions: output type {} does not match input type {}.",
+                self.output_count.type_id, next.input_count.type_id
+            )
+            .into());
+        }
+        if self.output_count.count != next.input_count.count {
+            return Err(format!(
+                "Cannot compose conversions: {} output wires do not match {} input wires.",
+                self.output_count.count, next.input_count.count
+            )
+            .into());
+        }
+        Ok(Conversion::new(next.output_count, self.input_count))
+    }
+
     /// Serialize this structure into a Flatbuffer message
     pub fn build(&self) -> generated::Conversion {
         let g_output_count = self.output_count.build();
@@ -61,3 +82,21 @@ impl Conversion {
         builder.create_vector(&g_conversions)
     }
 }
+
+#[test]
+fn test_conversion_compose() {
+    let a_to_b = Conversion::new(Count::new(1, 1), Count::new(0, 2));
+    let b_to_c = Conversion::new(Count::new(2, 8), Count::new(1, 1));
+    assert_eq!(
+        a_to_b.clone().compose(b_to_c.clone()).unwrap(),
+        Conversion::new(Count::new(2, 8), Count::new(0, 2))
+    );
+
+    // Type mismatch
+    assert!(b_to_c.compose(a_to_b).is_err());
+    // Wire count mismatch
+    let b2_to_c = Conversion::new(Count::new(2, 8), Count::new(1, 2));
+    assert!(Conversion::new(Count::new(1, 1), Count::new(0, 2))
+        .compose(b2_to_c)
+        .is_err());
+}
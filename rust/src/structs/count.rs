@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::error::Error;
+use std::ops::Add;
 
 use crate::sieve_ir_generated::sieve_ir as generated;
 use crate::TypeId;
@@ -32,6 +33,22 @@ impl Count {
         Count { type_id, count }
     }
 
+    /// Returns a Count of zero wires of type `type_id`.
+    pub fn zero(type_id: TypeId) -> Self {
+        Count::new(type_id, 0)
+    }
+
+    /// Returns the number of wires of each type in `counts`
+    /// (see `count_list_to_hashmap`).
+    pub fn total(counts: &[Count]) -> BTreeMap<TypeId, u64> {
+        count_list_to_hashmap(counts)
+    }
+
+    /// Returns this Count with its number of wires multiplied by `factor`.
+    pub fn scale(&self, factor: u64) -> Count {
+        Count::new(self.type_id, self.count * factor)
+    }
+
     /// Serialize this structure into a Flatbuffer message
     pub fn build(&self) -> generated::Count {
         generated::Count::new(self.type_id, self.count)
@@ -55,6 +72,20 @@ impl Count {
     }
 }
 
+/// Adds the number of wires of two Counts of the same type.
+/// Panics if the types are different.
+impl Add for Count {
+    type Output = Count;
+
+    fn add(self, other: Count) -> Count {
+        assert_eq!(
+            self.type_id, other.type_id,
+            "Cannot add Counts of different types"
+        );
+        Count::new(self.type_id, self.count + other.count)
+    }
+}
+
 pub fn count_list_to_hashmap(count_list: &[Count]) -> BTreeMap<TypeId, u64> {
     let mut map = BTreeMap::new();
     count_list.iter().for_each(|count| {
@@ -71,3 +102,28 @@ fn test_count_list_to_hashmap() {
     let expected_result: BTreeMap<TypeId, u64> = BTreeMap::from([(0, 3), (1, 7)]);
     assert_eq!(result, expected_result);
 }
+
+#[test]
+fn test_count_arithmetic() {
+    let counts = vec![
+        Count::new(2, 4),
+        Count::new(0, 1),
+        Count::new(2, 6),
+        Count::zero(1),
+        Count::new(0, 2),
+    ];
+    let expected: BTreeMap<TypeId, u64> = BTreeMap::from([(0, 3), (1, 0), (2, 10)]);
+    assert_eq!(Count::total(&counts), expected);
+    assert_eq!(Count::total(&[]), BTreeMap::new());
+
+    assert_eq!(Count::new(3, 5).scale(4), Count::new(3, 20));
+    assert_eq!(Count::new(3, 5).scale(0), Count::zero(3));
+    assert_eq!(Count::new(1, 2) + Count::new(1, 3), Count::new(1, 5));
+    assert_eq!(Count::zero(1) + Count::new(1, 3), Count::new(1, 3));
+}
+
+#[test]
+#[should_panic]
+fn test_count_add_different_types() {
+    let _ = Count::new(0, 1) + Count::new(1, 1);
+}
@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::TryFrom;
 use std::error::Error;
 
@@ -8,7 +8,9 @@ use flatbuffers::{FlatBufferBuilder, ForwardsUOffset, Vector, WIPOffset};
 
 use crate::structs::count::Count;
 use crate::structs::plugin::PluginBody;
-use crate::{Gate, Result, TypeId};
+use crate::structs::value::remove_trailing_zeros;
+use crate::structs::wirerange::{add_types_to_wire_ranges, WireRange};
+use crate::{Gate, Result, TypeId, WireId};
 
 // ******************************
 //
@@ -21,6 +23,150 @@ pub enum FunctionBody {
     PluginBody(PluginBody),
 }
 
+/// A one-to-one mapping between the wires of two function bodies.
+#[derive(Default)]
+struct WireBijection {
+    left_to_right: BTreeMap<(TypeId, WireId), WireId>,
+    right_to_left: BTreeMap<(TypeId, WireId), WireId>,
+}
+
+impl WireBijection {
+    /// Maps the wire `left` to the wire `right` (both of type `type_id`).
+    /// Returns false if one of them is already mapped to another wire.
+    fn map(&mut self, type_id: TypeId, left: WireId, right: WireId) -> bool {
+        match (
+            self.left_to_right.get(&(type_id, left)),
+            self.right_to_left.get(&(type_id, right)),
+        ) {
+            (None, None) => {
+                self.left_to_right.insert((type_id, left), right);
+                self.right_to_left.insert((type_id, right), left);
+                true
+            }
+            (Some(mapped_right), Some(mapped_left)) => {
+                *mapped_right == right && *mapped_left == left
+            }
+            _ => false,
+        }
+    }
+
+    fn map_range(
+        &mut self,
+        type_id: TypeId,
+        left: (WireId, WireId),
+        right: (WireId, WireId),
+    ) -> bool {
+        left.1 >= left.0
+            && right.1 >= right.0
+            && left.1 - left.0 == right.1 - right.0
+            && (0..=left.1 - left.0).all(|i| self.map(type_id, left.0 + i, right.0 + i))
+    }
+
+    /// Maps the wire ranges of two Call gates, typed according to `counts`.
+    fn map_call_ranges(
+        &mut self,
+        left: &[WireRange],
+        right: &[WireRange],
+        counts: &[Count],
+    ) -> bool {
+        match (
+            add_types_to_wire_ranges(left, counts),
+            add_types_to_wire_ranges(right, counts),
+        ) {
+            (Ok(left), Ok(right)) => left.iter().zip(right.iter()).all(|(l, r)| {
+                self.map_range(l.type_id, (l.first_id, l.last_id), (r.first_id, r.last_id))
+            }),
+            _ => false,
+        }
+    }
+
+    /// Returns true if `left` and `right` are equal up to the renaming of wires,
+    /// and extends the mapping with the wires they use.
+    fn map_gates(
+        &mut self,
+        left: &Gate,
+        right: &Gate,
+        known_functions: &HashMap<String, FunctionCounts>,
+    ) -> bool {
+        use Gate::*;
+        let same_value = |l: &[u8], r: &[u8]| remove_trailing_zeros(l) == remove_trailing_zeros(r);
+        match (left, right) {
+            (Constant(lt, lo, lv), Constant(rt, ro, rv)) => {
+                lt == rt && same_value(lv, rv) && self.map(*lt, *lo, *ro)
+            }
+            (AssertZero(lt, li), AssertZero(rt, ri)) => lt == rt && self.map(*lt, *li, *ri),
+            (Copy(lt, lo, li), Copy(rt, ro, ri)) => {
+                lt == rt && self.map(*lt, *li, *ri) && self.map(*lt, *lo, *ro)
+            }
+            (Add(lt, lo, ll, lr), Add(rt, ro, rl, rr))
+            | (Mul(lt, lo, ll, lr), Mul(rt, ro, rl, rr)) => {
+                lt == rt
+                    && self.map(*lt, *ll, *rl)
+                    && self.map(*lt, *lr, *rr)
+                    && self.map(*lt, *lo, *ro)
+            }
+            (AddConstant(lt, lo, li, lv), AddConstant(rt, ro, ri, rv))
+            | (MulConstant(lt, lo, li, lv), MulConstant(rt, ro, ri, rv)) => {
+                lt == rt && same_value(lv, rv) && self.map(*lt, *li, *ri) && self.map(*lt, *lo, *ro)
+            }
+            (Public(lt, lo), Public(rt, ro)) | (Private(lt, lo), Private(rt, ro)) => {
+                lt == rt && self.map(*lt, *lo, *ro)
+            }
+            (New(lt, lf, ll), New(rt, rf, rl)) | (Delete(lt, lf, ll), Delete(rt, rf, rl)) => {
+                lt == rt && self.map_range(*lt, (*lf, *ll), (*rf, *rl))
+            }
+            (Convert(lot, lof, lol, lit, lif, lil), Convert(rot, rof, rol, rit, rif, ril)) => {
+                lot == rot
+                    && lit == rit
+                    && self.map_range(*lit, (*lif, *lil), (*rif, *ril))
+                    && self.map_range(*lot, (*lof, *lol), (*rof, *rol))
+            }
+            (Call(ln, lo, li), Call(rn, ro, ri)) => match known_functions.get(ln) {
+                Some(counts) if ln == rn => {
+                    self.map_call_ranges(li, ri, &counts.input_count)
+                        && self.map_call_ranges(lo, ro, &counts.output_count)
+                }
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+impl FunctionBody {
+    /// Returns true if both bodies perform the same computation: either their gates are equal
+    /// one by one up to a one-to-one renaming of the wires, or they are the same plugin call.
+    /// `known_functions` gives the types of the wires of Call gates.
+    /// All wires are renamed, including the inputs and outputs of the function
+    /// (see `Function::is_semantically_equivalent` to keep them fixed).
+    pub fn is_semantically_equivalent(
+        self,
+        other: &FunctionBody,
+        known_functions: &HashMap<String, FunctionCounts>,
+    ) -> bool {
+        self.equivalent_with(other, known_functions, WireBijection::default())
+    }
+
+    fn equivalent_with(
+        &self,
+        other: &FunctionBody,
+        known_functions: &HashMap<String, FunctionCounts>,
+        mut bijection: WireBijection,
+    ) -> bool {
+        match (self, other) {
+            (FunctionBody::Gates(left), FunctionBody::Gates(right)) => {
+                left.len() == right.len()
+                    && left
+                        .iter()
+                        .zip(right.iter())
+                        .all(|(l, r)| bijection.map_gates(l, r, known_functions))
+            }
+            (FunctionBody::PluginBody(left), FunctionBody::PluginBody(right)) => left == right,
+            _ => false,
+        }
+    }
+}
+
 // ******************************
 //
 //   Function
@@ -150,10 +296,36 @@ impl Function {
         let g_functions: Vec<_> = functions.iter().map(|gate| gate.build(builder)).collect();
         builder.create_vector(&g_functions)
     }
+
+    /// Returns true if both functions have the same signature and semantically equivalent
+    /// bodies (see `FunctionBody::is_semantically_equivalent`), their input and output wires
+    /// being kept fixed. The names of the functions are not compared.
+    pub fn is_semantically_equivalent(
+        &self,
+        other: &Function,
+        known_functions: &HashMap<String, FunctionCounts>,
+    ) -> bool {
+        if self.output_count != other.output_count || self.input_count != other.input_count {
+            return false;
+        }
+        // Input and output wires are numbered from 0 for each type, outputs first.
+        let mut bijection = WireBijection::default();
+        let mut wire_counts: BTreeMap<TypeId, u64> = BTreeMap::new();
+        for count in self.output_count.iter().chain(self.input_count.iter()) {
+            *wire_counts.entry(count.type_id).or_insert(0) += count.count;
+        }
+        for (type_id, count) in wire_counts {
+            for wire_id in 0..count {
+                bijection.map(type_id, wire_id, wire_id);
+            }
+        }
+        self.body
+            .equivalent_with(&other.body, known_functions, bijection)
+    }
 }
 
 /// FunctionCounts contains the number of inputs, outputs, public/private inputs of a function.
-#[derive(Clone, Eq, PartialEq, PartialOrd, Ord)]
+#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
 pub struct FunctionCounts {
     pub input_count: Vec<Count>,
     pub output_count: Vec<Count>,
@@ -220,4 +392,199 @@ impl FunctionCounts {
         }
         Ok(())
     }
+
+    /// Combines the counts of two functions called one after the other:
+    /// public and private input counts are summed per type,
+    /// and input and output counts are concatenated (`self` first).
+    pub fn merge_sequential(mut self, other: FunctionCounts) -> FunctionCounts {
+        self.input_count.extend(other.input_count);
+        self.output_count.extend(other.output_count);
+        for (type_id, count) in other.public_count {
+            *self.public_count.entry(type_id).or_insert(0) += count;
+        }
+        for (type_id, count) in other.private_count {
+            *self.private_count.entry(type_id).or_insert(0) += count;
+        }
+        self
+    }
+
+    /// Returns the distinct type ids of the inputs and outputs, in increasing order
+    /// (i.e. the type ids referenced by a Call gate to this function).
+    pub fn type_ids(&self) -> Vec<TypeId> {
+        self.output_count
+            .iter()
+            .chain(self.input_count.iter())
+            .map(|count| count.type_id)
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Returns true if all counts are zero.
+    pub fn is_empty(&self) -> bool {
+        self.input_count.iter().all(|count| count.count == 0)
+            && self.output_count.iter().all(|count| count.count == 0)
+            && self.public_count.values().all(|count| *count == 0)
+            && self.private_count.values().all(|count| *count == 0)
+    }
+}
+
+#[test]
+fn test_function_counts_merge_sequential() {
+    let empty = FunctionCounts {
+        input_count: vec![],
+        output_count: vec![],
+        public_count: BTreeMap::new(),
+        private_count: BTreeMap::new(),
+    };
+    assert!(empty.is_empty());
+
+    let g = FunctionCounts {
+        input_count: vec![Count::new(0, 2)],
+        output_count: vec![Count::new(0, 1)],
+        public_count: BTreeMap::from([(0, 1)]),
+        private_count: BTreeMap::new(),
+    };
+    let h = FunctionCounts {
+        input_count: vec![Count::new(1, 1)],
+        output_count: vec![Count::new(1, 3)],
+        public_count: BTreeMap::from([(0, 2)]),
+        private_count: BTreeMap::from([(1, 4)]),
+    };
+    assert!(!g.is_empty());
+
+    let merged = g.merge_sequential(h);
+    assert_eq!(
+        merged,
+        FunctionCounts {
+            input_count: vec![Count::new(0, 2), Count::new(1, 1)],
+            output_count: vec![Count::new(0, 1), Count::new(1, 3)],
+            public_count: BTreeMap::from([(0, 3)]),
+            private_count: BTreeMap::from([(1, 4)]),
+        }
+    );
+    assert_eq!(empty.merge_sequential(merged.clone()), merged);
+}
+
+#[test]
+fn test_function_counts_type_ids() {
+    let counts = FunctionCounts {
+        input_count: vec![Count::new(3, 1), Count::new(0, 2)],
+        output_count: vec![Count::new(3, 1)],
+        public_count: BTreeMap::from([(1, 1)]),
+        private_count: BTreeMap::new(),
+    };
+    assert_eq!(counts.type_ids(), vec![0, 3]);
+}
+
+#[test]
+fn test_function_body_equivalence() {
+    use crate::Gate::*;
+
+    // f(x, y) = x * y + 5 (out = 0, inputs = 1 and 2), with internal wires from 3 or 10
+    let gates = |start: WireId| {
+        vec![
+            Mul(0, start, 1, 2),
+            Constant(0, start + 1, vec![5]),
+            Add(0, 0, start, start + 1),
+        ]
+    };
+    let counts = (vec![Count::new(0, 1)], vec![Count::new(0, 2)]);
+    let f = Function::new(
+        "f".to_string(),
+        counts.0.clone(),
+        counts.1.clone(),
+        FunctionBody::Gates(gates(3)),
+    );
+    let g = Function::new(
+        "g".to_string(),
+        counts.0.clone(),
+        counts.1.clone(),
+        FunctionBody::Gates(gates(10)),
+    );
+    let known_functions = HashMap::new();
+    assert!(f
+        .body
+        .clone()
+        .is_semantically_equivalent(&g.body, &known_functions));
+    assert!(f.is_semantically_equivalent(&g, &known_functions));
+
+    // Same computation with the inputs swapped in the Mul gate
+    let swapped = FunctionBody::Gates(vec![
+        Mul(0, 3, 2, 1),
+        Constant(0, 4, vec![5]),
+        Add(0, 0, 3, 4),
+    ]);
+    let h = Function::new("h".to_string(), counts.0.clone(), counts.1, swapped.clone());
+    // Equivalent up to the renaming of the inputs, but not as functions
+    assert!(f
+        .body
+        .clone()
+        .is_semantically_equivalent(&swapped, &known_functions));
+    assert!(!f.is_semantically_equivalent(&h, &known_functions));
+
+    // A wire cannot be mapped to two different wires
+    let reused = FunctionBody::Gates(vec![
+        Mul(0, 3, 1, 1),
+        Constant(0, 4, vec![5]),
+        Add(0, 0, 3, 4),
+    ]);
+    assert!(!f
+        .body
+        .clone()
+        .is_semantically_equivalent(&reused, &known_functions));
+    assert!(!reused.is_semantically_equivalent(&f.body, &known_functions));
+
+    let other_constant = FunctionBody::Gates(vec![
+        Mul(0, 3, 1, 2),
+        Constant(0, 4, vec![6]),
+        Add(0, 0, 3, 4),
+    ]);
+    assert!(!f
+        .body
+        .clone()
+        .is_semantically_equivalent(&other_constant, &known_functions));
+    let add_instead_of_mul = FunctionBody::Gates(vec![
+        Add(0, 3, 1, 2),
+        Constant(0, 4, vec![5]),
+        Add(0, 0, 3, 4),
+    ]);
+    assert!(!f
+        .body
+        .clone()
+        .is_semantically_equivalent(&add_instead_of_mul, &known_functions));
+
+    // Calls are compared with the types of their wires
+    let known_functions = HashMap::from([(
+        "f".to_string(),
+        FunctionCounts {
+            input_count: vec![Count::new(0, 2)],
+            output_count: vec![Count::new(0, 1)],
+            public_count: BTreeMap::new(),
+            private_count: BTreeMap::new(),
+        },
+    )]);
+    let call = |out: WireId, first: WireId| {
+        FunctionBody::Gates(vec![Call(
+            "f".to_string(),
+            vec![WireRange::new(out, out)],
+            vec![WireRange::new(first, first + 1)],
+        )])
+    };
+    assert!(call(0, 1).is_semantically_equivalent(&call(7, 3), &known_functions));
+    assert!(!call(0, 1).is_semantically_equivalent(&call(7, 3), &HashMap::new()));
+
+    // Plugin bodies are equivalent iff they are equal
+    let plugin = |operation: &str| {
+        FunctionBody::PluginBody(PluginBody {
+            name: "zkif_vector".to_string(),
+            operation: operation.to_string(),
+            params: vec!["0".to_string(), "2".to_string()],
+            public_count: BTreeMap::new(),
+            private_count: BTreeMap::new(),
+        })
+    };
+    assert!(plugin("add").is_semantically_equivalent(&plugin("add"), &known_functions));
+    assert!(!plugin("add").is_semantically_equivalent(&plugin("mul"), &known_functions));
+    assert!(!plugin("add").is_semantically_equivalent(&f.body, &known_functions));
 }
@@ -1,7 +1,7 @@
 use crate::Result;
 use flatbuffers::{FlatBufferBuilder, ForwardsUOffset, Vector, WIPOffset};
 use serde::{Deserialize, Serialize};
-use std::collections::{BTreeMap, BTreeSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::TryFrom;
 use std::error::Error;
 
@@ -443,6 +443,123 @@ impl Gate {
         builder.create_vector(&g_gates)
     }
 
+    /// Returns the distinct type ids referenced by this gate, in increasing order
+    /// (i.e. one type id except for Convert gates converting between two types).
+    /// The wire ranges of a Call gate are not typed: their types are given by the declaration
+    /// of the called function, so an empty vector is returned (see `FunctionCounts::type_ids`).
+    pub fn type_ids(&self) -> Vec<TypeId> {
+        match self {
+            Constant(type_id, _, _)
+            | AssertZero(type_id, _)
+            | Copy(type_id, _, _)
+            | Add(type_id, _, _, _)
+            | Mul(type_id, _, _, _)
+            | AddConstant(type_id, _, _, _)
+            | MulConstant(type_id, _, _, _)
+            | Public(type_id, _)
+            | Private(type_id, _)
+            | New(type_id, _, _)
+            | Delete(type_id, _, _) => vec![*type_id],
+            Convert(out_type_id, _, _, in_type_id, _, _) => {
+                let mut type_ids = vec![*out_type_id, *in_type_id];
+                type_ids.sort_unstable();
+                type_ids.dedup();
+                type_ids
+            }
+            Call(_, _, _) => vec![],
+        }
+    }
+
+    /// Returns this gate where each wire `(type_id, wire_id)` found in `map` is replaced by
+    /// `map[(type_id, wire_id)]`, and other wires are kept.
+    /// The wire ranges of a Call gate are not typed, so a wire of a Call gate is replaced by
+    /// `map[(type_id, wire_id)]` for any `type_id` found in `map`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a wire range (in New, Delete, Convert and Call gates) is not mapped to a
+    /// contiguous range in the same order, or if a wire of a Call gate is mapped to different
+    /// wires depending on its type.
+    pub fn substitute_wires(self, map: &HashMap<(TypeId, WireId), WireId>) -> Gate {
+        let wire =
+            |type_id: TypeId, wire_id: WireId| *map.get(&(type_id, wire_id)).unwrap_or(&wire_id);
+        let untyped_wire = |wire_id: WireId| {
+            let mut new_wire_ids =
+                (0..=TypeId::MAX).filter_map(|type_id| map.get(&(type_id, wire_id)));
+            let new_wire_id = *new_wire_ids.next().unwrap_or(&wire_id);
+            assert!(
+                new_wire_ids.all(|other| *other == new_wire_id),
+                "The wire {} of a Call gate is mapped to different wires depending on its type.",
+                wire_id
+            );
+            new_wire_id
+        };
+        match self {
+            Constant(type_id, out, value) => Constant(type_id, wire(type_id, out), value),
+            AssertZero(type_id, input) => AssertZero(type_id, wire(type_id, input)),
+            Copy(type_id, out, input) => Copy(type_id, wire(type_id, out), wire(type_id, input)),
+            Add(type_id, out, left, right) => Add(
+                type_id,
+                wire(type_id, out),
+                wire(type_id, left),
+                wire(type_id, right),
+            ),
+            Mul(type_id, out, left, right) => Mul(
+                type_id,
+                wire(type_id, out),
+                wire(type_id, left),
+                wire(type_id, right),
+            ),
+            AddConstant(type_id, out, input, value) => {
+                AddConstant(type_id, wire(type_id, out), wire(type_id, input), value)
+            }
+            MulConstant(type_id, out, input, value) => {
+                MulConstant(type_id, wire(type_id, out), wire(type_id, input), value)
+            }
+            Public(type_id, out) => Public(type_id, wire(type_id, out)),
+            Private(type_id, out) => Private(type_id, wire(type_id, out)),
+            New(type_id, first, last) => {
+                let range = substitute_wire_range(WireRange::new(first, last), |wire_id| {
+                    wire(type_id, wire_id)
+                });
+                New(type_id, range.first_id, range.last_id)
+            }
+            Delete(type_id, first, last) => {
+                let range = substitute_wire_range(WireRange::new(first, last), |wire_id| {
+                    wire(type_id, wire_id)
+                });
+                Delete(type_id, range.first_id, range.last_id)
+            }
+            Convert(out_type_id, out_first, out_last, in_type_id, in_first, in_last) => {
+                let out_range =
+                    substitute_wire_range(WireRange::new(out_first, out_last), |wire_id| {
+                        wire(out_type_id, wire_id)
+                    });
+                let in_range =
+                    substitute_wire_range(WireRange::new(in_first, in_last), |wire_id| {
+                        wire(in_type_id, wire_id)
+                    });
+                Convert(
+                    out_type_id,
+                    out_range.first_id,
+                    out_range.last_id,
+                    in_type_id,
+                    in_range.first_id,
+                    in_range.last_id,
+                )
+            }
+            Call(name, out_ids, in_ids) => {
+                let substitute_ranges = |ranges: Vec<WireRange>| {
+                    ranges
+                        .into_iter()
+                        .map(|range| substitute_wire_range(range, untyped_wire))
+                        .collect::<Vec<_>>()
+                };
+                Call(name, substitute_ranges(out_ids), substitute_ranges(in_ids))
+            }
+        }
+    }
+
     /// Returns the output wire id if exists.
     /// if not, returns None
     fn _get_output_wire_id(&self) -> Option<WireId> {
@@ -466,6 +583,26 @@ impl Gate {
     }
 }
 
+/// Returns the range of the wires `wire(wire_id)` for `wire_id` in `range`.
+///
+/// # Panics
+///
+/// Panics if the wires of `range` are not mapped to a contiguous range in the same order.
+fn substitute_wire_range(range: WireRange, wire: impl Fn(WireId) -> WireId) -> WireRange {
+    let new_first_id = wire(range.first_id);
+    assert!(
+        (range.first_id..=range.last_id)
+            .all(|wire_id| wire(wire_id) == new_first_id + (wire_id - range.first_id)),
+        "The wire range {}..={} is not mapped to a contiguous wire range.",
+        range.first_id,
+        range.last_id
+    );
+    WireRange::new(
+        new_first_id,
+        new_first_id + (range.last_id - range.first_id),
+    )
+}
+
 /// replace_output_wires goes through all gates in `gates` and replace `output_wires[i]` by `i`.
 /// If `output_wires[i]` belongs to a wire range (in New, Call, Convert gates),
 /// add `Copy(i, output_wires[i])` at the end of gates and do not modify other gates containing `output_wires[i]`.
@@ -707,3 +844,75 @@ fn test_replace_wire_id() {
     replace_wire_id(&0, &1, &mut wire, 8, 10);
     assert_eq!(wire, 8);
 }
+
+#[test]
+fn test_gate_type_ids() {
+    let single_type_gates = vec![
+        Constant(1, 0, vec![1]),
+        AssertZero(1, 0),
+        Copy(1, 1, 0),
+        Add(1, 2, 0, 1),
+        Mul(1, 3, 0, 1),
+        AddConstant(1, 4, 0, vec![2]),
+        MulConstant(1, 5, 0, vec![2]),
+        Public(1, 6),
+        Private(1, 7),
+        New(1, 8, 10),
+        Delete(1, 0, 10),
+        Convert(1, 11, 11, 1, 0, 1),
+    ];
+    for gate in single_type_gates.iter() {
+        assert_eq!(gate.type_ids(), vec![1]);
+    }
+    assert_eq!(Convert(2, 0, 0, 0, 0, 3).type_ids(), vec![0, 2]);
+
+    let call = Call(
+        "f".to_string(),
+        vec![WireRange::new(0, 0)],
+        vec![WireRange::new(1, 1), WireRange::new(2, 3)],
+    );
+    assert!(call.type_ids().is_empty());
+}
+
+#[test]
+fn test_gate_substitute_wires() {
+    let map = HashMap::from([((0, 1), 11), ((0, 2), 12), ((0, 3), 13), ((1, 1), 21)]);
+    assert_eq!(Add(0, 5, 1, 4).substitute_wires(&map), Add(0, 5, 11, 4));
+    assert_eq!(Mul(1, 1, 1, 2).substitute_wires(&map), Mul(1, 21, 21, 2));
+    assert_eq!(
+        Convert(1, 1, 1, 0, 1, 3).substitute_wires(&map),
+        Convert(1, 21, 21, 0, 11, 13)
+    );
+    assert_eq!(Delete(0, 5, 8).substitute_wires(&map), Delete(0, 5, 8));
+
+    let map = HashMap::from([((0, 1), 11), ((0, 2), 12), ((0, 3), 13), ((1, 4), 14)]);
+    assert_eq!(
+        Call(
+            "f".to_string(),
+            vec![WireRange::new(4, 4)],
+            vec![WireRange::new(1, 3), WireRange::new(5, 6)],
+        )
+        .substitute_wires(&map),
+        Call(
+            "f".to_string(),
+            vec![WireRange::new(14, 14)],
+            vec![WireRange::new(11, 13), WireRange::new(5, 6)],
+        )
+    );
+}
+
+#[test]
+#[should_panic]
+fn test_gate_substitute_wires_non_contiguous_range() {
+    // The range 0..=2 is only partially mapped
+    let map = HashMap::from([((0, 1), 11), ((0, 2), 12)]);
+    New(0, 0, 2).substitute_wires(&map);
+}
+
+#[test]
+#[should_panic]
+fn test_gate_substitute_wires_ambiguous_call_wire() {
+    // The untyped wire 1 of the Call gate is mapped to 11 or 21 depending on its type
+    let map = HashMap::from([((0, 1), 11), ((1, 1), 21)]);
+    Call("f".to_string(), vec![WireRange::new(1, 1)], vec![]).substitute_wires(&map);
+}
@@ -19,6 +19,9 @@ pub mod gates;
 /// Value holds a value assigned to a wire.
 pub mod value;
 
+/// Modular arithmetic on Values.
+pub mod value_arithmetic;
+
 /// WireRange are used to defined a range of wires
 pub mod wirerange;
 
@@ -7,6 +7,7 @@ use crate::sieve_ir_generated::sieve_ir as generated;
 use flatbuffers::{FlatBufferBuilder, WIPOffset};
 use itertools::Itertools;
 
+use crate::plugins::evaluate_plugin::extract_number;
 use crate::structs::count::{count_list_to_hashmap, Count};
 use crate::{Result, TypeId};
 
@@ -82,6 +83,37 @@ impl PluginBody {
         }
     }
 
+    /// Checks that `name` and `operation` are not empty and that all `params` are positive
+    /// numbers (decimal or hexadecimal with the prefix `0x`).
+    /// Plugin specific checks are performed by a `PluginValidator`.
+    pub fn validate(&self) -> Result<()> {
+        self.validate_name_and_operation()?;
+        for param in self.params.iter() {
+            match extract_number(param) {
+                Ok(number) if number > 0 => {}
+                _ => {
+                    return Err(format!(
+                        "plugin({}, {}): param '{}' is not a positive number.",
+                        self.name, self.operation, param
+                    )
+                    .into())
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that `name` and `operation` are not empty.
+    pub(crate) fn validate_name_and_operation(&self) -> Result<()> {
+        if self.name.is_empty() {
+            return Err("A plugin name cannot be empty.".into());
+        }
+        if self.operation.is_empty() {
+            return Err(format!("The operation of plugin {} cannot be empty.", self.name).into());
+        }
+        Ok(())
+    }
+
     /// Serialize this structure into a Flatbuffer message
     pub fn build<'a>(
         &self,
@@ -1,13 +1,19 @@
-use crate::Result;
-use flatbuffers::{FlatBufferBuilder, WIPOffset};
+use crate::{Gate, Result, TypeId, WireId};
+use flatbuffers::{FlatBufferBuilder, ForwardsUOffset, Vector, WIPOffset};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
 use std::convert::TryFrom;
 use std::error::Error;
 use std::io::Write;
+use std::mem::take;
 
+use crate::consumers::analysis::{
+    called_functions, detect_recursion, gate_output_wires, same_type,
+};
 use crate::sieve_ir_generated::sieve_ir as generated;
 use crate::structs::conversion::Conversion;
 use crate::structs::directives::Directive;
+use crate::structs::function::{Function, FunctionBody};
 use crate::structs::types::Type;
 
 #[derive(Clone, Default, Debug, Eq, PartialEq, Deserialize, Serialize)]
@@ -57,6 +63,44 @@ impl<'a> TryFrom<&'a [u8]> for Relation {
     }
 }
 
+/// Returns the Flatbuffers vector of directives of the Relation message in `buffer`,
+/// without copying it.
+pub(crate) fn relation_directives(
+    buffer: &[u8],
+) -> Result<Vector<'_, ForwardsUOffset<generated::Directive<'_>>>> {
+    let g_relation = generated::get_size_prefixed_root_as_root(buffer)
+        .message_as_relation()
+        .ok_or("Not a Relation message.")?;
+    Ok(g_relation.directives().ok_or("Missing directives")?)
+}
+
+/// Iterates lazily over the directives of the Relation message in `buffer`.
+/// Each directive is converted into an owned structure only when it is reached,
+/// so the whole `Vec<Directive>` is never allocated.
+pub fn directives_from_flatbuffer(buffer: &[u8]) -> impl Iterator<Item = Result<Directive>> + '_ {
+    let (g_directives, error) = match relation_directives(buffer) {
+        Ok(g_directives) => (Some(g_directives), None),
+        Err(err) => (None, Some(Err(err))),
+    };
+    let len = g_directives
+        .as_ref()
+        .map_or(0, |g_directives| g_directives.len());
+    error.into_iter().chain((0..len).map(move |i| {
+        // g_directives is Some whenever len > 0
+        Directive::try_from(g_directives.as_ref().unwrap().get(i))
+    }))
+}
+
+/// Iterates lazily over the top-level gates of the Relation message in `buffer`
+/// (see `directives_from_flatbuffer`). Function declarations are skipped.
+pub fn gates_from_flatbuffer(buffer: &[u8]) -> impl Iterator<Item = Result<Gate>> + '_ {
+    directives_from_flatbuffer(buffer).filter_map(|directive| match directive {
+        Ok(Directive::Gate(gate)) => Some(Ok(gate)),
+        Ok(Directive::Function(_)) => None,
+        Err(err) => Some(Err(err)),
+    })
+}
+
 impl Relation {
     /// Add this structure into a Flatbuffers message builder.
     pub fn build<'a>(&self, builder: &mut FlatBufferBuilder<'a>) -> WIPOffset<generated::Root<'a>> {
@@ -91,6 +135,149 @@ impl Relation {
         )
     }
 
+    /// Returns the function declared with the name `name`, if any.
+    pub fn extract_function(&self, name: &str) -> Option<&Function> {
+        self.directives
+            .iter()
+            .find_map(|directive| match directive {
+                Directive::Function(function) if function.name == name => Some(function),
+                _ => None,
+            })
+    }
+
+    /// Same as `extract_function`, but returns a copy of the function.
+    pub fn extract_function_owned(&self, name: &str) -> Option<Function> {
+        self.extract_function(name).cloned()
+    }
+
+    /// Returns the names of all declared functions, in declaration order.
+    pub fn function_names(&self) -> Vec<&str> {
+        self.directives
+            .iter()
+            .filter_map(|directive| match directive {
+                Directive::Function(function) => Some(function.name.as_str()),
+                Directive::Gate(_) => None,
+            })
+            .collect()
+    }
+
+    /// Inserts gates into the directives of this relation, e.g. to add debugging assertions.
+    /// Each gate is inserted before the directive at index `position` in the original
+    /// directives (or at the end if `position` is the number of directives).
+    /// Gates inserted at the same position keep their relative order.
+    /// Returns an error if a position is out of bounds, or if an inserted gate sets a wire
+    /// which is already set by a gate of the relation (or by another inserted gate).
+    pub fn with_gate_insertions(mut self, mut insertions: Vec<(usize, Gate)>) -> Result<Relation> {
+        // The sort is stable, so gates inserted at the same position keep their order.
+        insertions.sort_by_key(|(position, _)| *position);
+
+        // wire_id => types of the wires set with this id
+        let mut set_wires: BTreeMap<WireId, Vec<Option<TypeId>>> = BTreeMap::new();
+        for directive in self.directives.iter() {
+            if let Directive::Gate(gate) = directive {
+                for (type_id, wire_id) in gate_output_wires(gate) {
+                    set_wires.entry(wire_id).or_default().push(type_id);
+                }
+            }
+        }
+        for (position, gate) in insertions.iter() {
+            if *position > self.directives.len() {
+                return Err(format!(
+                    "Cannot insert a gate at position {}, the relation has only {} directives.",
+                    position,
+                    self.directives.len()
+                )
+                .into());
+            }
+            for (type_id, wire_id) in gate_output_wires(gate) {
+                let types = set_wires.entry(wire_id).or_default();
+                if types.iter().any(|other| same_type(*other, type_id)) {
+                    return Err(format!(
+                        "The inserted gate {:?} sets the wire {} which is already set.",
+                        gate, wire_id
+                    )
+                    .into());
+                }
+                types.push(type_id);
+            }
+        }
+
+        let mut insertions = insertions.into_iter().peekable();
+        let mut directives = Vec::with_capacity(self.directives.len() + insertions.len());
+        for (index, directive) in take(&mut self.directives).into_iter().enumerate() {
+            while let Some((_, gate)) = insertions.next_if(|(position, _)| *position == index) {
+                directives.push(Directive::Gate(gate));
+            }
+            directives.push(directive);
+        }
+        directives.extend(insertions.map(|(_, gate)| Directive::Gate(gate)));
+        self.directives = directives;
+        Ok(self)
+    }
+
+    /// Moves all function declarations before the gates, such that each function is declared
+    /// after the functions it calls (callee first). Functions without dependencies between them
+    /// keep their relative order, and the gates keep their order.
+    /// Returns an error if the functions call each other recursively.
+    pub fn reorder_functions_topologically(mut self) -> Result<Relation> {
+        let mut functions = vec![];
+        let mut gates = vec![];
+        for directive in take(&mut self.directives) {
+            match directive {
+                Directive::Function(function) => functions.push(function),
+                Directive::Gate(_) => gates.push(directive),
+            }
+        }
+        detect_recursion(&functions).map_err(|cycles| cycles.join("\n"))?;
+
+        let indices = functions
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(index, function)| (function.name.clone(), index))
+            .collect::<BTreeMap<_, _>>();
+        let callees = functions
+            .iter()
+            .map(|function| match &function.body {
+                FunctionBody::Gates(gates) => called_functions(gates)
+                    .iter()
+                    .filter_map(|name| indices.get(name).copied())
+                    .collect(),
+                FunctionBody::PluginBody(_) => vec![],
+            })
+            .collect::<Vec<Vec<usize>>>();
+
+        // Depth-first post-order traversal; there is no cycle, so it terminates.
+        fn visit(
+            index: usize,
+            callees: &[Vec<usize>],
+            visited: &mut BTreeSet<usize>,
+            order: &mut Vec<usize>,
+        ) {
+            if !visited.insert(index) {
+                return;
+            }
+            for callee in callees[index].iter() {
+                visit(*callee, callees, visited, order);
+            }
+            order.push(index);
+        }
+        let mut visited = BTreeSet::new();
+        let mut order = vec![];
+        for index in 0..functions.len() {
+            visit(index, &callees, &mut visited, &mut order);
+        }
+
+        let mut functions = functions.into_iter().map(Some).collect::<Vec<_>>();
+        self.directives = order
+            .into_iter()
+            .filter_map(|index| functions[index].take())
+            .map(Directive::Function)
+            .chain(gates)
+            .collect();
+        Ok(self)
+    }
+
     /// Writes this Relation as a Flatbuffers message into the provided buffer.
     ///
     /// # Examples
@@ -112,3 +299,227 @@ impl Relation {
         Ok(())
     }
 }
+
+#[test]
+fn test_relation_extract_function() {
+    use crate::producers::examples::example_relation;
+    use crate::structs::count::Count;
+    use crate::structs::function::FunctionBody;
+
+    let relation = example_relation();
+    assert_eq!(
+        relation.function_names(),
+        vec![
+            "assert_equal_private",
+            "square",
+            "vector_mul_7_2",
+            "ring_add",
+            "ring_mul",
+            "ring_equal"
+        ]
+    );
+
+    let square = relation.extract_function("square").unwrap();
+    assert_eq!(square.output_count, vec![Count::new(1, 1)]);
+    assert_eq!(square.input_count, vec![Count::new(1, 1)]);
+    match &square.body {
+        FunctionBody::Gates(gates) => assert_eq!(gates.len(), 1),
+        FunctionBody::PluginBody(_) => panic!("square is not a plugin function"),
+    }
+    assert_eq!(
+        relation.extract_function_owned("square").as_ref(),
+        Some(square)
+    );
+    assert!(relation.extract_function("unknown").is_none());
+}
+
+#[test]
+fn test_relation_with_gate_insertions() -> Result<()> {
+    use crate::consumers::evaluator::{Evaluator, PlaintextBackend};
+    use crate::producers::examples::*;
+    use crate::Message;
+
+    let evaluate = |relation: Relation| {
+        let mut messages = vec![];
+        messages.extend(
+            example_public_inputs()
+                .into_iter()
+                .map(Message::PublicInputs),
+        );
+        messages.extend(
+            example_private_inputs()
+                .into_iter()
+                .map(Message::PrivateInputs),
+        );
+        messages.push(Message::Relation(relation));
+        let evaluator = Evaluator::from_messages(
+            messages.into_iter().map(Ok),
+            &mut PlaintextBackend::default(),
+        );
+        evaluator.get_violations()
+    };
+    assert_eq!(evaluate(example_relation()), Vec::<String>::new());
+
+    // The public input (wire 0 of type 0) is 5, so asserting it is zero fails.
+    let relation = example_relation().with_gate_insertions(vec![(3, Gate::AssertZero(0, 0))])?;
+    assert_eq!(
+        relation.directives.len(),
+        example_relation().directives.len() + 1
+    );
+    assert_eq!(
+        relation.directives[3],
+        Directive::Gate(Gate::AssertZero(0, 0))
+    );
+    assert_eq!(relation.directives[4], example_relation().directives[3]);
+    assert_eq!(evaluate(relation).len(), 1);
+
+    // Inserted gates cannot set existing wires
+    assert!(example_relation()
+        .with_gate_insertions(vec![(3, Gate::Constant(0, 1, vec![1]))])
+        .is_err());
+    assert!(example_relation()
+        .with_gate_insertions(vec![
+            (2, Gate::Constant(0, 10, vec![1])),
+            (5, Gate::Constant(0, 10, vec![2])),
+        ])
+        .is_err());
+    // A new wire can be used by a later inserted gate
+    let relation = example_relation().with_gate_insertions(vec![
+        (4, Gate::AddConstant(0, 10, 0, vec![4])),
+        (2, Gate::Constant(0, 11, vec![1])),
+        (4, Gate::AssertZero(0, 10)),
+    ])?;
+    assert_eq!(
+        relation.directives[2],
+        Directive::Gate(Gate::Constant(0, 11, vec![1]))
+    );
+    assert_eq!(
+        relation.directives[5],
+        Directive::Gate(Gate::AddConstant(0, 10, 0, vec![4]))
+    );
+    assert_eq!(
+        relation.directives[6],
+        Directive::Gate(Gate::AssertZero(0, 10))
+    );
+
+    let len = example_relation().directives.len();
+    assert!(example_relation()
+        .with_gate_insertions(vec![(len + 1, Gate::AssertZero(0, 0))])
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn test_gates_from_flatbuffer() -> Result<()> {
+    use crate::producers::examples::{example_public_inputs, example_relation};
+    use crate::Message;
+
+    let relation = example_relation();
+    let mut buffer = vec![];
+    relation.write_into(&mut buffer)?;
+
+    let directives = directives_from_flatbuffer(&buffer).collect::<Result<Vec<_>>>()?;
+    assert_eq!(directives, relation.directives);
+
+    let gates = gates_from_flatbuffer(&buffer).collect::<Result<Vec<_>>>()?;
+    let expected_gates = relation
+        .directives
+        .iter()
+        .filter_map(|directive| match directive {
+            Directive::Gate(gate) => Some(gate.clone()),
+            Directive::Function(_) => None,
+        })
+        .collect::<Vec<_>>();
+    assert!(!expected_gates.is_empty());
+    assert_eq!(gates, expected_gates);
+
+    // Other messages are rejected
+    let mut buffer = vec![];
+    Message::PublicInputs(example_public_inputs()[0].clone()).write_into(&mut buffer)?;
+    let results = gates_from_flatbuffer(&buffer).collect::<Vec<_>>();
+    assert_eq!(results.len(), 1);
+    assert!(results[0].is_err());
+    Ok(())
+}
+
+#[test]
+fn test_reorder_functions_topologically() -> Result<()> {
+    use crate::consumers::validator::Validator;
+    use crate::structs::count::Count;
+    use crate::structs::wirerange::WireRange;
+    use crate::structs::IR_VERSION;
+    use crate::Message;
+
+    let double = Directive::Function(Function::new(
+        "double".to_string(),
+        vec![Count::new(0, 1)],
+        vec![Count::new(0, 1)],
+        FunctionBody::Gates(vec![Gate::Add(0, 0, 1, 1)]),
+    ));
+    let quadruple = Directive::Function(Function::new(
+        "quadruple".to_string(),
+        vec![Count::new(0, 1)],
+        vec![Count::new(0, 1)],
+        FunctionBody::Gates(vec![
+            Gate::Call(
+                "double".to_string(),
+                vec![WireRange::new(2, 2)],
+                vec![WireRange::new(1, 1)],
+            ),
+            Gate::Call(
+                "double".to_string(),
+                vec![WireRange::new(0, 0)],
+                vec![WireRange::new(2, 2)],
+            ),
+        ]),
+    ));
+    let gates = vec![
+        Directive::Gate(Gate::Constant(0, 0, vec![3])),
+        Directive::Gate(Gate::Call(
+            "quadruple".to_string(),
+            vec![WireRange::new(1, 1)],
+            vec![WireRange::new(0, 0)],
+        )),
+        Directive::Gate(Gate::AddConstant(0, 2, 1, vec![89])),
+        Directive::Gate(Gate::AssertZero(0, 2)),
+    ];
+    let new_relation = |directives: Vec<Directive>| Relation {
+        version: IR_VERSION.to_string(),
+        plugins: vec![],
+        types: vec![Type::Field(vec![101])],
+        conversions: vec![],
+        directives,
+    };
+    let validate = |relation: &Relation| {
+        let mut validator = Validator::new_as_verifier();
+        validator.ingest_message(&Message::Relation(relation.clone()));
+        validator.get_violations()
+    };
+
+    // `quadruple` is declared before `double`, and a gate comes first
+    let mut directives = vec![gates[0].clone(), quadruple.clone(), double.clone()];
+    directives.extend_from_slice(&gates[1..]);
+    let relation = new_relation(directives);
+    assert!(!validate(&relation).is_empty());
+
+    let reordered = relation.reorder_functions_topologically()?;
+    let mut expected = vec![double.clone(), quadruple.clone()];
+    expected.extend_from_slice(&gates);
+    assert_eq!(reordered.directives, expected);
+    assert_eq!(validate(&reordered), Vec::<String>::new());
+
+    // Recursive functions cannot be ordered
+    let recursive = Directive::Function(Function::new(
+        "double".to_string(),
+        vec![Count::new(0, 1)],
+        vec![Count::new(0, 1)],
+        FunctionBody::Gates(vec![Gate::Call(
+            "quadruple".to_string(),
+            vec![WireRange::new(0, 0)],
+            vec![WireRange::new(1, 1)],
+        )]),
+    ));
+    let relation = new_relation(vec![quadruple, recursive]);
+    assert!(relation.reorder_functions_topologically().is_err());
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+
+use crate::structs::value::{remove_trailing_zeros, value_to_biguint, Value};
+use crate::Result;
+
+// All functions below reduce their inputs modulo `modulus`, and return values
+// zero-padded to the length of `modulus` (without its trailing zeros).
+
+fn biguint_to_value(number: &BigUint, modulus: &Value) -> Value {
+    let mut value = number.to_bytes_le();
+    let len = remove_trailing_zeros(modulus).len();
+    if value.len() < len {
+        value.resize(len, 0);
+    }
+    value
+}
+
+/// Returns true if `value` is strictly smaller than `modulus`,
+/// i.e. if `value` is the canonical representation of a field element.
+pub fn value_lt_modulus(value: &[u8], modulus: &[u8]) -> bool {
+    value_to_biguint(value) < value_to_biguint(modulus)
+}
+
+/// Returns `a + b mod modulus`.
+pub fn value_add(a: &Value, b: &Value, modulus: &Value) -> Value {
+    let modulo = value_to_biguint(modulus);
+    biguint_to_value(
+        &((value_to_biguint(a) + value_to_biguint(b)) % &modulo),
+        modulus,
+    )
+}
+
+/// Returns `a * b mod modulus`.
+pub fn value_mul(a: &Value, b: &Value, modulus: &Value) -> Value {
+    let modulo = value_to_biguint(modulus);
+    biguint_to_value(
+        &((value_to_biguint(a) * value_to_biguint(b)) % &modulo),
+        modulus,
+    )
+}
+
+/// Returns `-a mod modulus`.
+pub fn value_neg(a: &Value, modulus: &Value) -> Value {
+    let modulo = value_to_biguint(modulus);
+    let a = value_to_biguint(a) % &modulo;
+    biguint_to_value(&((&modulo - a) % &modulo), modulus)
+}
+
+/// Returns the inverse of `a` modulo `modulus`, computed as `a^(modulus-2)`.
+/// `modulus` must be prime. It returns an error if `a` is not invertible.
+pub fn value_inv(a: &Value, modulus: &Value) -> Result<Value> {
+    let modulo = value_to_biguint(modulus);
+    let a = value_to_biguint(a) % &modulo;
+    if a.is_zero() || modulo < BigUint::from(2u8) {
+        return Err("Zero has no inverse.".into());
+    }
+    let inverse = a.modpow(&(&modulo - 2u8), &modulo);
+    if !((&a * &inverse) % &modulo).is_one() {
+        return Err(format!("{} is not invertible modulo {}.", a, modulo).into());
+    }
+    Ok(biguint_to_value(&inverse, modulus))
+}
+
+/// Returns `base^exp mod modulus`.
+pub fn value_pow(base: &Value, exp: u64, modulus: &Value) -> Value {
+    let modulo = value_to_biguint(modulus);
+    biguint_to_value(
+        &value_to_biguint(base).modpow(&BigUint::from(exp), &modulo),
+        modulus,
+    )
+}
+
+#[test]
+fn test_value_arithmetic() -> Result<()> {
+    let p = vec![101];
+    let p_minus_one = vec![100];
+    assert_eq!(value_add(&p_minus_one, &vec![1], &p), vec![0]);
+    assert_eq!(value_add(&p_minus_one, &p_minus_one, &p), vec![99]);
+    assert_eq!(value_mul(&p_minus_one, &p_minus_one, &p), vec![1]);
+    assert_eq!(value_mul(&vec![], &p_minus_one, &p), vec![0]);
+    assert_eq!(value_neg(&vec![], &p), vec![0]);
+    assert_eq!(value_neg(&vec![1], &p), p_minus_one);
+    // Inputs are reduced modulo p
+    assert_eq!(value_neg(&vec![102], &p), p_minus_one);
+    assert_eq!(value_inv(&p_minus_one, &p)?, p_minus_one);
+    assert_eq!(value_inv(&vec![2], &p)?, vec![51]);
+    assert!(value_inv(&vec![0], &p).is_err());
+    assert!(value_inv(&p, &p).is_err());
+    assert_eq!(value_pow(&vec![3], 0, &p), vec![1]);
+    assert_eq!(value_pow(&vec![3], 100, &p), vec![1]);
+    assert_eq!(value_pow(&vec![0], 5, &p), vec![0]);
+
+    // 2^61 - 1: results are padded to 8 bytes
+    let p = vec![0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x1f];
+    let p_minus_one = vec![0xfe, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x1f];
+    assert_eq!(
+        value_add(&vec![1], &vec![2], &p),
+        vec![3, 0, 0, 0, 0, 0, 0, 0]
+    );
+    assert_eq!(value_add(&p_minus_one, &vec![1], &p), vec![0; 8]);
+    assert_eq!(value_neg(&vec![1], &p), p_minus_one);
+    assert_eq!(
+        value_mul(&p_minus_one, &p_minus_one, &p),
+        value_pow(&vec![1], 7, &p)
+    );
+    assert_eq!(value_inv(&p_minus_one, &p)?, p_minus_one);
+    let two_inv = value_inv(&vec![2], &p)?;
+    assert_eq!(
+        value_mul(&two_inv, &vec![2], &p),
+        vec![1, 0, 0, 0, 0, 0, 0, 0]
+    );
+    assert_eq!(value_pow(&vec![5], 1 << 61, &p), value_pow(&vec![5], 2, &p));
+    // Non-prime modulus
+    assert!(value_inv(&vec![2], &vec![4]).is_err());
+
+    assert!(value_lt_modulus(&p_minus_one, &p));
+    assert!(value_lt_modulus(&[], &p));
+    assert!(!value_lt_modulus(&p, &p));
+    assert!(!value_lt_modulus(&[0, 0, 0, 0, 0, 0, 0, 0, 1], &p));
+    assert!(value_lt_modulus(&[1, 0, 0, 0, 0, 0, 0, 0, 0], &p));
+    Ok(())
+}
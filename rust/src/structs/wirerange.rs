@@ -3,6 +3,7 @@ use crate::Result;
 use crate::{Count, TypeId, WireId};
 use flatbuffers::{FlatBufferBuilder, Vector, WIPOffset};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::error::Error;
 
@@ -154,3 +155,47 @@ fn test_check_wire_ranges_with_counts() {
     let counts = [Count::new(0, 3)];
     assert!(!check_wire_ranges_with_counts(&wire_ranges, &counts));
 }
+
+/// Returns the number of wires of each type in `wire_ranges`.
+/// It returns an error if a range is empty (`last_id < first_id`).
+pub fn wire_count_per_type(wire_ranges: &[WireRangeWithType]) -> Result<HashMap<TypeId, u64>> {
+    let mut map = HashMap::new();
+    for wire_range in wire_ranges.iter() {
+        if wire_range.last_id < wire_range.first_id {
+            return Err(format!(
+                "The wire range ({}: {}..={}) is empty",
+                wire_range.type_id, wire_range.first_id, wire_range.last_id
+            )
+            .into());
+        }
+        *map.entry(wire_range.type_id).or_insert(0_u64) +=
+            wire_range.last_id - wire_range.first_id + 1;
+    }
+    Ok(map)
+}
+
+/// Returns the number of wires in `wire_ranges`, whatever their type.
+pub fn total_wire_count(wire_ranges: &[WireRangeWithType]) -> Result<u64> {
+    Ok(wire_count_per_type(wire_ranges)?.values().sum())
+}
+
+#[test]
+fn test_wire_count_per_type() {
+    let wire_ranges = [
+        WireRangeWithType::new(2, 0, 4),
+        WireRangeWithType::new(0, 1, 1),
+        WireRangeWithType::new(1, 10, 12),
+        WireRangeWithType::new(2, 7, 8),
+        WireRangeWithType::new(0, 5, 6),
+    ];
+    let expected_result: HashMap<TypeId, u64> = HashMap::from([(0, 3), (1, 3), (2, 7)]);
+    assert_eq!(wire_count_per_type(&wire_ranges).unwrap(), expected_result);
+    assert_eq!(total_wire_count(&wire_ranges).unwrap(), 13);
+
+    assert_eq!(wire_count_per_type(&[]).unwrap(), HashMap::new());
+    assert_eq!(total_wire_count(&[]).unwrap(), 0);
+
+    let wire_ranges = [WireRangeWithType::new(0, 3, 2)];
+    assert!(wire_count_per_type(&wire_ranges).is_err());
+    assert!(total_wire_count(&wire_ranges).is_err());
+}
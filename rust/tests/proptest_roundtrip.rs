@@ -0,0 +1,118 @@
+//! Round-trip property: gates built through a GateBuilder are read back identically
+//! from the produced messages.
+
+use proptest::prelude::*;
+use zki_sieve::producers::build_gates::NO_OUTPUT;
+use zki_sieve::producers::builder::{BuildGate, GateBuilder, GateBuilderT};
+use zki_sieve::producers::sink::MemorySink;
+use zki_sieve::structs::directives::Directive;
+use zki_sieve::structs::types::Type;
+use zki_sieve::{Gate, Source, TypeId, WireId};
+
+/// A gate to build. Input wires are given as indices into the wires already allocated
+/// for the type of the gate, so they are always valid.
+#[derive(Clone, Debug)]
+enum GateSpec {
+    Constant(u8),
+    AssertZero(usize),
+    Copy(usize),
+    Add(usize, usize),
+    Mul(usize, usize),
+    AddConstant(usize, u8),
+    MulConstant(usize, u8),
+    Public(u8),
+    Private(u8),
+}
+
+fn gate_spec() -> impl Strategy<Value = GateSpec> {
+    prop_oneof![
+        any::<u8>().prop_map(GateSpec::Constant),
+        any::<usize>().prop_map(GateSpec::AssertZero),
+        any::<usize>().prop_map(GateSpec::Copy),
+        (any::<usize>(), any::<usize>()).prop_map(|(l, r)| GateSpec::Add(l, r)),
+        (any::<usize>(), any::<usize>()).prop_map(|(l, r)| GateSpec::Mul(l, r)),
+        (any::<usize>(), any::<u8>()).prop_map(|(w, v)| GateSpec::AddConstant(w, v)),
+        (any::<usize>(), any::<u8>()).prop_map(|(w, v)| GateSpec::MulConstant(w, v)),
+        any::<u8>().prop_map(GateSpec::Public),
+        any::<u8>().prop_map(GateSpec::Private),
+    ]
+}
+
+const MODULI: [u8; 2] = [101, 7];
+
+fn types() -> Vec<Type> {
+    MODULI
+        .iter()
+        .map(|&modulus| Type::Field(vec![modulus]))
+        .collect()
+}
+
+/// Converts `spec` into a BuildGate of type `type_id`, picking its inputs among `allocated`.
+/// Constants are reduced modulo the modulus of the type, since the builder rejects the others.
+fn build_gate(spec: &GateSpec, type_id: TypeId, allocated: &[WireId]) -> BuildGate {
+    let wire = |index: usize| allocated[index % allocated.len()];
+    let constant = |value: u8| vec![value % MODULI[type_id as usize]];
+    match *spec {
+        GateSpec::Constant(value) => BuildGate::Constant(type_id, constant(value)),
+        GateSpec::AssertZero(w) => BuildGate::AssertZero(type_id, wire(w)),
+        GateSpec::Copy(w) => BuildGate::Copy(type_id, wire(w)),
+        GateSpec::Add(l, r) => BuildGate::Add(type_id, wire(l), wire(r)),
+        GateSpec::Mul(l, r) => BuildGate::Mul(type_id, wire(l), wire(r)),
+        GateSpec::AddConstant(w, value) => {
+            BuildGate::AddConstant(type_id, wire(w), constant(value))
+        }
+        GateSpec::MulConstant(w, value) => {
+            BuildGate::MulConstant(type_id, wire(w), constant(value))
+        }
+        GateSpec::Public(value) => BuildGate::Public(type_id, Some(vec![value])),
+        GateSpec::Private(value) => BuildGate::Private(type_id, Some(vec![value])),
+    }
+}
+
+proptest! {
+    #[test]
+    fn gate_builder_round_trip(
+        specs in prop::collection::vec((any::<bool>(), gate_spec()), 0..200),
+        max_len in 1usize..50,
+    ) {
+        let types = types();
+        let mut b = GateBuilder::new(MemorySink::default(), &[], &types, &[]);
+        b.set_max_len(max_len).unwrap();
+
+        // Start with one wire of each type, so that all gates have possible inputs.
+        let mut allocated: Vec<Vec<WireId>> = vec![vec![], vec![]];
+        let mut expected: Vec<Gate> = vec![];
+        for type_id in 0..types.len() as TypeId {
+            let gate = BuildGate::Constant(type_id, vec![1]);
+            let out = b.create_gate(gate.clone()).unwrap();
+            allocated[type_id as usize].push(out);
+            expected.push(gate.with_output(out));
+        }
+        for (type_bit, spec) in specs.iter() {
+            let type_id = *type_bit as TypeId;
+            let gate = build_gate(spec, type_id, &allocated[type_id as usize]);
+            let has_output = gate.has_output();
+            let out = b.create_gate(gate.clone()).unwrap();
+            if has_output {
+                allocated[type_id as usize].push(out);
+            } else {
+                assert_eq!(out, NO_OUTPUT);
+            }
+            expected.push(gate.with_output(out));
+        }
+
+        let source: Source = b.finish().into();
+        let messages = source.read_all_messages().unwrap();
+        let gates = messages
+            .relations
+            .iter()
+            .flat_map(|relation| relation.directives.iter())
+            .map(|directive| match directive {
+                Directive::Gate(gate) => gate.clone(),
+                Directive::Function(function) => panic!("Unexpected function {}", function.name),
+            })
+            .collect::<Vec<_>>();
+        prop_assert_eq!(gates, expected);
+        prop_assert_eq!(&messages.relations[0].types, &types);
+    }
+}